@@ -1,5 +1,13 @@
-use clap::ValueEnum;
+use clap::{ValueEnum, ValueHint};
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::str::FromStr;
+use ufwlog::export::Export;
+use ufwlog::UfwLog;
 
+pub mod cef;
 pub mod csv;
 
 #[derive(Debug)]
@@ -20,18 +28,563 @@ impl Config {
             overwrite,
         }
     }
+
+    /// Resolve output file path, add extension if user doesn't give one.
+    ///
+    /// Return error if the path is invalid, or the file exists and user doesn't allow overwrite.
+    pub fn output_path(&self, extension: &str) -> Result<PathBuf, Box<dyn Error>> {
+        let mut path = PathBuf::from(&self.output_filename);
+        if path.file_name().is_none() {
+            return Err("Please specify a file name.".into());
+        }
+        if path.extension().is_none() {
+            path.set_extension(extension);
+        };
+        // if the file exists, return error
+        if path.exists() && !self.overwrite {
+            return Err(format!(
+                "The file {} is exist. Overwrite it with `--overwrite` flag.",
+                path.to_str().unwrap()
+            )
+            .into());
+        }
+        Ok(path)
+    }
+}
+
+/// Output file and format of export
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[command(flatten)]
+    options: FormatArgs,
+
+    /// Specify output path and filename.
+    #[arg(
+        short,
+        long = "output",
+        value_name = "filename",
+        value_hint = ValueHint::AnyPath,
+        default_value = "ufwlog"
+    )]
+    output_filename: Option<String>,
+
+    /// Overwrite the output file if it exists.
+    #[arg(long = "overwrite", default_value_t = false)]
+    overwrite: bool,
+}
+
+impl Args {
+    /// Get the config of output file
+    pub fn config(&self) -> Config {
+        Config::new(&self.output_filename, self.overwrite)
+    }
+
+    /// Export logs with selected format and options
+    pub fn export(
+        &self,
+        logs: impl Iterator<Item = Result<UfwLog, ufwlog::error::Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        match self.options.format() {
+            #[cfg(feature = "sqlite")]
+            ExportFormat::Sqlite => {
+                self.options.check()?;
+                export_sqlite(logs, self.config(), self.options.append, self.options.year)
+            }
+            _ => export(self.options.exporter()?.as_ref(), logs, self.config()),
+        }
+    }
+}
+
+/// Format and its options
+#[derive(clap::Args, Debug)]
+pub struct FormatArgs {
+    /// Which type to be export.
+    #[arg(default_value = "csv")]
+    format: Option<ExportFormat>,
+
+    /// Field delimiter of csv, use `\t` for TSV.
+    #[arg(long, default_value = ",", value_parser = csv::parse_delimiter)]
+    delimiter: char,
+
+    /// Don't write header row of csv.
+    #[arg(long = "no-header", default_value_t = false)]
+    no_header: bool,
+
+    /// Use `\r\n` as line terminator of csv.
+    #[arg(long, default_value_t = false)]
+    crlf: bool,
+
+    /// Columns to output and their order, rename column by `field=Header`,
+    /// e.g. `time,src=Source,dst,dpt,policy`. Default is all columns.
+    #[arg(long, value_name = "columns", value_parser = ufwlog::export::column::Columns::from_str)]
+    columns: Option<ufwlog::export::column::Columns>,
+
+    /// How TCP flags are rendered.
+    #[arg(long, value_enum, default_value = "combined")]
+    flags: FlagStyle,
+
+    /// Year of logs, which is used to make timestamp of ecs, cef, leef, gelf, influx, otlp,
+    /// splunk, zeek, pcapng, parquet, sqlite and xlsx.
+    /// Default is the current year, or last year if the date would be in the future.
+    #[arg(long)]
+    year: Option<i32>,
+
+    /// Device vendor of cef and leef.
+    #[arg(long, default_value = "ufw")]
+    vendor: String,
+
+    /// Device product of cef and leef.
+    #[arg(long, default_value = "ufwlog")]
+    product: String,
+
+    /// Severity of policy for cef and leef, e.g. `block=7,limit-block=9,allow=1`.
+    #[arg(long, value_name = "severity", value_delimiter = ',', value_parser = cef::parse_severity)]
+    severity: Vec<(ufwlog::UfwPolicy, u8)>,
+
+    /// Measurement name of influx.
+    #[arg(long, value_name = "name", default_value = "ufw")]
+    measurement: String,
+
+    /// Aggregate influx points per given seconds, fields are `count` of events and `bytes` of
+    /// packet length.
+    #[arg(long, value_name = "seconds", value_parser = clap::value_parser!(u32).range(1..))]
+    aggregate: Option<u32>,
+
+    /// `service.name` resource attribute of otlp.
+    #[arg(long = "service-name", value_name = "name", default_value = "ufw")]
+    service_name: String,
+
+    /// Sourcetype of splunk events.
+    #[arg(long, value_name = "sourcetype", default_value = "ufw")]
+    sourcetype: String,
+
+    /// Template of each line, e.g. `{time} {policy:<6} {src:>15} -> {dst}:{dpt|-}`. Use
+    /// `{field:>width}` to pad, `{field|default}` for absent value and
+    /// `{?field}...{/field}` (or `{!field}` for absent) for conditional section.
+    #[arg(long = "format", value_name = "template", required_if_eq("format", "template"), value_parser = ufwlog::export::template::Template::from_str)]
+    template: Option<ufwlog::export::template::Template>,
+
+    /// Compression codec of parquet.
+    #[cfg(feature = "parquet")]
+    #[arg(long, value_enum, default_value = "snappy")]
+    compression: Compression,
+
+    /// Maximum rows of a parquet row group.
+    #[cfg(feature = "parquet")]
+    #[arg(long = "row-group-size", value_name = "rows", default_value_t = 1024 * 1024, value_parser = clap::value_parser!(u64).range(1..))]
+    row_group_size: u64,
+
+    /// Insert into the existing sqlite database and skip records already in it, instead of
+    /// replacing the table.
+    #[cfg(feature = "sqlite")]
+    #[arg(long, default_value_t = false)]
+    append: bool,
+
+    /// Add a summary sheet of counts by policy and destination port to xlsx.
+    #[cfg(feature = "xlsx")]
+    #[arg(long, default_value_t = false)]
+    summary: bool,
+}
+
+impl FormatArgs {
+    /// Get the selected format
+    pub fn format(&self) -> ExportFormat {
+        self.format.clone().unwrap_or(ExportFormat::Csv)
+    }
+
+    /// Get the severity of each policy for cef and leef
+    fn severity(&self) -> ufwlog::export::cef::Severity {
+        let mut severity = ufwlog::export::cef::Severity::default();
+        for (policy, level) in &self.severity {
+            severity.set(*policy, *level);
+        }
+        severity
+    }
+
+    /// Return error if an option is given but doesn't apply to the selected format.
+    ///
+    /// An option is given if it differs from its default value.
+    pub fn check(&self) -> Result<(), Box<dyn Error>> {
+        use ExportFormat::*;
+        let format = self.format();
+        let timestamp_formats = [
+            Ecs,
+            Cef,
+            Leef,
+            Gelf,
+            Influx,
+            Otlp,
+            Splunk,
+            Zeek,
+            Pcapng,
+            #[cfg(feature = "parquet")]
+            Parquet,
+            #[cfg(feature = "sqlite")]
+            Sqlite,
+            #[cfg(feature = "xlsx")]
+            Xlsx,
+        ];
+
+        let options: &[(&str, bool, &[ExportFormat])] = &[
+            ("--delimiter", self.delimiter != ',', &[Csv]),
+            ("--no-header", self.no_header, &[Csv]),
+            ("--crlf", self.crlf, &[Csv]),
+            ("--columns", self.columns.is_some(), &[Csv]),
+            ("--flags", self.flags != FlagStyle::Combined, &[Csv]),
+            ("--year", self.year.is_some(), &timestamp_formats),
+            ("--vendor", self.vendor != "ufw", &[Cef, Leef]),
+            ("--product", self.product != "ufwlog", &[Cef, Leef]),
+            ("--severity", !self.severity.is_empty(), &[Cef, Leef]),
+            ("--measurement", self.measurement != "ufw", &[Influx]),
+            ("--aggregate", self.aggregate.is_some(), &[Influx]),
+            ("--service-name", self.service_name != "ufw", &[Otlp]),
+            ("--sourcetype", self.sourcetype != "ufw", &[Splunk]),
+            ("--format", self.template.is_some(), &[Template]),
+            #[cfg(feature = "parquet")]
+            (
+                "--compression",
+                self.compression != Compression::Snappy,
+                &[Parquet],
+            ),
+            #[cfg(feature = "parquet")]
+            (
+                "--row-group-size",
+                self.row_group_size != 1024 * 1024,
+                &[Parquet],
+            ),
+            #[cfg(feature = "sqlite")]
+            ("--append", self.append, &[Sqlite]),
+            #[cfg(feature = "xlsx")]
+            ("--summary", self.summary, &[Xlsx]),
+        ];
+
+        match options
+            .iter()
+            .find(|(_, given, formats)| *given && !formats.contains(&format))
+        {
+            Some((option, _, _)) => Err(format!(
+                "`{option}` doesn't apply to {} format.",
+                format.to_possible_value().unwrap().get_name()
+            )
+            .into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the exporter of selected stream format with options
+    ///
+    /// Return error if an option doesn't apply to the format, or the format isn't a stream
+    /// format (sqlite).
+    pub fn exporter(&self) -> Result<Box<dyn Export>, Box<dyn Error>> {
+        self.check()?;
+        Ok(match self.format() {
+            ExportFormat::Csv => Box::new(
+                ufwlog::export::csv::Exporter::new()
+                    .columns(
+                        self.columns
+                            .clone()
+                            .unwrap_or_default()
+                            .flag_style(self.flags.clone().into()),
+                    )
+                    .delimiter(self.delimiter)
+                    .has_header(!self.no_header)
+                    .terminator(if self.crlf {
+                        ufwlog::export::csv::Terminator::Crlf
+                    } else {
+                        ufwlog::export::csv::Terminator::Lf
+                    }),
+            ),
+            ExportFormat::Json => Box::new(ufwlog::export::json::Exporter),
+            ExportFormat::Ndjson => Box::new(ufwlog::export::ndjson::Exporter),
+            ExportFormat::Ecs => {
+                let mut exporter = ufwlog::export::ecs::Exporter::new();
+                if let Some(year) = self.year {
+                    exporter = exporter.year(year);
+                }
+                Box::new(exporter)
+            }
+            ExportFormat::Cef => {
+                let mut exporter = ufwlog::export::cef::Exporter::new()
+                    .vendor(&self.vendor)
+                    .product(&self.product)
+                    .severity(self.severity());
+                if let Some(year) = self.year {
+                    exporter = exporter.year(year);
+                }
+                Box::new(exporter)
+            }
+            ExportFormat::Gelf => {
+                let mut exporter = ufwlog::export::gelf::Exporter::new();
+                if let Some(year) = self.year {
+                    exporter = exporter.year(year);
+                }
+                Box::new(exporter)
+            }
+            ExportFormat::Influx => {
+                let mut exporter =
+                    ufwlog::export::influx::Exporter::new().measurement(&self.measurement);
+                if let Some(seconds) = self.aggregate {
+                    exporter = exporter.interval(seconds);
+                }
+                if let Some(year) = self.year {
+                    exporter = exporter.year(year);
+                }
+                Box::new(exporter)
+            }
+            ExportFormat::Otlp => {
+                let mut exporter =
+                    ufwlog::export::otlp::Exporter::new().service_name(&self.service_name);
+                if let Some(year) = self.year {
+                    exporter = exporter.year(year);
+                }
+                Box::new(exporter)
+            }
+            ExportFormat::Splunk => {
+                let mut exporter =
+                    ufwlog::export::splunk::Exporter::new().sourcetype(&self.sourcetype);
+                if let Some(year) = self.year {
+                    exporter = exporter.year(year);
+                }
+                Box::new(exporter)
+            }
+            ExportFormat::Zeek => {
+                let mut exporter = ufwlog::export::zeek::Exporter::new();
+                if let Some(year) = self.year {
+                    exporter = exporter.year(year);
+                }
+                Box::new(exporter)
+            }
+            ExportFormat::Pcapng => {
+                let mut exporter = ufwlog::export::pcapng::Exporter::new();
+                if let Some(year) = self.year {
+                    exporter = exporter.year(year);
+                }
+                Box::new(exporter)
+            }
+            ExportFormat::Msgpack => Box::new(ufwlog::export::msgpack::Exporter::new()),
+            ExportFormat::Template => {
+                let mut exporter = ufwlog::export::template::Exporter::new();
+                if let Some(template) = &self.template {
+                    exporter = exporter.template(template.clone());
+                }
+                Box::new(exporter)
+            }
+            ExportFormat::Leef => {
+                let mut exporter = ufwlog::export::leef::Exporter::new()
+                    .vendor(&self.vendor)
+                    .product(&self.product)
+                    .severity(self.severity());
+                if let Some(year) = self.year {
+                    exporter = exporter.year(year);
+                }
+                Box::new(exporter)
+            }
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => {
+                let mut exporter = ufwlog::export::parquet::Exporter::new()
+                    .compression(self.compression.clone().into())
+                    .row_group_size(self.row_group_size as usize);
+                if let Some(year) = self.year {
+                    exporter = exporter.year(year);
+                }
+                Box::new(exporter)
+            }
+            #[cfg(feature = "sqlite")]
+            ExportFormat::Sqlite => return Err("Sqlite is not a stream format.".into()),
+            #[cfg(feature = "xlsx")]
+            ExportFormat::Xlsx => {
+                let mut exporter = ufwlog::export::xlsx::Exporter::new().summary(self.summary);
+                if let Some(year) = self.year {
+                    exporter = exporter.year(year);
+                }
+                Box::new(exporter)
+            }
+        })
+    }
+}
+
+/// Export logs into file with given exporter
+///
+/// Logs are read and written one by one, so large log file can be handled with constant memory.
+pub fn export(
+    exporter: &dyn Export,
+    logs: impl Iterator<Item = Result<UfwLog, ufwlog::error::Error>>,
+    config: Config,
+) -> Result<(), Box<dyn Error>> {
+    let path = config.output_path(exporter.get_extension())?;
+    let mut writer = BufWriter::new(File::create(&path)?);
+
+    let pb = crate::parser::spinner();
+    pb.set_message("Exporting...");
+    let mut logs = logs.inspect(|_| pb.inc(1));
+    if let Err(e) = exporter.export_iter(&mut logs, &mut writer) {
+        pb.abandon_with_message("Export failed!");
+        drop(writer);
+        return Err(remove_partial(&path, e.into()));
+    }
+    pb.finish_with_message("Exported!");
+
+    Ok(())
+}
+
+/// Remove the partially written output file, so a failed export doesn't leave a truncated one.
+fn remove_partial(path: &std::path::Path, error: Box<dyn Error>) -> Box<dyn Error> {
+    match std::fs::remove_file(path) {
+        Ok(()) => error,
+        Err(e) => format!("{error} (failed to remove {}: {e})", path.display()).into(),
+    }
+}
+
+/// Export logs into sqlite database
+///
+/// The database file is kept in append mode, or replaced if overwrite is allowed.
+#[cfg(feature = "sqlite")]
+pub fn export_sqlite(
+    logs: impl Iterator<Item = Result<UfwLog, ufwlog::error::Error>>,
+    config: Config,
+    append: bool,
+    year: Option<i32>,
+) -> Result<(), Box<dyn Error>> {
+    let config = Config {
+        overwrite: config.overwrite || append,
+        ..config
+    };
+    let path = config.output_path("db")?;
+    if path.exists() && !append {
+        std::fs::remove_file(&path)?;
+    }
+    // an existing database in append mode keeps the committed transactions on error
+    let created = !path.exists();
+    let mut conn = ufwlog::export::sqlite::Connection::open(&path)?;
+
+    let pb = crate::parser::spinner();
+    pb.set_message("Exporting...");
+    let mut logs = logs.inspect(|_| pb.inc(1));
+    let mut exporter = ufwlog::export::sqlite::Exporter::new().append(append);
+    if let Some(year) = year {
+        exporter = exporter.year(year);
+    }
+    let summary = match exporter.export_iter(&mut logs, &mut conn) {
+        Ok(summary) => summary,
+        Err(e) => {
+            pb.abandon_with_message("Export failed!");
+            drop(conn);
+            return Err(if created {
+                remove_partial(&path, e.into())
+            } else {
+                e.into()
+            });
+        }
+    };
+    pb.finish_with_message(format!(
+        "Exported! {} new records, {} skipped.",
+        summary.inserted,
+        summary.read - summary.inserted
+    ));
+
+    Ok(())
 }
 
 /// Export format that CLI support
 #[derive(ValueEnum, Debug, Clone, PartialEq)]
 pub(crate) enum ExportFormat {
     Csv,
+    Json,
+    Ndjson,
+    /// Elastic Common Schema, one document per line
+    Ecs,
+    /// ArcSight Common Event Format
+    Cef,
+    /// QRadar Log Event Extended Format 2.0
+    Leef,
+    /// Graylog Extended Log Format, one message per line
+    Gelf,
+    /// InfluxDB line protocol
+    Influx,
+    /// OpenTelemetry logs in OTLP/JSON, one request per line
+    Otlp,
+    /// Splunk HTTP Event Collector events, one per line
+    Splunk,
+    /// Zeek conn.log TSV with `#fields` and `#types` headers
+    Zeek,
+    /// Synthetic packets rebuilt from headers in log, for Wireshark
+    Pcapng,
+    /// MessagePack archive, which can be converted back
+    Msgpack,
+    /// One line per log rendered from `--format` template
+    Template,
+    #[cfg(feature = "parquet")]
+    Parquet,
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+    /// Excel workbook with typed cells
+    #[cfg(feature = "xlsx")]
+    Xlsx,
 }
 
 impl From<ExportFormat> for ufwlog::export::Format {
     fn from(value: ExportFormat) -> Self {
         match value {
             ExportFormat::Csv => ufwlog::export::Format::Csv,
+            ExportFormat::Json => ufwlog::export::Format::Json,
+            ExportFormat::Ndjson => ufwlog::export::Format::Ndjson,
+            ExportFormat::Ecs => ufwlog::export::Format::Ecs,
+            ExportFormat::Cef => ufwlog::export::Format::Cef,
+            ExportFormat::Leef => ufwlog::export::Format::Leef,
+            ExportFormat::Gelf => ufwlog::export::Format::Gelf,
+            ExportFormat::Influx => ufwlog::export::Format::Influx,
+            ExportFormat::Otlp => ufwlog::export::Format::Otlp,
+            ExportFormat::Splunk => ufwlog::export::Format::Splunk,
+            ExportFormat::Zeek => ufwlog::export::Format::Zeek,
+            ExportFormat::Pcapng => ufwlog::export::Format::Pcapng,
+            ExportFormat::Msgpack => ufwlog::export::Format::Msgpack,
+            ExportFormat::Template => ufwlog::export::Format::Template,
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => ufwlog::export::Format::Parquet,
+            #[cfg(feature = "sqlite")]
+            ExportFormat::Sqlite => ufwlog::export::Format::Sqlite,
+            #[cfg(feature = "xlsx")]
+            ExportFormat::Xlsx => ufwlog::export::Format::Xlsx,
+        }
+    }
+}
+
+/// How TCP flags are rendered in tabular format
+#[derive(ValueEnum, Debug, Clone, PartialEq)]
+pub(crate) enum FlagStyle {
+    /// One column contains all flags, e.g. `SYN ACK`
+    Combined,
+    /// One boolean column per flag
+    Separate,
+}
+
+impl From<FlagStyle> for ufwlog::export::column::FlagStyle {
+    fn from(value: FlagStyle) -> Self {
+        match value {
+            FlagStyle::Combined => ufwlog::export::column::FlagStyle::Combined,
+            FlagStyle::Separate => ufwlog::export::column::FlagStyle::Separate,
+        }
+    }
+}
+
+/// Compression codec of parquet
+#[cfg(feature = "parquet")]
+#[derive(ValueEnum, Debug, Clone, PartialEq)]
+pub(crate) enum Compression {
+    None,
+    Snappy,
+    Gzip,
+    Zstd,
+}
+
+#[cfg(feature = "parquet")]
+impl From<Compression> for ufwlog::export::parquet::Compression {
+    fn from(value: Compression) -> Self {
+        match value {
+            Compression::None => ufwlog::export::parquet::Compression::None,
+            Compression::Snappy => ufwlog::export::parquet::Compression::Snappy,
+            Compression::Gzip => ufwlog::export::parquet::Compression::Gzip,
+            Compression::Zstd => ufwlog::export::parquet::Compression::Zstd,
         }
     }
 }
@@ -0,0 +1,12 @@
+use ufwlog::UfwPolicy;
+
+/// Parse severity of a policy, e.g. `block=7`, the severity should be between 0 and 10
+pub fn parse_severity(s: &str) -> Result<(UfwPolicy, u8), String> {
+    let (policy, severity) = crate::generate::parse_policy_weight(s)?;
+    if severity > 10 {
+        return Err(format!(
+            "Severity should be between 0 and 10, got {severity}"
+        ));
+    }
+    Ok((policy, severity as u8))
+}
@@ -1,42 +1,19 @@
-use crate::export::Config;
-use indicatif::ProgressBar;
-use std::error::Error;
-use std::path::PathBuf;
-use ufwlog::export::Export;
-use ufwlog::UfwLog;
-
-pub fn convert(logs: Vec<UfwLog>, config: Config) -> Result<(), Box<dyn Error>> {
-    let exporter = ufwlog::export::csv::Exporter;
-    // resolve file path and name
-    let mut path = PathBuf::from(config.output_filename);
-    if path.file_name().is_none() {
-        return Err("Please specify a file name.".into());
-    }
-    if path.extension().is_none() {
-        path.set_extension(exporter.get_extension());
+/// Parse csv delimiter, `\t` is accepted as tab
+///
+/// The delimiter is validated by [`Exporter::try_delimiter`](ufwlog::export::csv::Exporter::try_delimiter).
+pub fn parse_delimiter(s: &str) -> Result<char, String> {
+    let delimiter = match s {
+        "\\t" => '\t',
+        _ => {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => c,
+                _ => return Err(format!("Delimiter should be a single character, got `{s}`")),
+            }
+        }
     };
-    // if the file exists, return error
-    if path.exists() && !config.overwrite {
-        return Err(format!(
-            "The file {} is exist. Overwrite it with `--overwrite` flag.",
-            path.to_str().unwrap()
-        )
-        .into());
-    }
-
-    let mut wtr = csv::Writer::from_path(path.to_str().unwrap())?;
-    wtr.write_record(exporter.get_header())
-        .expect("Write failed when try to insert header row.");
-
-    let pb = ProgressBar::new(logs.len() as u64);
-    for i in logs {
-        let row = exporter.get_csv_row(&i);
-
-        wtr.write_record(row).expect("Write csv file occur error");
-
-        pb.inc(1); // increase progress bar
-    }
-    pb.finish();
-
-    Ok(())
+    ufwlog::export::csv::Exporter::new()
+        .try_delimiter(delimiter)
+        .map(|_| delimiter)
+        .map_err(|e| e.to_string())
 }
@@ -0,0 +1,80 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+use ufwlog::generator::{Config, Generator, Protocol};
+use ufwlog::UfwPolicy;
+
+/// Write `count` generated log lines into `output`, or stdout if not given.
+pub fn generate(
+    config: Config,
+    count: usize,
+    output: &Option<String>,
+    overwrite: bool,
+) -> Result<(), Box<dyn Error>> {
+    let mut writer: Box<dyn Write> = match output {
+        Some(path) => {
+            if std::path::Path::new(path).exists() && !overwrite {
+                return Err(format!(
+                    "The file {} is exist. Overwrite it with `--overwrite` flag.",
+                    path
+                )
+                .into());
+            }
+            Box::new(BufWriter::new(File::create(path)?))
+        }
+        None => Box::new(BufWriter::new(std::io::stdout().lock())),
+    };
+    for line in Generator::new(config).take(count) {
+        writeln!(writer, "{}", line)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Seed from current time, used when user doesn't give one.
+pub fn random_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// Parse policy weight, e.g. `block=80`
+pub fn parse_policy_weight(s: &str) -> Result<(UfwPolicy, u32), String> {
+    parse_weight(s, |name| {
+        let policy = UfwPolicy::from(name.replace(['-', '_'], " "));
+        if policy == UfwPolicy::Unknown {
+            return Err(format!("Unknown policy: {name}"));
+        }
+        Ok(policy)
+    })
+}
+
+/// Parse protocol weight, e.g. `tcp=70`
+pub fn parse_protocol_weight(s: &str) -> Result<(Protocol, u32), String> {
+    parse_weight(s, |name| name.parse::<Protocol>())
+}
+
+/// Parse ratio between 0 and 1
+pub fn parse_ratio(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(r) if (0.0..=1.0).contains(&r) => Ok(r),
+        _ => Err(format!("{s} is not a number between 0 and 1")),
+    }
+}
+
+/// Parse `name=weight` pair
+fn parse_weight<T>(
+    s: &str,
+    parse_name: impl Fn(&str) -> Result<T, String>,
+) -> Result<(T, u32), String> {
+    let (name, weight) = s
+        .split_once('=')
+        .ok_or(format!("Expect `name=weight`, got `{s}`"))?;
+    let weight = weight
+        .trim()
+        .parse::<u32>()
+        .map_err(|_| format!("Invalid weight: {weight}"))?;
+    Ok((parse_name(name.trim())?, weight))
+}
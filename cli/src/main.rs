@@ -1,7 +1,11 @@
 mod export;
+mod generate;
+mod metrics;
 mod parser;
+mod report;
+mod send;
+mod tail;
 
-use crate::export::ExportFormat;
 use clap::{CommandFactory, Parser, Subcommand, ValueHint};
 use clap_complete::generate;
 
@@ -12,20 +16,85 @@ fn main() {
     // handle subcommand
     match &cli.command {
         // export
-        Some(SubCommands::Export {
-            format,
+        Some(SubCommands::Export { args }) => {
+            let logs = parser::open_ufwlog(cli.log_path.clone().unwrap().as_str())
+                .unwrap_or_else(|e| panic!("Error occur when trying to read file: {}", e));
+            export(args, logs)
+        }
+        Some(SubCommands::Convert {
+            input,
+            from,
+            input_delimiter,
+            args,
+        }) => {
+            let logs = parser::open(input, from.clone(), *input_delimiter)
+                .unwrap_or_else(|e| panic!("Error occur when trying to read file: {}", e));
+            export(args, logs)
+        }
+        Some(SubCommands::Send { target }) => {
+            let log_path = cli.log_path.clone().unwrap();
+            if target.follow() {
+                let logs = ufwlog::follow::Follower::open(&log_path)
+                    .unwrap_or_else(|e| panic!("Error occur when following file: {}", e))
+                    .filter(|log| match log {
+                        Err(ufwlog::error::Error::Parse(e)) => {
+                            eprintln!("Skip invalid line: {e}");
+                            false
+                        }
+                        _ => true,
+                    });
+                send::send(target, logs).unwrap()
+            } else {
+                let logs = parser::open_ufwlog(log_path.as_str())
+                    .unwrap_or_else(|e| panic!("Error occur when trying to read file: {}", e));
+                send::send(target, logs).unwrap()
+            }
+        }
+        Some(SubCommands::Tail { args }) => args
+            .run(cli.log_path.clone().unwrap().as_str())
+            .unwrap_or_else(|e| panic!("Error occur when following file: {}", e)),
+        Some(SubCommands::Metrics { args }) => args
+            .run(cli.log_path.clone().unwrap().as_str())
+            .unwrap_or_else(|e| panic!("Error occur when counting metrics: {}", e)),
+        Some(SubCommands::Report { args }) => args
+            .run(cli.log_path.clone().unwrap().as_str())
+            .unwrap_or_else(|e| panic!("Error occur when rendering report: {}", e)),
+        Some(SubCommands::Generate {
+            count,
+            seed,
+            hostnames,
+            policy_mix,
+            protocol_mix,
+            ipv6_ratio,
+            scan_ratio,
+            brute_force_ratio,
             output_filename,
             overwrite,
         }) => {
-            // export with specific format
-            match *format {
-                Some(ExportFormat::Csv) => export::csv::convert(
-                    parser::get_ufwlog_vec(cli.log_path.clone().unwrap().as_str()),
-                    export::Config::new(output_filename, *overwrite),
-                )
-                .unwrap(),
-                _ => println!("Current not support other format"),
-            }
+            let default = ufwlog::generator::Config::default();
+            let config = ufwlog::generator::Config {
+                seed: seed.unwrap_or_else(generate::random_seed),
+                hostnames: if hostnames.is_empty() {
+                    default.hostnames
+                } else {
+                    hostnames.clone()
+                },
+                policies: if policy_mix.is_empty() {
+                    default.policies
+                } else {
+                    policy_mix.clone()
+                },
+                protocols: if protocol_mix.is_empty() {
+                    default.protocols
+                } else {
+                    protocol_mix.clone()
+                },
+                ipv6_ratio: *ipv6_ratio,
+                scan_ratio: *scan_ratio,
+                brute_force_ratio: *brute_force_ratio,
+                ..default
+            };
+            generate::generate(config, *count, output_filename, *overwrite).unwrap()
         }
         Some(SubCommands::Completion { shell }) => {
             // generate shell completion
@@ -40,6 +109,17 @@ fn main() {
     }
 }
 
+/// Export logs, print the error and exit with failure instead of panicking.
+fn export(
+    args: &export::Args,
+    logs: impl Iterator<Item = Result<ufwlog::UfwLog, ufwlog::error::Error>>,
+) {
+    if let Err(e) = args.export(logs) {
+        eprintln!("Error occur when exporting: {e}");
+        std::process::exit(1);
+    }
+}
+
 #[derive(Parser)]
 #[command(name = "ufwlog", bin_name = "ufwlog", version, about, long_about = None)]
 struct Cli {
@@ -75,17 +155,86 @@ struct Cli {
 enum SubCommands {
     /// Export UFW log file with other format
     Export {
-        /// Which type to be export.
-        #[arg(default_value = "csv")]
-        format: Option<ExportFormat>,
+        #[command(flatten)]
+        args: export::Args,
+    },
+    /// Convert exported file (e.g. csv) or log file into other format
+    Convert {
+        /// Path of input file.
+        #[arg(short, long, value_name = "input", value_hint = ValueHint::FilePath)]
+        input: String,
+
+        /// Format of input file, detect by extension if not given.
+        #[arg(long, value_enum)]
+        from: Option<parser::InputFormat>,
+
+        /// Field delimiter of input csv, default is tab for `.tsv` file, comma for others.
+        #[arg(long = "input-delimiter", value_parser = export::csv::parse_delimiter)]
+        input_delimiter: Option<char>,
+
+        #[command(flatten)]
+        args: export::Args,
+    },
+    /// Send UFW log to log server
+    Send {
+        #[command(subcommand)]
+        target: send::Target,
+    },
+    /// Follow UFW log file and write new logs to stdout with given format
+    Tail {
+        #[command(flatten)]
+        args: tail::Args,
+    },
+    /// Count UFW log into Prometheus metrics, write to file or serve on `/metrics`
+    Metrics {
+        #[command(flatten)]
+        args: metrics::Args,
+    },
+    /// Summarize UFW log into a report, as a single-file HTML page or Markdown
+    Report {
+        #[command(flatten)]
+        args: report::Args,
+    },
+    /// Generate synthetic UFW log for testing or demo.
+    Generate {
+        /// How many log lines to generate.
+        #[arg(short = 'n', long, default_value_t = 1000)]
+        count: usize,
+
+        /// Seed of random generator, the same seed produce the same output.
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Hostname of log, can be given multiple times.
+        #[arg(long = "hostname", value_name = "hostname")]
+        hostnames: Vec<String>,
+
+        /// Weights of policy, e.g. `block=80,allow=15,audit=4,audit-invalid=1,limit-block=0`.
+        #[arg(long = "policy-mix", value_name = "mix", value_delimiter = ',', value_parser = generate::parse_policy_weight)]
+        policy_mix: Vec<(ufwlog::UfwPolicy, u32)>,
+
+        /// Weights of protocol, e.g. `tcp=70,udp=20,icmp=8,icmpv6=2`.
+        #[arg(long = "protocol-mix", value_name = "mix", value_delimiter = ',', value_parser = generate::parse_protocol_weight)]
+        protocol_mix: Vec<(ufwlog::generator::Protocol, u32)>,
+
+        /// Probability of IPv6 packet.
+        #[arg(long, value_name = "ratio", default_value_t = 0.1, value_parser = generate::parse_ratio)]
+        ipv6_ratio: f64,
+
+        /// Probability that a port scan starts at each line.
+        #[arg(long, value_name = "ratio", default_value_t = 0.002, value_parser = generate::parse_ratio)]
+        scan_ratio: f64,
+
+        /// Probability that a brute-force attack starts at each line.
+        #[arg(long, value_name = "ratio", default_value_t = 0.002, value_parser = generate::parse_ratio)]
+        brute_force_ratio: f64,
 
-        /// Specify output path and filename.
+        /// Write to file instead of stdout.
         #[arg(
             short,
             long = "output",
             value_name = "filename",
-            value_hint = ValueHint::AnyPath,
-            default_value = "ufwlog"
+            value_hint = ValueHint::AnyPath
         )]
         output_filename: Option<String>,
 
@@ -0,0 +1,153 @@
+use clap::ValueHint;
+use std::error::Error;
+use std::net::TcpListener;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use ufwlog::follow::Follower;
+use ufwlog::metrics::{Exposition, Label, Metrics};
+
+/// Initial wait before reading log again after an error in follow mode
+const FOLLOW_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Maximum wait before reading log again in follow mode
+const MAX_FOLLOW_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Output and options of metrics
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Write metrics into file, e.g. `/var/lib/node_exporter/textfile_collector/ufw.prom`.
+    /// Default is stdout if not serving.
+    #[arg(short, long = "output", value_name = "filename", value_hint = ValueHint::AnyPath)]
+    output_filename: Option<String>,
+
+    /// Serve metrics on `/metrics` of given address, e.g. `127.0.0.1:9632`.
+    #[arg(long, value_name = "addr")]
+    listen: Option<String>,
+
+    /// Keep counting lines appended to log file, until interrupted.
+    #[arg(short, long, default_value_t = false)]
+    follow: bool,
+
+    /// Seconds between rewriting output file in follow mode.
+    #[arg(long, value_name = "seconds", default_value_t = 15, value_parser = clap::value_parser!(u64).range(1..))]
+    interval: u64,
+
+    /// Labels of metrics, from hostname, policy, proto, in, out, src, dst, spt and dpt.
+    #[arg(long, value_name = "labels", value_delimiter = ',', default_value = "policy,proto,in,dpt", value_parser = Label::from_str)]
+    labels: Vec<Label>,
+
+    /// Maximum number of label sets, events of other label sets are counted into a series whose
+    /// labels are all `other`.
+    #[arg(long = "max-series", value_name = "count", default_value_t = 1000)]
+    max_series: usize,
+
+    /// Write output file in OpenMetrics format instead of Prometheus text format.
+    #[arg(long, default_value_t = false)]
+    openmetrics: bool,
+}
+
+impl Args {
+    /// Count logs of given file, then write or serve metrics
+    pub fn run(&self, log_path: &str) -> Result<(), Box<dyn Error>> {
+        let metrics = Metrics::new()
+            .labels(self.labels.clone())
+            .max_series(self.max_series);
+        let metrics = Arc::new(Mutex::new(metrics));
+        let mut follow_thread = None;
+        let listener = match &self.listen {
+            Some(addr) => Some(TcpListener::bind(addr)?),
+            None => None,
+        };
+
+        if self.follow {
+            if self.output_filename.is_none() && listener.is_none() {
+                return Err("Please specify `--output` or `--listen` to follow log.".into());
+            }
+            let follower = Follower::open(log_path)?;
+            let shared = metrics.clone();
+            follow_thread = Some(std::thread::spawn(move || follow(follower, &shared)));
+        } else {
+            lock(&metrics)?.extend(crate::parser::open_ufwlog(log_path)?);
+        }
+
+        let server = listener.map(|listener| {
+            let shared = metrics.clone();
+            std::thread::spawn(move || ufwlog::metrics::serve(listener, shared))
+        });
+        match &self.output_filename {
+            Some(path) => loop {
+                write_file(&*lock(&metrics)?, path, self.exposition())?;
+                if !self.follow {
+                    break;
+                }
+                std::thread::sleep(Duration::from_secs(self.interval));
+                // following only stops by error
+                if follow_thread.as_ref().is_some_and(JoinHandle::is_finished) {
+                    let thread = follow_thread.take().unwrap();
+                    thread.join().map_err(|_| "Log follower panicked")??;
+                }
+            },
+            None if server.is_none() => {
+                let stdout = std::io::stdout();
+                lock(&metrics)?.write(&mut stdout.lock(), self.exposition())?;
+            }
+            None => {}
+        }
+        if let Some(server) = server {
+            server.join().map_err(|_| "Metrics server panicked")??;
+        }
+        Ok(())
+    }
+
+    fn exposition(&self) -> Exposition {
+        if self.openmetrics {
+            Exposition::OpenMetrics
+        } else {
+            Exposition::Prometheus
+        }
+    }
+}
+
+/// Count logs appended to log file, until the metrics can't be locked.
+///
+/// Lines which can't be parsed are counted as parse errors. Other errors (e.g. the file is
+/// rotated or can't be read) are reported to stderr, then reading is retried with backoff.
+fn follow(follower: Follower, metrics: &Mutex<Metrics>) -> Result<(), String> {
+    let mut backoff = FOLLOW_BACKOFF;
+    for log in follower {
+        match log {
+            Ok(_) | Err(ufwlog::error::Error::Parse(_)) => {
+                backoff = FOLLOW_BACKOFF;
+                lock(metrics).map_err(|e| e.to_string())?.extend([log]);
+            }
+            Err(e) => {
+                eprintln!("Failed to read log: {e}, retry in {backoff:?}");
+                std::thread::sleep(backoff);
+                backoff = (backoff * 2).min(MAX_FOLLOW_BACKOFF);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Lock metrics, or return error if another thread panicked with the lock.
+fn lock(metrics: &Mutex<Metrics>) -> Result<MutexGuard<'_, Metrics>, Box<dyn Error>> {
+    metrics
+        .lock()
+        .map_err(|e| format!("Failed to lock metrics: {e}").into())
+}
+
+/// Write metrics into a temporary file then rename it, so that collector never reads a partial
+/// file.
+fn write_file(metrics: &Metrics, path: &str, format: Exposition) -> Result<(), Box<dyn Error>> {
+    let path = Path::new(path);
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let mut file = std::fs::File::create(&temp)?;
+    metrics.write(&mut file, format)?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
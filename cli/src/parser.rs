@@ -1,14 +1,73 @@
+use clap::ValueEnum;
 use indicatif::{ProgressBar, ProgressStyle};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
 use std::time::Duration;
+use ufwlog::error::Error;
 use ufwlog::UfwLog;
 
-/// Get vector of UfwLog object from log file
-pub fn get_ufwlog_vec(path: &str) -> Vec<UfwLog> {
-    // make a spinner
+/// Open log file and get a lazy iterator of UfwLog
+pub fn open_ufwlog(path: &str) -> Result<impl Iterator<Item = Result<UfwLog, Error>>, Error> {
+    let reader = BufReader::new(File::open(path)?);
+    Ok(UfwLog::from_buf_reader(reader))
+}
+
+/// Format of input file
+#[derive(ValueEnum, Debug, Clone, PartialEq)]
+pub(crate) enum InputFormat {
+    /// Original ufw log
+    Log,
+    /// Csv or TSV exported by this program
+    Csv,
+    /// MessagePack archive exported by this program
+    Msgpack,
+}
+
+/// Open file with given format and get a lazy iterator of UfwLog
+///
+/// Format is detected by extension if not given: `.csv` and `.tsv` are csv, `.msgpack` is
+/// msgpack, others are log.
+pub fn open(
+    path: &str,
+    format: Option<InputFormat>,
+    delimiter: Option<char>,
+) -> Result<Box<dyn Iterator<Item = Result<UfwLog, Error>>>, Error> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+    let format = format.unwrap_or(match extension.as_deref() {
+        Some("csv") | Some("tsv") => InputFormat::Csv,
+        Some("msgpack") => InputFormat::Msgpack,
+        _ => InputFormat::Log,
+    });
+    match format {
+        InputFormat::Log => Ok(Box::new(open_ufwlog(path)?)),
+        InputFormat::Csv => {
+            let delimiter = delimiter.unwrap_or(if extension.as_deref() == Some("tsv") {
+                '\t'
+            } else {
+                ','
+            });
+            let reader = BufReader::new(File::open(path)?);
+            Ok(Box::new(
+                ufwlog::import::csv::Reader::new(reader).delimiter(delimiter),
+            ))
+        }
+        InputFormat::Msgpack => {
+            let reader = BufReader::new(File::open(path)?);
+            Ok(Box::new(ufwlog::import::msgpack::Reader::new(reader)))
+        }
+    }
+}
+
+/// Make a spinner which shows how many records are handled
+pub fn spinner() -> ProgressBar {
     let pb = ProgressBar::new_spinner();
     pb.enable_steady_tick(Duration::from_millis(150));
     pb.set_style(
-        ProgressStyle::with_template("{spinner:.yellow} {msg}")
+        ProgressStyle::with_template("{spinner:.yellow} {msg} {pos}")
             .unwrap()
             .tick_strings(&[
                 "😑 😑 😑 😑 😑",
@@ -22,17 +81,5 @@ pub fn get_ufwlog_vec(path: &str) -> Vec<UfwLog> {
                 "🥳 🥳 🥳 🥳 🥳",
             ]),
     );
-    pb.set_message("Parsing...");
-
-    // parse as UfwLog struct
-    let ufw_log_vec = UfwLog::from_file(path);
-    if ufw_log_vec.is_err() {
-        panic!(
-            "Error occur when trying to read file: {}",
-            ufw_log_vec.err().unwrap()
-        );
-    }
-
-    pb.finish_with_message("Parsed!");
-    ufw_log_vec.unwrap()
+    pb
 }
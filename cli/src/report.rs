@@ -0,0 +1,123 @@
+use clap::ValueHint;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::Duration;
+use ufwlog::report::{html, markdown};
+use ufwlog::UfwLog;
+
+/// Format and options of report
+#[derive(clap::Args, Debug)]
+#[command(group = clap::ArgGroup::new("format").required(true))]
+pub struct Args {
+    /// Render a single-file HTML page with inline CSS and JavaScript.
+    #[arg(long, group = "format", default_value_t = false)]
+    html: bool,
+
+    /// Render a Markdown summary for tickets and chat.
+    #[arg(long, group = "format", default_value_t = false)]
+    markdown: bool,
+
+    /// Write report into file instead of stdout.
+    #[arg(short, long = "output", value_name = "filename", value_hint = ValueHint::AnyPath)]
+    output_filename: Option<String>,
+
+    /// Title of report, default is `UFW Report` for HTML and `UFW Summary` for Markdown.
+    #[arg(long)]
+    title: Option<String>,
+
+    /// How many rows of top sources, destinations and ports.
+    #[arg(long, value_name = "count", default_value_t = 10)]
+    top: usize,
+
+    /// Maximum events in the table of HTML report, only the latest events are kept.
+    #[arg(long = "max-events", value_name = "count", default_value_t = 1000)]
+    max_events: usize,
+
+    /// Seconds of each bar in the histogram of HTML report.
+    #[arg(long, value_name = "seconds", default_value_t = 3600, value_parser = clap::value_parser!(u64).range(1..))]
+    bucket: u64,
+
+    /// How many raw lines of the latest logs are attached to Markdown report.
+    #[arg(long, value_name = "count", default_value_t = 0, requires = "markdown")]
+    sample: usize,
+
+    /// Only include logs at or after the time, in UTC, e.g. `2024-01-16`, `2024-01-16T08:00:00Z`
+    /// or unix timestamp.
+    #[arg(long, value_name = "time", value_parser = parse_time)]
+    since: Option<i64>,
+
+    /// Only include logs before the time, in the same format as `--since`.
+    #[arg(long, value_name = "time", value_parser = parse_time)]
+    until: Option<i64>,
+
+    /// Year of logs, which is used to place logs in the histogram and time window.
+    /// Default is the current year, or last year if the date would be in the future.
+    #[arg(long)]
+    year: Option<i32>,
+}
+
+impl Args {
+    /// Aggregate logs of given file and write the report
+    pub fn run(&self, log_path: &str) -> Result<(), Box<dyn Error>> {
+        let mut logs = crate::parser::open_ufwlog(log_path)?;
+        match &self.output_filename {
+            Some(path) => {
+                let mut writer = BufWriter::new(File::create(path)?);
+                self.render(&mut logs, &mut writer)
+            }
+            None => {
+                let stdout = std::io::stdout();
+                self.render(&mut logs, &mut stdout.lock())
+            }
+        }
+    }
+
+    fn render(
+        &self,
+        logs: &mut dyn Iterator<Item = Result<UfwLog, ufwlog::error::Error>>,
+        writer: &mut dyn Write,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.markdown {
+            let mut report = markdown::Report::new().top(self.top).sample(self.sample);
+            if let Some(title) = &self.title {
+                report = report.title(title);
+            }
+            if let Some(since) = self.since {
+                report = report.since(since);
+            }
+            if let Some(until) = self.until {
+                report = report.until(until);
+            }
+            if let Some(year) = self.year {
+                report = report.year(year);
+            }
+            report.render(logs, writer)?;
+        } else {
+            let mut report = html::Report::new()
+                .top(self.top)
+                .max_events(self.max_events)
+                .bucket(Duration::from_secs(self.bucket));
+            if let Some(title) = &self.title {
+                report = report.title(title);
+            }
+            if let Some(since) = self.since {
+                report = report.since(since);
+            }
+            if let Some(until) = self.until {
+                report = report.until(until);
+            }
+            if let Some(year) = self.year {
+                report = report.year(year);
+            }
+            report.render(logs, writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse time in UTC as unix timestamp, see [`ufwlog::report::parse_time`].
+fn parse_time(text: &str) -> Result<i64, String> {
+    ufwlog::report::parse_time(text)
+        .ok_or_else(|| format!("Invalid time `{text}`, expected e.g. `2024-01-16T08:00:00Z`"))
+}
@@ -0,0 +1,368 @@
+use clap::Subcommand;
+use std::error::Error;
+use ufwlog::UfwLog;
+
+/// Where to send logs
+#[derive(Subcommand, Debug)]
+pub enum Target {
+    /// Send to Graylog as GELF messages
+    Gelf {
+        /// Address of GELF UDP input, e.g. `127.0.0.1:12201`.
+        #[arg(
+            long,
+            value_name = "addr",
+            required_unless_present = "tcp",
+            conflicts_with = "tcp"
+        )]
+        udp: Option<String>,
+
+        /// Address of GELF TCP input, e.g. `127.0.0.1:12201`.
+        #[arg(long, value_name = "addr")]
+        tcp: Option<String>,
+
+        /// Maximum size of UDP datagram, larger message is split into chunks.
+        #[arg(long = "chunk-size", value_name = "bytes", default_value_t = 1420, value_parser = clap::value_parser!(u64).range(13..))]
+        chunk_size: u64,
+
+        /// Year of logs, default is the current year, or last year if the date would be in the
+        /// future.
+        #[arg(long)]
+        year: Option<i32>,
+    },
+    /// Send to OpenTelemetry Collector as OTLP/JSON logs over HTTP
+    #[cfg(feature = "http")]
+    Otlp {
+        /// URL of OTLP/HTTP logs endpoint, e.g. `http://localhost:4318/v1/logs`.
+        #[arg(long, value_name = "url")]
+        endpoint: String,
+
+        /// Header of each request, e.g. `Authorization: Bearer token`. Can be repeated.
+        #[arg(long, value_name = "header", value_parser = parse_header)]
+        header: Vec<(String, String)>,
+
+        /// `service.name` resource attribute.
+        #[arg(long = "service-name", value_name = "name", default_value = "ufw")]
+        service_name: String,
+
+        /// Maximum log records of a request.
+        #[arg(long = "batch-size", value_name = "records", default_value_t = 512, value_parser = clap::value_parser!(u64).range(1..))]
+        batch_size: u64,
+
+        /// Year of logs, default is the current year, or last year if the date would be in the
+        /// future.
+        #[arg(long)]
+        year: Option<i32>,
+    },
+    /// Index into Elasticsearch or OpenSearch through the `_bulk` API
+    #[cfg(feature = "http")]
+    #[command(alias = "opensearch")]
+    Elasticsearch {
+        /// URL of cluster, e.g. `http://localhost:9200`.
+        #[arg(long, value_name = "url")]
+        url: String,
+
+        /// Index pattern, `%Y`, `%m`, `%d` and `%H` are replaced by date of log in UTC.
+        #[arg(long, value_name = "pattern", default_value = "ufw-%Y.%m.%d")]
+        index: String,
+
+        /// Header of each request, e.g. `Authorization: ApiKey bXlfa2V5`. Can be repeated.
+        #[arg(long, value_name = "header", value_parser = parse_header)]
+        header: Vec<(String, String)>,
+
+        /// Maximum documents of a request.
+        #[arg(long = "batch-size", value_name = "documents", default_value_t = 500, value_parser = clap::value_parser!(u64).range(1..))]
+        batch_size: u64,
+
+        /// Maximum bytes of a request body.
+        #[arg(long = "batch-bytes", value_name = "bytes", default_value_t = 5 * 1024 * 1024, value_parser = clap::value_parser!(u64).range(1..))]
+        batch_bytes: u64,
+
+        /// Year of logs, default is the current year, or last year if the date would be in the
+        /// future.
+        #[arg(long)]
+        year: Option<i32>,
+    },
+    /// Send to Splunk HTTP Event Collector
+    #[cfg(feature = "http")]
+    Splunk {
+        /// URL of HEC, e.g. `https://localhost:8088`.
+        #[arg(long, value_name = "url")]
+        url: String,
+
+        /// HEC token.
+        #[arg(long, value_name = "token", env = "SPLUNK_HEC_TOKEN")]
+        token: String,
+
+        /// Channel GUID, required if indexer acknowledgement is enabled on the token. Each
+        /// request waits until its events are indexed.
+        #[arg(long, value_name = "guid")]
+        channel: Option<String>,
+
+        /// Sourcetype of events.
+        #[arg(long, value_name = "sourcetype", default_value = "ufw")]
+        sourcetype: String,
+
+        /// Source of events, default is the source of token.
+        #[arg(long, value_name = "source")]
+        source: Option<String>,
+
+        /// Index of events, default is the index of token.
+        #[arg(long, value_name = "index")]
+        index: Option<String>,
+
+        /// Maximum events of a request.
+        #[arg(long = "batch-size", value_name = "events", default_value_t = 100, value_parser = clap::value_parser!(u64).range(1..))]
+        batch_size: u64,
+
+        /// Year of logs, default is the current year, or last year if the date would be in the
+        /// future.
+        #[arg(long)]
+        year: Option<i32>,
+    },
+    /// Push to Grafana Loki through its push API
+    #[cfg(feature = "http")]
+    Loki {
+        /// URL of Loki push API, e.g. `http://localhost:3100/loki/api/v1/push`.
+        #[arg(long, value_name = "url")]
+        endpoint: String,
+
+        /// Labels of streams, from hostname, policy, proto, in, out and dpt. Hostname is labeled
+        /// as `host`, and `job="ufw"` is always added.
+        #[arg(long, value_name = "labels", value_delimiter = ',', default_value = "hostname,policy,proto", value_parser = parse_loki_label)]
+        labels: Vec<ufwlog::metrics::Label>,
+
+        /// Payload encoding and compression.
+        #[arg(long, value_enum, default_value_t = LokiCompression::Gzip)]
+        compression: LokiCompression,
+
+        /// Tenant of multi-tenant Loki, sent as `X-Scope-OrgID` header.
+        #[arg(long, value_name = "id")]
+        tenant: Option<String>,
+
+        /// Header of each request, e.g. `Authorization: Basic dXNlcjpwYXNz`. Can be repeated.
+        #[arg(long, value_name = "header", value_parser = parse_header)]
+        header: Vec<(String, String)>,
+
+        /// Maximum entries of a request.
+        #[arg(long = "batch-size", value_name = "entries", default_value_t = 1000, value_parser = clap::value_parser!(u64).range(1..))]
+        batch_size: u64,
+
+        /// Maximum milliseconds to wait for more logs before sending a request.
+        #[arg(long = "batch-wait", value_name = "ms", default_value_t = 1000)]
+        batch_wait: u64,
+
+        /// Keep pushing lines appended to log file, until interrupted.
+        #[arg(short, long, default_value_t = false)]
+        follow: bool,
+
+        /// Year of logs, default is the current year, or last year if the date would be in the
+        /// future.
+        #[arg(long)]
+        year: Option<i32>,
+    },
+}
+
+/// Payload encoding and compression of Loki
+#[cfg(feature = "http")]
+#[derive(clap::ValueEnum, Debug, Clone, PartialEq)]
+pub enum LokiCompression {
+    /// JSON without compression
+    None,
+    /// JSON compressed by gzip
+    Gzip,
+    /// Protobuf compressed by snappy
+    Snappy,
+}
+
+impl Target {
+    /// Whether to follow the log file instead of reading it once
+    pub fn follow(&self) -> bool {
+        match self {
+            #[cfg(feature = "http")]
+            Target::Loki { follow, .. } => *follow,
+            _ => false,
+        }
+    }
+}
+
+/// Parse label of Loki stream, IP addresses and source port are rejected
+#[cfg(feature = "http")]
+fn parse_loki_label(s: &str) -> Result<ufwlog::metrics::Label, String> {
+    use ufwlog::metrics::Label;
+    match s.parse()? {
+        label @ (Label::Src | Label::Dst | Label::Spt) => Err(format!(
+            "{} has too high cardinality to be a Loki label",
+            label.name()
+        )),
+        label => Ok(label),
+    }
+}
+
+/// Parse header in `Name: value` form
+#[cfg(feature = "http")]
+fn parse_header(s: &str) -> Result<(String, String), String> {
+    match s.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!("Header should be `Name: value`, got `{s}`")),
+    }
+}
+
+/// Send logs to given target
+pub fn send(
+    target: &Target,
+    logs: impl Iterator<Item = Result<UfwLog, ufwlog::error::Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let pb = crate::parser::spinner();
+    pb.set_message("Sending...");
+    let mut logs = logs.inspect(|_| pb.inc(1));
+
+    match target {
+        Target::Gelf {
+            udp,
+            tcp,
+            chunk_size,
+            year,
+        } => {
+            let mut exporter = ufwlog::export::gelf::Exporter::new();
+            if let Some(year) = year {
+                exporter = exporter.year(*year);
+            }
+            let mut transport: Box<dyn ufwlog::export::gelf::Transport> = match (udp, tcp) {
+                (Some(addr), _) => Box::new(
+                    ufwlog::export::gelf::UdpSender::connect(addr.as_str())?
+                        .chunk_size(*chunk_size as usize),
+                ),
+                (None, Some(addr)) => {
+                    Box::new(ufwlog::export::gelf::TcpSender::connect(addr.as_str())?)
+                }
+                (None, None) => unreachable!("one of udp and tcp is required"),
+            };
+            exporter.send_iter(&mut logs, transport.as_mut())?;
+        }
+        #[cfg(feature = "http")]
+        Target::Otlp {
+            endpoint,
+            header,
+            service_name,
+            batch_size,
+            year,
+        } => {
+            let mut exporter = ufwlog::export::otlp::Exporter::new()
+                .service_name(service_name)
+                .batch_size(*batch_size as usize);
+            if let Some(year) = year {
+                exporter = exporter.year(*year);
+            }
+            let mut sender = ufwlog::export::otlp::HttpSender::new(endpoint);
+            for (name, value) in header {
+                sender = sender.header(name, value);
+            }
+            exporter.send_iter(&mut logs, &sender)?;
+        }
+        #[cfg(feature = "http")]
+        Target::Elasticsearch {
+            url,
+            index,
+            header,
+            batch_size,
+            batch_bytes,
+            year,
+        } => {
+            let mut indexer = ufwlog::export::elasticsearch::Indexer::new(url)
+                .index(index)
+                .batch_size(*batch_size as usize)
+                .batch_bytes(*batch_bytes as usize);
+            for (name, value) in header {
+                indexer = indexer.header(name, value);
+            }
+            if let Some(year) = year {
+                indexer = indexer.year(*year);
+            }
+            let summary = indexer.index_iter(&mut logs)?;
+            for failure in &summary.failed {
+                pb.suspend(|| {
+                    eprintln!(
+                        "Failed to index into {} ({}): {}",
+                        failure.index, failure.status, failure.error
+                    )
+                });
+            }
+            if !summary.failed.is_empty() {
+                pb.abandon();
+                return Err(format!(
+                    "{} documents are indexed, {} failed.",
+                    summary.indexed,
+                    summary.failed.len()
+                )
+                .into());
+            }
+        }
+        #[cfg(feature = "http")]
+        Target::Splunk {
+            url,
+            token,
+            channel,
+            sourcetype,
+            source,
+            index,
+            batch_size,
+            year,
+        } => {
+            let mut exporter = ufwlog::export::splunk::Exporter::new()
+                .sourcetype(sourcetype)
+                .batch_size(*batch_size as usize);
+            if let Some(source) = source {
+                exporter = exporter.source(source);
+            }
+            if let Some(index) = index {
+                exporter = exporter.index(index);
+            }
+            if let Some(year) = year {
+                exporter = exporter.year(*year);
+            }
+            let mut sender = ufwlog::export::splunk::HecSender::new(url, token);
+            if let Some(channel) = channel {
+                sender = sender.channel(channel);
+            }
+            exporter.send_iter(&mut logs, &sender)?;
+        }
+        #[cfg(feature = "http")]
+        Target::Loki {
+            endpoint,
+            labels,
+            compression,
+            tenant,
+            header,
+            batch_size,
+            batch_wait,
+            follow: _,
+            year,
+        } => {
+            use ufwlog::export::loki::{Compression, Pusher};
+            let mut pusher = Pusher::new(endpoint)
+                .labels(labels.clone())
+                .compression(match compression {
+                    LokiCompression::None => Compression::None,
+                    LokiCompression::Gzip => Compression::Gzip,
+                    LokiCompression::Snappy => Compression::Snappy,
+                })
+                .batch_size(*batch_size as usize)
+                .batch_wait(std::time::Duration::from_millis(*batch_wait));
+            if let Some(tenant) = tenant {
+                pusher = pusher.tenant(tenant);
+            }
+            for (name, value) in header {
+                pusher = pusher.header(name, value);
+            }
+            if let Some(year) = year {
+                pusher = pusher.year(*year);
+            }
+            pusher.push_iter(&mut logs)?;
+        }
+    }
+
+    pb.finish_with_message("Sent!");
+    Ok(())
+}
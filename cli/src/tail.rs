@@ -0,0 +1,54 @@
+use crate::export::{ExportFormat, FormatArgs};
+use std::error::Error;
+use ufwlog::follow::Follower;
+
+/// Format of output and where to start
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[command(flatten)]
+    options: FormatArgs,
+
+    /// Read the existing lines from the beginning of file first.
+    #[arg(long = "from-start", default_value_t = false)]
+    from_start: bool,
+}
+
+impl Args {
+    /// Follow log file and write each new log to stdout, until interrupted
+    ///
+    /// Lines which can't be parsed are reported to stderr and skipped.
+    pub fn run(&self, log_path: &str) -> Result<(), Box<dyn Error>> {
+        match self.options.format() {
+            ExportFormat::Json => {
+                return Err("Json array can't be tailed, use ndjson instead.".into())
+            }
+            ExportFormat::Pcapng => return Err("Pcapng can't be tailed.".into()),
+            ExportFormat::Msgpack => return Err("Msgpack can't be tailed.".into()),
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => return Err("Parquet can't be tailed.".into()),
+            #[cfg(feature = "sqlite")]
+            ExportFormat::Sqlite => return Err("Sqlite can't be tailed.".into()),
+            #[cfg(feature = "xlsx")]
+            ExportFormat::Xlsx => return Err("Xlsx can't be tailed.".into()),
+            _ => {}
+        }
+
+        let mut follower = Follower::open(log_path)?;
+        if self.from_start {
+            follower = follower.from_start()?;
+        }
+        let mut logs = follower.filter(|log| match log {
+            Err(ufwlog::error::Error::Parse(e)) => {
+                eprintln!("Skip invalid line: {e}");
+                false
+            }
+            _ => true,
+        });
+        // stdout is line buffered, so each record is written immediately
+        let stdout = std::io::stdout();
+        self.options
+            .exporter()?
+            .export_iter(&mut logs, &mut stdout.lock())?;
+        Ok(())
+    }
+}
@@ -21,3 +21,368 @@ fn unknown_subcommand_would_show_usage() {
     assert!(output.contains("ufwlog")); // binary name
     assert!(output.contains("--help"));
 }
+
+#[test]
+fn generate_with_seed_is_reproducible() {
+    let run = || {
+        let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+        cmd.args(["generate", "-n", "50", "--seed", "5232"]);
+        cmd.assert().success();
+        String::from_utf8(cmd.output().unwrap().stdout).unwrap()
+    };
+    let output = run();
+    assert_eq!(output.lines().count(), 50);
+    assert_eq!(output, run());
+}
+
+#[test]
+fn export_rejects_option_of_other_format() {
+    let log_path = std::env::current_dir()
+        .unwrap()
+        .join("tests")
+        .join("fixtures")
+        .join("ufw.log");
+    let output = std::env::temp_dir().join("ufwlog-rejected-option.json");
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.args([
+        "export",
+        "json",
+        "--columns",
+        "time,src",
+        "--overwrite",
+        "-o",
+    ])
+    .arg(&output)
+    .arg("-l")
+    .arg(&log_path);
+    cmd.assert().failure();
+    let stderr = String::from_utf8(cmd.output().unwrap().stderr).unwrap();
+    assert!(stderr.contains("`--columns` doesn't apply to json format."));
+    assert!(!output.exists());
+}
+
+#[test]
+fn tail_from_start_to_influx() {
+    use std::io::{BufRead, BufReader};
+
+    let log_path = std::env::current_dir()
+        .unwrap()
+        .join("tests")
+        .join("fixtures")
+        .join("ufw.log");
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("ufwlog"))
+        .args(["tail", "influx", "--from-start", "--year", "2024", "-l"])
+        .arg(log_path)
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    // tail never ends by itself
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert!(line.starts_with("ufw,host=103213020,policy=AUDIT,proto=ICMP,out=lo len=84i,ttl=64i,"));
+    assert!(line.ends_with(" 1705018277000000000\n"));
+}
+
+#[test]
+fn metrics_to_stdout() {
+    let log_path = std::env::current_dir()
+        .unwrap()
+        .join("tests")
+        .join("fixtures")
+        .join("ufw.log");
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.args([
+        "metrics",
+        "--labels",
+        "policy",
+        "-l",
+        log_path.to_str().unwrap(),
+    ]);
+    cmd.assert().success();
+    let output = String::from_utf8(cmd.output().unwrap().stdout).unwrap();
+    assert!(output.contains("# TYPE ufw_events_total counter\n"));
+    assert!(output.contains("ufw_events_total{policy=\"BLOCK\"} "));
+    assert!(output.contains("ufw_parse_errors_total 0\n"));
+}
+
+#[test]
+fn report_html_to_file() {
+    let log_path = std::env::current_dir()
+        .unwrap()
+        .join("tests")
+        .join("fixtures")
+        .join("ufw.log");
+    let output = std::env::temp_dir().join(format!("ufwlog-report-{}.html", std::process::id()));
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.args([
+        "report",
+        "--html",
+        "--title",
+        "Weekly report",
+        "-o",
+        output.to_str().unwrap(),
+        "-l",
+        log_path.to_str().unwrap(),
+    ]);
+    cmd.assert().success();
+    let html = std::fs::read_to_string(&output).unwrap();
+    std::fs::remove_file(&output).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<title>Weekly report</title>"));
+    assert!(html.contains("<div class=\"label\">BLOCK</div>"));
+    assert!(html.contains("<table id=\"events\">"));
+
+    // format is required
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.args(["report", "-l", log_path.to_str().unwrap()]);
+    cmd.assert().failure();
+}
+
+#[test]
+fn report_markdown_in_window() {
+    let log_path = std::env::current_dir()
+        .unwrap()
+        .join("tests")
+        .join("fixtures")
+        .join("ufw.log");
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.args([
+        "report",
+        "--markdown",
+        "--since",
+        "2024-01-12",
+        "--until",
+        "2024-01-13T00:00:00Z",
+        "--year",
+        "2024",
+        "--sample",
+        "1",
+        "-l",
+        log_path.to_str().unwrap(),
+    ]);
+    cmd.assert().success();
+    let output = String::from_utf8(cmd.output().unwrap().stdout).unwrap();
+    assert!(output.starts_with(
+        "## UFW Summary\n\n- Window: 2024-01-12T00:00:00Z to 2024-01-13T00:00:00Z\n- Events: 3\n"
+    ));
+    assert!(output.contains("### Top talkers\n\n| Source | Events |\n"));
+    assert!(output.contains("### Sample\n\n```text\nJan 12 00:11:31 "));
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.args(["report", "--markdown", "--since", "yesterday"]);
+    cmd.assert().failure();
+}
+
+#[test]
+fn send_gelf_over_udp() {
+    let log_path = std::env::current_dir()
+        .unwrap()
+        .join("tests")
+        .join("fixtures")
+        .join("ufw.log");
+    let server = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.args(["send", "gelf", "--udp"]);
+    cmd.arg(server.local_addr().unwrap().to_string());
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.assert().success();
+
+    let mut buffer = [0; 1420];
+    let size = server.recv(&mut buffer).unwrap();
+    let message = String::from_utf8(buffer[..size].to_vec()).unwrap();
+    assert!(message.starts_with(r#"{"version":"1.1","host":"#));
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn send_otlp_over_http() {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let log_path = std::env::current_dir()
+        .unwrap()
+        .join("tests")
+        .join("fixtures")
+        .join("ufw.log");
+    let server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}/v1/logs", server.local_addr().unwrap());
+    let collector = std::thread::spawn(move || {
+        let (stream, _) = server.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut length = 0;
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 2 {
+            if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                length = value.trim().parse().unwrap();
+            }
+            line.clear();
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        String::from_utf8(body).unwrap()
+    });
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.args(["send", "otlp", "--endpoint", &endpoint]);
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.assert().success();
+
+    let body = collector.join().unwrap();
+    assert!(body.starts_with(r#"{"resourceLogs":[{"resource":{"attributes":[{"key":"host.name","#));
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn send_elasticsearch_bulk() {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let log_path = std::env::current_dir()
+        .unwrap()
+        .join("tests")
+        .join("fixtures")
+        .join("ufw.log");
+    let server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", server.local_addr().unwrap());
+    let cluster = std::thread::spawn(move || {
+        let (stream, _) = server.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut request = String::new();
+        reader.read_line(&mut request).unwrap();
+        let mut length = 0;
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 2 {
+            if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                length = value.trim().parse().unwrap();
+            }
+            line.clear();
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        let response = r#"{"took":1,"errors":false,"items":[]}"#;
+        write!(
+            reader.get_mut(),
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+            response.len()
+        )
+        .unwrap();
+        (request, String::from_utf8(body).unwrap())
+    });
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.args(["send", "elasticsearch", "--url", &url, "--index", "ufw-%Y"]);
+    cmd.args(["--year", "2024", "-l", log_path.to_str().unwrap()]);
+    cmd.assert().success();
+
+    let (request, body) = cluster.join().unwrap();
+    assert!(request.starts_with("POST /_bulk "));
+    assert!(body.starts_with("{\"index\":{\"_index\":\"ufw-2024\",\"_id\":\""));
+    assert!(body.contains("\"}}\n{\"@timestamp\":"));
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn send_splunk_hec() {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let log_path = std::env::current_dir()
+        .unwrap()
+        .join("tests")
+        .join("fixtures")
+        .join("ufw.log");
+    let server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", server.local_addr().unwrap());
+    let hec = std::thread::spawn(move || {
+        let (stream, _) = server.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut authorization = String::new();
+        let mut length = 0;
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 2 {
+            let lowercase = line.to_lowercase();
+            if let Some(value) = lowercase.strip_prefix("content-length:") {
+                length = value.trim().parse().unwrap();
+            }
+            if lowercase.starts_with("authorization:") {
+                authorization = line[14..].trim().to_string();
+            }
+            line.clear();
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        let response = r#"{"text":"Success","code":0}"#;
+        write!(
+            reader.get_mut(),
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+            response.len()
+        )
+        .unwrap();
+        (authorization, String::from_utf8(body).unwrap())
+    });
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.args(["send", "splunk", "--url", &url, "--index", "firewall"]);
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.env("SPLUNK_HEC_TOKEN", "secret");
+    cmd.assert().success();
+
+    let (authorization, body) = hec.join().unwrap();
+    assert_eq!(authorization, "Splunk secret");
+    assert!(body.starts_with(r#"{"time":"#));
+    assert!(body
+        .lines()
+        .all(|line| line.contains(r#""sourcetype":"ufw","index":"firewall","event":{"#)));
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn send_loki_over_http() {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let log_path = std::env::current_dir()
+        .unwrap()
+        .join("tests")
+        .join("fixtures")
+        .join("ufw.log");
+    let server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}/loki/api/v1/push", server.local_addr().unwrap());
+    let loki = std::thread::spawn(move || {
+        let (stream, _) = server.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut length = 0;
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 2 {
+            if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                length = value.trim().parse().unwrap();
+            }
+            line.clear();
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        String::from_utf8(body).unwrap()
+    });
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.args(["send", "loki", "--endpoint", &endpoint]);
+    cmd.args(["--labels", "policy", "--compression", "none"]);
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.assert().success();
+
+    let body = loki.join().unwrap();
+    assert!(body.starts_with(r#"{"streams":[{"stream":{"job":"ufw","policy":"#));
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.args(["send", "loki", "--endpoint", &endpoint, "--labels", "src"]);
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.assert().failure();
+}
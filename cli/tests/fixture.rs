@@ -27,3 +27,396 @@ fn ufw_log() {
     // teardown
     std::fs::remove_file(output_path).unwrap(); // remove output file
 }
+
+#[test]
+/// Test use ufw.log as input, and expect one JSON object per line as output
+fn ufw_log_to_ndjson() {
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    let current_path = std::env::current_dir().unwrap();
+    let log_path = current_path.join("tests").join("fixtures").join("ufw.log");
+    let output_path = current_path
+        .join("tests")
+        .join("test_ufw.log_output.ndjson");
+
+    cmd.arg("export").arg("ndjson");
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.args(["-o", output_path.to_str().unwrap()]);
+    cmd.assert().success();
+    // check content
+    let input = std::fs::read_to_string(log_path).unwrap();
+    let output = std::fs::read_to_string(&output_path).unwrap();
+    assert_eq!(input.lines().count(), output.lines().count());
+    for (log, json) in input.lines().zip(output.lines()) {
+        assert!(json.starts_with(r#"{"month":"#));
+        assert!(json.ends_with(&format!(r#""origin":"{log}"}}"#)));
+    }
+
+    // teardown
+    std::fs::remove_file(output_path).unwrap(); // remove output file
+}
+
+#[test]
+/// Test convert ufwlog.csv to ndjson, expect the same output as export from ufw.log directly
+fn ufwlog_csv_convert_to_ndjson() {
+    let current_path = std::env::current_dir().unwrap();
+    let log_path = current_path.join("tests").join("fixtures").join("ufw.log");
+    let csv_path = current_path
+        .join("tests")
+        .join("fixtures")
+        .join("ufwlog.csv");
+    let expect_path = current_path
+        .join("tests")
+        .join("test_ufwlog.csv_expect.ndjson");
+    let output_path = current_path
+        .join("tests")
+        .join("test_ufwlog.csv_output.ndjson");
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.arg("export").arg("ndjson");
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.args(["-o", expect_path.to_str().unwrap()]);
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.arg("convert").arg("ndjson");
+    cmd.args(["-i", csv_path.to_str().unwrap()]);
+    cmd.args(["-o", output_path.to_str().unwrap()]);
+    cmd.assert().success();
+    // check content
+    let expect = std::fs::read_to_string(&expect_path).unwrap();
+    let output = std::fs::read_to_string(&output_path).unwrap();
+    assert_eq!(expect, output);
+
+    // teardown
+    std::fs::remove_file(expect_path).unwrap(); // remove output file
+    std::fs::remove_file(output_path).unwrap(); // remove output file
+}
+
+#[test]
+/// Test export ufw.log to msgpack then convert it back to ndjson, expect the same output as
+/// export from ufw.log directly
+fn ufw_log_msgpack_round_trip() {
+    let current_path = std::env::current_dir().unwrap();
+    let log_path = current_path.join("tests").join("fixtures").join("ufw.log");
+    let msgpack_path = current_path
+        .join("tests")
+        .join("test_ufw_log_output.msgpack");
+    let expect_path = current_path
+        .join("tests")
+        .join("test_ufw_log_msgpack_expect.ndjson");
+    let output_path = current_path
+        .join("tests")
+        .join("test_ufw_log_msgpack_output.ndjson");
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.arg("export").arg("msgpack");
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.args(["-o", msgpack_path.to_str().unwrap()]);
+    cmd.assert().success();
+    assert!(std::fs::read(&msgpack_path).unwrap().starts_with(b"UFWM"));
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.arg("export").arg("ndjson");
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.args(["-o", expect_path.to_str().unwrap()]);
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.arg("convert").arg("ndjson");
+    cmd.args(["-i", msgpack_path.to_str().unwrap()]);
+    cmd.args(["-o", output_path.to_str().unwrap()]);
+    cmd.assert().success();
+    // check content
+    let expect = std::fs::read_to_string(&expect_path).unwrap();
+    let output = std::fs::read_to_string(&output_path).unwrap();
+    assert_eq!(expect, output);
+
+    // teardown
+    std::fs::remove_file(msgpack_path).unwrap(); // remove output file
+    std::fs::remove_file(expect_path).unwrap(); // remove output file
+    std::fs::remove_file(output_path).unwrap(); // remove output file
+}
+
+#[test]
+/// Test export ufw.log with template, one rendered line per log
+fn ufw_log_to_template() {
+    let current_path = std::env::current_dir().unwrap();
+    let log_path = current_path.join("tests").join("fixtures").join("ufw.log");
+    let output_path = current_path.join("tests").join("test_ufw_log_output.txt");
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.arg("export").arg("template");
+    cmd.args([
+        "--format",
+        "{time} {policy:<6} {src}{?dpt} -> {dpt:>5}{/dpt}{!dpt} -> {dst|-}{/dpt}",
+    ]);
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.args(["-o", output_path.to_str().unwrap()]);
+    cmd.assert().success();
+    // check content
+    let input = std::fs::read_to_string(&log_path).unwrap();
+    let output = std::fs::read_to_string(&output_path).unwrap();
+    assert_eq!(output.lines().count(), input.lines().count());
+    assert!(output.contains("00:11:24 BLOCK  192.168.0.8 -> 42313\n"));
+
+    // teardown
+    std::fs::remove_file(output_path).unwrap(); // remove output file
+}
+
+#[test]
+#[cfg(feature = "parquet")]
+/// Test export ufw.log to parquet
+fn ufw_log_to_parquet() {
+    let current_path = std::env::current_dir().unwrap();
+    let log_path = current_path.join("tests").join("fixtures").join("ufw.log");
+    let output_path = current_path
+        .join("tests")
+        .join("test_ufw_log_output.parquet");
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.arg("export").arg("parquet");
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.args(["-o", output_path.to_str().unwrap()]);
+    cmd.args(["--compression", "zstd", "--row-group-size", "2"]);
+    cmd.assert().success();
+    // read back rows, one per log in row groups of 2
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::Field;
+    let reader = SerializedFileReader::new(std::fs::File::open(&output_path).unwrap()).unwrap();
+    let input = std::fs::read_to_string(&log_path).unwrap();
+    assert_eq!(
+        reader.metadata().num_row_groups(),
+        input.lines().count().div_ceil(2)
+    );
+    let rows = reader
+        .get_row_iter(None)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(rows.len(), input.lines().count());
+    // src and dpt of the second log
+    let column = |name: &str| {
+        rows[1]
+            .get_column_iter()
+            .find(|(column, _)| *column == name)
+            .map(|(_, field)| field.clone())
+            .unwrap()
+    };
+    assert_eq!(column("src"), Field::Str("192.168.0.8".to_string()));
+    assert_eq!(column("dpt"), Field::UShort(42313));
+
+    // teardown
+    std::fs::remove_file(output_path).unwrap(); // remove output file
+}
+
+#[test]
+#[cfg(feature = "xlsx")]
+/// Test export ufw.log to xlsx with summary sheet
+fn ufw_log_to_xlsx() {
+    let current_path = std::env::current_dir().unwrap();
+    let log_path = current_path.join("tests").join("fixtures").join("ufw.log");
+    let output_path = current_path.join("tests").join("test_ufw_log_output.xlsx");
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.arg("export").arg("xlsx");
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.args(["-o", output_path.to_str().unwrap()]);
+    cmd.args(["--summary", "--year", "2024"]);
+    cmd.assert().success();
+    // xlsx is a zip archive, read back the workbook and the event sheet
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(&output_path).unwrap()).unwrap();
+    let read = |archive: &mut zip::ZipArchive<std::fs::File>, name: &str| {
+        let mut content = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name(name).unwrap(), &mut content).unwrap();
+        content
+    };
+    let workbook = read(&mut archive, "xl/workbook.xml");
+    assert!(workbook.contains(r#"<sheet name="Events" sheetId="1""#));
+    assert!(workbook.contains(r#"<sheet name="Summary" sheetId="2""#));
+    let sheet = read(&mut archive, "xl/worksheets/sheet1.xml");
+    // header and one row per log
+    let rows = std::fs::read_to_string(&log_path).unwrap().lines().count() + 1;
+    assert!(sheet.contains(&format!(r#"<autoFilter ref="A1:AQ{rows}"/>"#)));
+    // dpt of the second log
+    assert!(sheet.contains(r#"<c r="U3"><v>42313</v></c>"#));
+
+    // teardown
+    std::fs::remove_file(output_path).unwrap(); // remove output file
+}
+
+#[test]
+#[cfg(feature = "sqlite")]
+/// Test export ufw.log to sqlite, then append the same log without overwrite
+fn ufw_log_to_sqlite_and_append() {
+    let current_path = std::env::current_dir().unwrap();
+    let log_path = current_path.join("tests").join("fixtures").join("ufw.log");
+    let output_path = current_path.join("tests").join("test_ufw_log_output.db");
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.arg("export").arg("sqlite");
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.args(["-o", output_path.to_str().unwrap()]);
+    cmd.assert().success();
+    let output = std::fs::read(&output_path).unwrap();
+    assert!(output.starts_with(b"SQLite format 3\0"));
+
+    // exists without append
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.arg("export").arg("sqlite");
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.args(["-o", output_path.to_str().unwrap()]);
+    cmd.assert().failure();
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.arg("export").arg("sqlite").arg("--append");
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.args(["-o", output_path.to_str().unwrap()]);
+    cmd.assert().success();
+
+    // teardown
+    std::fs::remove_file(output_path).unwrap(); // remove output file
+}
+
+#[test]
+/// Test export ufw.log to ecs
+fn ufw_log_to_ecs() {
+    let current_path = std::env::current_dir().unwrap();
+    let log_path = current_path.join("tests").join("fixtures").join("ufw.log");
+    let output_path = current_path
+        .join("tests")
+        .join("test_ufw_log_output_ecs.ndjson");
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.arg("export").arg("ecs").args(["--year", "2024"]);
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.args(["-o", output_path.to_str().unwrap()]);
+    cmd.assert().success();
+    // check content
+    let input = std::fs::read_to_string(&log_path).unwrap();
+    let output = std::fs::read_to_string(&output_path).unwrap();
+    assert_eq!(input.lines().count(), output.lines().count());
+    for line in output.lines() {
+        assert!(line.starts_with(r#"{"@timestamp":"2024-"#));
+        assert!(line.contains(r#""event":{"kind":"event""#));
+    }
+
+    // teardown
+    std::fs::remove_file(output_path).unwrap(); // remove output file
+}
+
+#[test]
+/// Test export ufw.log to cef with custom vendor and severity
+fn ufw_log_to_cef() {
+    let current_path = std::env::current_dir().unwrap();
+    let log_path = current_path.join("tests").join("fixtures").join("ufw.log");
+    let output_path = current_path.join("tests").join("test_ufw_log_output.cef");
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.arg("export").arg("cef");
+    cmd.args(["--vendor", "Acme", "--severity", "block=9,audit=0"]);
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.args(["-o", output_path.to_str().unwrap()]);
+    cmd.assert().success();
+    // check content
+    let output = std::fs::read_to_string(&output_path).unwrap();
+    for line in output.lines() {
+        assert!(line.starts_with("CEF:0|Acme|ufwlog|"));
+        if line.contains("|BLOCK|") {
+            assert!(line.contains("|UFW BLOCK|9|"));
+        }
+        if line.contains("|AUDIT|") {
+            assert!(line.contains("|UFW AUDIT|0|"));
+        }
+    }
+
+    // teardown
+    std::fs::remove_file(output_path).unwrap(); // remove output file
+}
+
+#[test]
+/// Test export ufw.log to zeek conn.log
+fn ufw_log_to_zeek() {
+    let current_path = std::env::current_dir().unwrap();
+    let log_path = current_path.join("tests").join("fixtures").join("ufw.log");
+    let output_path = current_path
+        .join("tests")
+        .join("test_ufw_log_output_conn.log");
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.arg("export").arg("zeek").args(["--year", "2024"]);
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.args(["-o", output_path.to_str().unwrap()]);
+    cmd.assert().success();
+    // check content
+    let input = std::fs::read_to_string(&log_path).unwrap();
+    let output = std::fs::read_to_string(&output_path).unwrap();
+    let rows = output.lines().filter(|line| !line.starts_with('#'));
+    assert_eq!(input.lines().count(), rows.clone().count());
+    for row in rows {
+        assert_eq!(row.split('\t').count(), 10);
+        assert!(row.starts_with("17"));
+    }
+    assert!(output.contains("\n#fields\tts\tid.orig_h\tid.orig_p\tid.resp_h\tid.resp_p\tproto\t"));
+
+    // teardown
+    std::fs::remove_file(output_path).unwrap(); // remove output file
+}
+
+#[test]
+/// Test export ufw.log to pcapng
+fn ufw_log_to_pcapng() {
+    let current_path = std::env::current_dir().unwrap();
+    let log_path = current_path.join("tests").join("fixtures").join("ufw.log");
+    let output_path = current_path
+        .join("tests")
+        .join("test_ufw_log_output.pcapng");
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.arg("export").arg("pcapng");
+    cmd.args(["-l", log_path.to_str().unwrap()]);
+    cmd.args(["-o", output_path.to_str().unwrap()]);
+    cmd.assert().success();
+    // count enhanced packet blocks
+    let input = std::fs::read_to_string(&log_path).unwrap();
+    let output = std::fs::read(&output_path).unwrap();
+    let mut offset = 0;
+    let mut packets = 0;
+    while offset < output.len() {
+        let block_type = u32::from_le_bytes(output[offset..offset + 4].try_into().unwrap());
+        let length = u32::from_le_bytes(output[offset + 4..offset + 8].try_into().unwrap());
+        if block_type == 6 {
+            packets += 1;
+        }
+        offset += length as usize;
+    }
+    assert_eq!(offset, output.len());
+    assert_eq!(input.lines().count(), packets);
+
+    // teardown
+    std::fs::remove_file(output_path).unwrap(); // remove output file
+}
+
+#[test]
+/// Test export stops at an invalid line, exits with failure and leaves no partial output file
+fn ufw_log_invalid_line_removes_output() {
+    let current_path = std::env::current_dir().unwrap();
+    let log_path = current_path.join("tests").join("fixtures").join("ufw.log");
+    let input_path = current_path.join("tests").join("test_invalid_input.log");
+    let output_path = current_path.join("tests").join("test_invalid_output.csv");
+    let mut input = std::fs::read_to_string(log_path).unwrap();
+    input.push_str("this is not a ufw log\n");
+    std::fs::write(&input_path, input).unwrap();
+
+    let mut cmd = Command::cargo_bin("ufwlog").unwrap();
+    cmd.arg("export");
+    cmd.args(["-l", input_path.to_str().unwrap()]);
+    cmd.args(["-o", output_path.to_str().unwrap()]);
+    let assert = cmd.assert().code(1);
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("Error occur when exporting"));
+    assert!(!stderr.contains("panicked"));
+    assert!(!output_path.exists());
+
+    // teardown
+    std::fs::remove_file(input_path).unwrap(); // remove input file
+}
@@ -0,0 +1,313 @@
+//! Convert logs into [Apache Arrow](https://arrow.apache.org/) record batches.
+//!
+//! The columns are named after the fields of [`UfwLog`], with typed values: integers for
+//! numbers, booleans for flags, dictionary-encoded strings for low-cardinality fields (hostname,
+//! interface, policy and protocol) and a `timestamp` column. See [`schema`] for details.
+//!
+//! This module requires `arrow` feature.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use std::io::BufReader;
+//! use ufwlog::arrow::RecordBatches;
+//! use ufwlog::UfwLog;
+//!
+//! let logs = UfwLog::from_file("./ufw.log")?;
+//! let batch = ufwlog::arrow::to_record_batch(&logs, Some(2024))?;
+//! assert_eq!(batch.num_rows(), logs.len());
+//!
+//! // or convert a large file batch by batch
+//! let reader = BufReader::new(std::fs::File::open("./ufw.log")?);
+//! for batch in RecordBatches::new(UfwLog::from_buf_reader(reader), 8192) {
+//!     println!("{} rows", batch?.num_rows());
+//! }
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use crate::error::Error;
+use crate::ufw_log::UfwLog;
+use arrow_array::types::Int32Type;
+use arrow_array::{
+    ArrayRef, BooleanArray, DictionaryArray, Float64Array, RecordBatch, StringArray,
+    TimestampMillisecondArray, UInt16Array, UInt32Array, UInt8Array,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use std::sync::Arc;
+
+/// Get the schema of record batches.
+///
+/// Columns are in the same order as the fields of [`UfwLog`], with an extra `timestamp` column
+/// (milliseconds in UTC) at first. Optional fields of [`UfwLog`] are nullable, and `timestamp`
+/// is null if the date is invalid. `uptime` is parsed as seconds.
+pub fn schema() -> SchemaRef {
+    let dictionary = DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+    Arc::new(Schema::new(vec![
+        Field::new(
+            "timestamp",
+            DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+            true,
+        ),
+        Field::new("month", DataType::UInt8, false),
+        Field::new("day", DataType::UInt8, false),
+        Field::new("time", DataType::Utf8, false),
+        Field::new("hostname", dictionary.clone(), false),
+        Field::new("uptime", DataType::Float64, true),
+        Field::new("policy", dictionary.clone(), false),
+        Field::new("in", dictionary.clone(), false),
+        Field::new("out", dictionary.clone(), false),
+        Field::new("mac", DataType::Utf8, false),
+        Field::new("src", DataType::Utf8, false),
+        Field::new("dst", DataType::Utf8, false),
+        Field::new("len", DataType::UInt32, false),
+        Field::new("tos", DataType::Utf8, true),
+        Field::new("prec", DataType::Utf8, true),
+        Field::new("ttl", DataType::UInt16, true),
+        Field::new("id", DataType::UInt32, true),
+        Field::new("df", DataType::Boolean, false),
+        Field::new("proto", dictionary.clone(), false),
+        Field::new("spt", DataType::UInt16, true),
+        Field::new("dpt", DataType::UInt16, true),
+        Field::new("window", DataType::UInt32, true),
+        Field::new("res", DataType::Utf8, false),
+        Field::new("cwr", DataType::Boolean, false),
+        Field::new("ece", DataType::Boolean, false),
+        Field::new("urg", DataType::Boolean, false),
+        Field::new("ack", DataType::Boolean, false),
+        Field::new("psh", DataType::Boolean, false),
+        Field::new("rst", DataType::Boolean, false),
+        Field::new("syn", DataType::Boolean, false),
+        Field::new("fin", DataType::Boolean, false),
+        Field::new("urgp", DataType::UInt16, true),
+        Field::new("tc", DataType::UInt8, true),
+        Field::new("hoplimit", DataType::UInt8, true),
+        Field::new("flowlbl", DataType::UInt32, true),
+        Field::new("type", DataType::UInt8, true),
+        Field::new("code", DataType::UInt8, true),
+        Field::new("seq", DataType::UInt32, true),
+        Field::new("mtu", DataType::UInt16, true),
+        Field::new("mark", DataType::UInt32, true),
+        Field::new("physin", dictionary.clone(), true),
+        Field::new("phyout", dictionary, true),
+        Field::new("origin", DataType::Utf8, false),
+    ]))
+}
+
+/// Convert logs into a record batch with [`schema`].
+///
+/// `year` is used to make `timestamp` column, see [`UfwLog::timestamp`].
+pub fn to_record_batch(logs: &[UfwLog], year: Option<i32>) -> Result<RecordBatch, Error> {
+    let string = |f: fn(&UfwLog) -> &str| -> ArrayRef {
+        Arc::new(logs.iter().map(|l| Some(f(l))).collect::<StringArray>())
+    };
+    let optional_string = |f: fn(&UfwLog) -> Option<&str>| -> ArrayRef {
+        Arc::new(logs.iter().map(f).collect::<StringArray>())
+    };
+    let dictionary = |f: fn(&UfwLog) -> Option<&str>| -> ArrayRef {
+        Arc::new(logs.iter().map(f).collect::<DictionaryArray<Int32Type>>())
+    };
+    let boolean = |f: fn(&UfwLog) -> bool| -> ArrayRef {
+        Arc::new(logs.iter().map(|l| Some(f(l))).collect::<BooleanArray>())
+    };
+    let u8 = |f: fn(&UfwLog) -> Option<u8>| -> ArrayRef {
+        Arc::new(logs.iter().map(f).collect::<UInt8Array>())
+    };
+    let u16 = |f: fn(&UfwLog) -> Option<u16>| -> ArrayRef {
+        Arc::new(logs.iter().map(f).collect::<UInt16Array>())
+    };
+    let u32 = |f: fn(&UfwLog) -> Option<u32>| -> ArrayRef {
+        Arc::new(logs.iter().map(f).collect::<UInt32Array>())
+    };
+    let policies = logs
+        .iter()
+        .map(|l| l.policy.to_string())
+        .collect::<Vec<_>>();
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(
+            logs.iter()
+                .map(|l| l.timestamp(year).map(|t| t * 1000))
+                .collect::<TimestampMillisecondArray>()
+                .with_timezone("UTC"),
+        ),
+        u8(|l| Some(l.month)),
+        u8(|l| Some(l.day)),
+        string(|l| &l.time),
+        dictionary(|l| Some(&l.hostname)),
+        Arc::new(
+            logs.iter()
+                .map(|l| l.uptime.trim().parse::<f64>().ok())
+                .collect::<Float64Array>(),
+        ),
+        Arc::new(
+            policies
+                .iter()
+                .map(|p| Some(p.as_str()))
+                .collect::<DictionaryArray<Int32Type>>(),
+        ),
+        dictionary(|l| Some(&l.r#in)),
+        dictionary(|l| Some(&l.out)),
+        string(|l| &l.mac),
+        string(|l| &l.src),
+        string(|l| &l.dst),
+        u32(|l| Some(l.len)),
+        optional_string(|l| l.tos.as_deref()),
+        optional_string(|l| l.prec.as_deref()),
+        u16(|l| l.ttl),
+        u32(|l| l.id),
+        boolean(|l| l.df),
+        dictionary(|l| Some(&l.proto)),
+        u16(|l| l.spt),
+        u16(|l| l.dpt),
+        u32(|l| l.window),
+        string(|l| &l.res),
+        boolean(|l| l.cwr),
+        boolean(|l| l.ece),
+        boolean(|l| l.urg),
+        boolean(|l| l.ack),
+        boolean(|l| l.psh),
+        boolean(|l| l.rst),
+        boolean(|l| l.syn),
+        boolean(|l| l.fin),
+        u16(|l| l.urgp),
+        u8(|l| l.tc),
+        u8(|l| l.hoplimit),
+        u32(|l| l.flowlbl),
+        u8(|l| l.r#type),
+        u8(|l| l.code),
+        u32(|l| l.seq),
+        u16(|l| l.mtu),
+        u32(|l| l.mark),
+        dictionary(|l| l.physin.as_deref()),
+        dictionary(|l| l.phyout.as_deref()),
+        string(|l| l.get_origin()),
+    ];
+    Ok(RecordBatch::try_new(schema(), columns)?)
+}
+
+/// Iterator adapter that converts logs into record batches of at most `batch_size` rows.
+///
+/// Only one batch of logs is kept in memory. Iteration stops after the first error.
+pub struct RecordBatches<I> {
+    logs: I,
+    batch_size: usize,
+    year: Option<i32>,
+    done: bool,
+}
+
+impl<I: Iterator<Item = Result<UfwLog, Error>>> RecordBatches<I> {
+    /// New an adapter of given logs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the batch size is zero.
+    pub fn new(logs: I, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "Batch size should be positive");
+        Self {
+            logs,
+            batch_size,
+            year: None,
+            done: false,
+        }
+    }
+
+    /// Set the year of logs, see [`to_record_batch`].
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+}
+
+impl<I: Iterator<Item = Result<UfwLog, Error>>> Iterator for RecordBatches<I> {
+    type Item = Result<RecordBatch, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut chunk = Vec::with_capacity(self.batch_size.min(8192));
+        while chunk.len() < self.batch_size {
+            match self.logs.next() {
+                Some(Ok(log)) => chunk.push(log),
+                Some(Err(e)) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+                None => {
+                    self.done = true;
+                    break;
+                }
+            }
+        }
+        if chunk.is_empty() {
+            return None;
+        }
+        Some(to_record_batch(&chunk, self.year))
+    }
+}
+
+impl From<arrow_schema::ArrowError> for Error {
+    fn from(value: arrow_schema::ArrowError) -> Self {
+        Self::Export(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float64Type, TimestampMillisecondType, UInt16Type};
+    use std::str::FromStr;
+
+    const LOG: &str = "Jan 16 02:13:52 hostname kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:09:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=42313 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    #[test]
+    fn test_to_record_batch() {
+        let logs = vec![UfwLog::from_str(LOG).unwrap()];
+        let batch = to_record_batch(&logs, Some(2024)).unwrap();
+        assert_eq!(batch.num_rows(), 1);
+        assert_eq!(batch.schema(), schema());
+        let timestamp = batch
+            .column_by_name("timestamp")
+            .unwrap()
+            .as_primitive::<TimestampMillisecondType>();
+        assert_eq!(timestamp.value(0), 1705371232000);
+        let dpt = batch
+            .column_by_name("dpt")
+            .unwrap()
+            .as_primitive::<UInt16Type>();
+        assert_eq!(dpt.value(0), 42313);
+        assert!(batch.column_by_name("syn").unwrap().as_boolean().value(0));
+        assert!(batch.column_by_name("tc").unwrap().is_null(0));
+        let uptime = batch
+            .column_by_name("uptime")
+            .unwrap()
+            .as_primitive::<Float64Type>();
+        assert_eq!(uptime.value(0), 1230.568282);
+        let policy = batch
+            .column_by_name("policy")
+            .unwrap()
+            .as_dictionary::<Int32Type>();
+        let values = policy.values().as_string::<i32>();
+        assert_eq!(values.value(policy.keys().value(0) as usize), "BLOCK");
+    }
+
+    #[test]
+    fn test_record_batches() {
+        let logs = std::iter::repeat_with(|| UfwLog::from_str(LOG)).take(5);
+        let rows = RecordBatches::new(logs, 2)
+            .map(|b| b.unwrap().num_rows())
+            .collect::<Vec<_>>();
+        assert_eq!(rows, vec![2, 2, 1]);
+
+        // stop after error
+        let logs = vec![
+            UfwLog::from_str(LOG),
+            Err(Error::Export("broken".to_string())),
+        ]
+        .into_iter();
+        let mut batches = RecordBatches::new(logs, 2);
+        assert!(batches.next().unwrap().is_err());
+        assert!(batches.next().is_none());
+    }
+}
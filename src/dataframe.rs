@@ -0,0 +1,165 @@
+//! Convert logs into [Polars](https://pola.rs/) data frame.
+//!
+//! The columns are the same as [arrow](crate::arrow) module, dictionary-encoded strings are
+//! `Categorical`, and `timestamp` is `Datetime` in milliseconds without time zone, the value is
+//! in UTC. Enable `lazy` feature of polars in your project to run group-bys and joins with lazy
+//! API.
+//!
+//! This module requires `polars` feature.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use ufwlog::UfwLog;
+//!
+//! let logs = UfwLog::from_file("./ufw.log")?;
+//! let df = ufwlog::dataframe::to_data_frame(&logs, None)?;
+//!
+//! // packets with SYN flag
+//! let mask = df.column("syn")?.bool()?.clone();
+//! let syn = df.filter(&mask)?;
+//! println!("{}", syn.head(Some(10)));
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use crate::error::Error;
+use crate::ufw_log::UfwLog;
+use polars::prelude::{
+    CategoricalOrdering, Column, DataFrame, DataType, NamedFrom, PolarsError, Series, TimeUnit,
+};
+
+/// Convert logs into a data frame.
+///
+/// `year` is used to make `timestamp` column, see [`UfwLog::timestamp`].
+pub fn to_data_frame(logs: &[UfwLog], year: Option<i32>) -> Result<DataFrame, Error> {
+    let string = |name: &str, f: fn(&UfwLog) -> &str| -> Column {
+        Series::new(name.into(), logs.iter().map(f).collect::<Vec<_>>()).into()
+    };
+    let optional_string = |name: &str, f: fn(&UfwLog) -> Option<&str>| -> Column {
+        Series::new(name.into(), logs.iter().map(f).collect::<Vec<_>>()).into()
+    };
+    let categorical = |name: &str, f: fn(&UfwLog) -> Option<&str>| -> Result<Column, Error> {
+        Ok(
+            Series::new(name.into(), logs.iter().map(f).collect::<Vec<_>>())
+                .cast(&DataType::Categorical(None, CategoricalOrdering::Physical))?
+                .into(),
+        )
+    };
+    let boolean = |name: &str, f: fn(&UfwLog) -> bool| -> Column {
+        Series::new(name.into(), logs.iter().map(f).collect::<Vec<_>>()).into()
+    };
+    let u8 = |name: &str, f: fn(&UfwLog) -> Option<u8>| -> Column {
+        Series::new(name.into(), logs.iter().map(f).collect::<Vec<_>>()).into()
+    };
+    let u16 = |name: &str, f: fn(&UfwLog) -> Option<u16>| -> Column {
+        Series::new(name.into(), logs.iter().map(f).collect::<Vec<_>>()).into()
+    };
+    let u32 = |name: &str, f: fn(&UfwLog) -> Option<u32>| -> Column {
+        Series::new(name.into(), logs.iter().map(f).collect::<Vec<_>>()).into()
+    };
+    let policies = logs
+        .iter()
+        .map(|l| l.policy.to_string())
+        .collect::<Vec<_>>();
+
+    let columns = vec![
+        Series::new(
+            "timestamp".into(),
+            logs.iter()
+                .map(|l| l.timestamp(year).map(|t| t * 1000))
+                .collect::<Vec<_>>(),
+        )
+        .cast(&DataType::Datetime(TimeUnit::Milliseconds, None))?
+        .into(),
+        u8("month", |l| Some(l.month)),
+        u8("day", |l| Some(l.day)),
+        string("time", |l| &l.time),
+        categorical("hostname", |l| Some(&l.hostname))?,
+        Series::new(
+            "uptime".into(),
+            logs.iter()
+                .map(|l| l.uptime.trim().parse::<f64>().ok())
+                .collect::<Vec<_>>(),
+        )
+        .into(),
+        Series::new("policy".into(), &policies)
+            .cast(&DataType::Categorical(None, CategoricalOrdering::Physical))?
+            .into(),
+        categorical("in", |l| Some(&l.r#in))?,
+        categorical("out", |l| Some(&l.out))?,
+        string("mac", |l| &l.mac),
+        string("src", |l| &l.src),
+        string("dst", |l| &l.dst),
+        u32("len", |l| Some(l.len)),
+        optional_string("tos", |l| l.tos.as_deref()),
+        optional_string("prec", |l| l.prec.as_deref()),
+        u16("ttl", |l| l.ttl),
+        u32("id", |l| l.id),
+        boolean("df", |l| l.df),
+        categorical("proto", |l| Some(&l.proto))?,
+        u16("spt", |l| l.spt),
+        u16("dpt", |l| l.dpt),
+        u32("window", |l| l.window),
+        string("res", |l| &l.res),
+        boolean("cwr", |l| l.cwr),
+        boolean("ece", |l| l.ece),
+        boolean("urg", |l| l.urg),
+        boolean("ack", |l| l.ack),
+        boolean("psh", |l| l.psh),
+        boolean("rst", |l| l.rst),
+        boolean("syn", |l| l.syn),
+        boolean("fin", |l| l.fin),
+        u16("urgp", |l| l.urgp),
+        u8("tc", |l| l.tc),
+        u8("hoplimit", |l| l.hoplimit),
+        u32("flowlbl", |l| l.flowlbl),
+        u8("type", |l| l.r#type),
+        u8("code", |l| l.code),
+        u32("seq", |l| l.seq),
+        u16("mtu", |l| l.mtu),
+        u32("mark", |l| l.mark),
+        categorical("physin", |l| l.physin.as_deref())?,
+        categorical("phyout", |l| l.phyout.as_deref())?,
+        string("origin", |l| l.get_origin()),
+    ];
+    Ok(DataFrame::new(columns)?)
+}
+
+impl From<PolarsError> for Error {
+    fn from(value: PolarsError) -> Self {
+        Self::Export(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const LOG: &str = "Jan 16 02:13:52 hostname kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:09:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=42313 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    #[test]
+    fn test_to_data_frame() {
+        let logs = vec![
+            UfwLog::from_str(LOG).unwrap(),
+            UfwLog::from_str(LOG).unwrap(),
+        ];
+        let df = to_data_frame(&logs, Some(2024)).unwrap();
+        assert_eq!(df.shape(), (2, 43));
+        assert_eq!(df.column("dpt").unwrap().u16().unwrap().get(0), Some(42313));
+        assert_eq!(df.column("syn").unwrap().bool().unwrap().get(1), Some(true));
+        assert_eq!(df.column("tc").unwrap().null_count(), 2);
+        assert_eq!(
+            df.column("timestamp").unwrap().dtype(),
+            &DataType::Datetime(TimeUnit::Milliseconds, None)
+        );
+        assert_eq!(
+            df.column("timestamp").unwrap().datetime().unwrap().get(0),
+            Some(1705371232000)
+        );
+        assert!(matches!(
+            df.column("hostname").unwrap().dtype(),
+            DataType::Categorical(..)
+        ));
+    }
+}
@@ -1,12 +1,22 @@
 use crate::ufw_log::ParseError;
 use std::fmt::{Display, Formatter};
 
+/// Errors of parsing, importing and exporting logs.
+///
+/// New variants may be added without a breaking release.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// A log line could not be parsed.
     Parse(ParseError),
     /// An I/O error occurred while reading or writing.
     Io(std::io::Error),
+    /// The logs could not be exported, e.g. the encoder of a binary format failed.
+    Export(String),
+    /// An HTTP request failed after retries, or its response is unexpected.
+    Http(String),
+    /// The operation isn't supported by the format, e.g. convert a log to binary format.
+    Unsupported(String),
 }
 
 impl Display for Error {
@@ -21,6 +31,9 @@ impl Display for Error {
                 }
             },
             Error::Io(e) => write!(f, "IO error: {}", e),
+            Error::Export(e) => write!(f, "Export error: {}", e),
+            Error::Http(e) => write!(f, "HTTP error: {}", e),
+            Error::Unsupported(e) => write!(f, "Unsupported: {}", e),
         }
     }
 }
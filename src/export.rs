@@ -10,7 +10,8 @@
 //! let target = "csv";
 //!
 //! let exporter: Box<dyn ufwlog::export::Export> = match target {
-//!     "csv" => Box::new(ufwlog::export::csv::Exporter),
+//!     "csv" => Box::new(ufwlog::export::csv::Exporter::default()),
+//!     "json" => Box::new(ufwlog::export::json::Exporter),
 //!     _ => unimplemented!(),
 //! };
 //! // write to stdout
@@ -20,23 +21,80 @@
 //!
 //! # Ok::<(), ufwlog::error::Error>(())
 //! ```
+//!
+//! Or stream logs from a reader with constant memory, see [`Export::export_iter`].
 
 use crate::error::Error;
 
+pub mod cef;
+pub mod column;
 pub mod csv;
+pub mod ecs;
+#[cfg(feature = "http")]
+pub mod elasticsearch;
+pub mod gelf;
+pub mod influx;
+pub mod json;
+pub mod leef;
+#[cfg(feature = "http")]
+pub mod loki;
+pub mod msgpack;
+pub mod ndjson;
+pub mod otlp;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod pcapng;
+pub mod splunk;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod template;
+#[cfg(feature = "xlsx")]
+pub mod xlsx;
+pub mod zeek;
 
 /// Supported export formats
+///
+/// Some variants depend on features, so new variants may appear without a breaking release.
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum Format {
     Csv,
+    Json,
+    Ndjson,
+    Ecs,
+    Cef,
+    Leef,
+    Gelf,
+    Influx,
+    Otlp,
+    Splunk,
+    Zeek,
+    Pcapng,
+    Msgpack,
+    Template,
+    #[cfg(feature = "parquet")]
+    Parquet,
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+    #[cfg(feature = "xlsx")]
+    Xlsx,
 }
 
 /// Defines the interface for exporting UFW logs into a specific format.
 ///
+/// Export is split into three steps: [`begin`](Export::begin) writes the content before the
+/// first record (e.g. header row), [`write_record`](Export::write_record) writes each record and
+/// [`finish`](Export::finish) writes the content after the last record (e.g. closing bracket).
+/// So that [`export_iter`](Export::export_iter) can stream logs from an iterator, such as
+/// [`UfwLog::from_buf_reader`](crate::UfwLog::from_buf_reader), with constant memory.
+///
 /// # Implementing
 ///
-/// Types that implement this trait should be zero-size structs, as they
-/// typically hold no state.
+/// Types that implement this trait should not hold state between calls, they only carry the
+/// options of output format (e.g. delimiter of csv).
+///
+/// Only [`get_extension`](Export::get_extension) and [`convert`](Export::convert) are required,
+/// the default implementation writes one converted record per line without header or footer.
 pub trait Export {
     /// Get the extension of this format. (e.g. csv, json)
     fn get_extension(&self) -> &'static str;
@@ -44,6 +102,94 @@ pub trait Export {
     /// convert a single log entry into a formatted string.
     fn convert(&self, log: &crate::UfwLog) -> Result<String, Error>;
 
+    /// Write the content before the first record, e.g. header row.
+    fn begin(&self, writer: &mut dyn std::io::Write) -> Result<(), Error> {
+        let _ = writer;
+        Ok(())
+    }
+
+    /// Write a single record.
+    ///
+    /// `index` is the zero-based position of the record in output, which can be used to write
+    /// separator between records.
+    fn write_record(
+        &self,
+        index: usize,
+        log: &crate::UfwLog,
+        writer: &mut dyn std::io::Write,
+    ) -> Result<(), Error> {
+        let _ = index;
+        writeln!(writer, "{}", self.convert(log)?)?;
+        Ok(())
+    }
+
+    /// Write the content after the last record, e.g. closing bracket, then flush the writer.
+    fn finish(&self, writer: &mut dyn std::io::Write) -> Result<(), Error> {
+        Ok(writer.flush()?)
+    }
+
     /// Export log entries into a writer.
-    fn export(&self, logs: &[crate::UfwLog], writer: &mut dyn std::io::Write) -> Result<(), Error>;
+    fn export(&self, logs: &[crate::UfwLog], writer: &mut dyn std::io::Write) -> Result<(), Error> {
+        self.begin(writer)?;
+        for (index, log) in logs.iter().enumerate() {
+            self.write_record(index, log, writer)?;
+        }
+        self.finish(writer)
+    }
+
+    /// Export log entries from an iterator into a writer, and return how many records are
+    /// exported.
+    ///
+    /// Logs are written as soon as they are read, so memory usage doesn't grow with input size.
+    /// It stops at the first error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust, no_run
+    /// use std::io::BufReader;
+    /// use ufwlog::export::Export;
+    /// use ufwlog::UfwLog;
+    ///
+    /// let reader = BufReader::new(std::fs::File::open("./ufw.log")?);
+    /// let mut logs = UfwLog::from_buf_reader(reader);
+    /// let mut writer = std::io::BufWriter::new(std::fs::File::create("output.json")?);
+    /// let count = ufwlog::export::json::Exporter.export_iter(&mut logs, &mut writer)?;
+    /// println!("{count} records exported");
+    ///
+    /// # std::fs::remove_file("output.json").unwrap();
+    /// # Ok::<(), ufwlog::error::Error>(())
+    /// ```
+    fn export_iter(
+        &self,
+        logs: &mut dyn Iterator<Item = Result<crate::UfwLog, Error>>,
+        writer: &mut dyn std::io::Write,
+    ) -> Result<usize, Error> {
+        self.begin(writer)?;
+        let mut count = 0;
+        for log in logs {
+            self.write_record(count, &log?, writer)?;
+            count += 1;
+        }
+        self.finish(writer)?;
+        Ok(count)
+    }
+}
+
+/// 64-bit FNV-1a hash, which is stable across versions and platforms.
+#[cfg_attr(not(any(feature = "http", feature = "sqlite")), allow(dead_code))]
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
 }
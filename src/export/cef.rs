@@ -0,0 +1,260 @@
+//! CEF Exporter module
+//!
+//! Export logs as ArcSight [Common Event Format](https://www.microfocus.com/documentation/arcsight/arcsight-smartconnectors/pdfdoc/common-event-format-v25/common-event-format-v25.pdf),
+//! one event per line:
+//!
+//! ```text
+//! CEF:0|ufw|ufwlog|0.3.0|BLOCK|UFW BLOCK|5|rt=1705371232000 dvchost=myhost act=BLOCK src=192.168.0.8 ...
+//! ```
+//!
+//! Pipes and backslashes in header, and equal signs, backslashes and line breaks in extension
+//! are escaped. Device vendor, product and severity of each policy are configurable.
+//!
+//! The packet length `LEN` is written as custom number `cn1` labelled `len`, since `in` of CEF
+//! is bytes received by the connection. TCP flags are written as custom string `cs1` labelled
+//! `flags`.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use ufwlog::UfwLog;
+//! use ufwlog::export::Export;
+//! use ufwlog::export::cef::{Exporter as CefExporter, Severity};
+//!
+//! let logs = UfwLog::from_file("./ufw.log")?;
+//! let exporter = CefExporter::new()
+//!     .vendor("Example")
+//!     .product("Edge Firewall")
+//!     .severity(Severity { block: 7, ..Severity::default() });
+//! exporter.export(&logs, &mut std::io::stdout())?;
+//!
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::column::Field;
+use crate::error::Error;
+use crate::ufw_log::{Policy, UfwLog};
+
+/// Severity of each policy, from 0 (lowest) to 10 (highest)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Severity {
+    pub unknown: u8,
+    pub block: u8,
+    pub allow: u8,
+    pub audit: u8,
+    pub audit_invalid: u8,
+    pub limit_block: u8,
+}
+
+impl Default for Severity {
+    fn default() -> Self {
+        Self {
+            unknown: 3,
+            block: 5,
+            allow: 1,
+            audit: 2,
+            audit_invalid: 4,
+            limit_block: 6,
+        }
+    }
+}
+
+impl Severity {
+    /// Get the severity of given policy, a value above 10 is clamped to 10.
+    pub fn get(&self, policy: Policy) -> u8 {
+        let severity = match policy {
+            Policy::Unknown => self.unknown,
+            Policy::Block => self.block,
+            Policy::Allow => self.allow,
+            Policy::Audit => self.audit,
+            Policy::AuditInvalid => self.audit_invalid,
+            Policy::LimitBlock => self.limit_block,
+        };
+        severity.min(10)
+    }
+
+    /// Set the severity of given policy, a value above 10 is clamped to 10.
+    pub fn set(&mut self, policy: Policy, severity: u8) {
+        *match policy {
+            Policy::Unknown => &mut self.unknown,
+            Policy::Block => &mut self.block,
+            Policy::Allow => &mut self.allow,
+            Policy::Audit => &mut self.audit,
+            Policy::AuditInvalid => &mut self.audit_invalid,
+            Policy::LimitBlock => &mut self.limit_block,
+        } = severity.min(10);
+    }
+}
+
+/// Exporter for CEF format
+///
+/// Default device vendor is `ufw`, and device product is `ufwlog`.
+#[derive(Debug, Clone)]
+pub struct Exporter {
+    vendor: String,
+    product: String,
+    severity: Severity,
+    year: Option<i32>,
+}
+
+impl Default for Exporter {
+    fn default() -> Self {
+        Self {
+            vendor: "ufw".to_string(),
+            product: "ufwlog".to_string(),
+            severity: Severity::default(),
+            year: None,
+        }
+    }
+}
+
+impl super::Export for Exporter {
+    fn get_extension(&self) -> &'static str {
+        "cef"
+    }
+
+    fn convert(&self, log: &UfwLog) -> Result<String, Error> {
+        let policy = log.policy.to_string();
+        let header = [
+            self.vendor.as_str(),
+            self.product.as_str(),
+            env!("CARGO_PKG_VERSION"),
+            &policy,
+            &format!("UFW {policy}"),
+        ]
+        .map(escape_header)
+        .join("|");
+
+        let flags = Field::Flags.value(log);
+        let extension = [
+            (
+                "rt",
+                log.timestamp(self.year).map(|t| (t * 1000).to_string()),
+            ),
+            ("dvchost", Some(log.hostname.clone())),
+            ("act", Some(policy.clone())),
+            ("src", Some(log.src.clone())),
+            ("dst", Some(log.dst.clone())),
+            ("spt", log.spt.map(|p| p.to_string())),
+            ("dpt", log.dpt.map(|p| p.to_string())),
+            ("proto", Some(log.proto.clone())),
+            ("cn1Label", Some("len".to_string())),
+            ("cn1", Some(log.len.to_string())),
+            ("deviceInboundInterface", Some(log.r#in.clone())),
+            ("deviceOutboundInterface", Some(log.out.clone())),
+            ("cs1Label", (!flags.is_empty()).then(|| "flags".to_string())),
+            ("cs1", Some(flags)),
+            ("rawEvent", Some(log.get_origin().to_string())),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| match value {
+            Some(value) if !value.is_empty() => Some(format!("{key}={}", escape_extension(&value))),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+        Ok(format!(
+            "CEF:0|{header}|{}|{extension}",
+            self.severity.get(log.policy)
+        ))
+    }
+}
+
+impl Exporter {
+    /// New an exporter with default config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the device vendor.
+    pub fn vendor(mut self, vendor: &str) -> Self {
+        self.vendor = vendor.to_string();
+        self
+    }
+
+    /// Set the device product.
+    pub fn product(mut self, product: &str) -> Self {
+        self.product = product.to_string();
+        self
+    }
+
+    /// Set the severity of each policy.
+    pub fn severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Set the year of logs, which is used to make `rt` (receipt time). See
+    /// [`UfwLog::timestamp`] for the default.
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+}
+
+/// Escape pipe and backslash in header field.
+fn escape_header(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+/// Escape equal sign, backslash and line break in extension value.
+fn escape_extension(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('=', "\\=")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::Export;
+    use std::str::FromStr;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW LIMIT BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN ACK URGP=0";
+
+    #[test]
+    fn test_convert() {
+        let log = UfwLog::from_str(LOG).unwrap();
+        let cef = Exporter::new().year(2024).convert(&log).unwrap();
+        assert!(cef.starts_with(&format!(
+            "CEF:0|ufw|ufwlog|{}|LIMIT BLOCK|UFW LIMIT BLOCK|6|",
+            env!("CARGO_PKG_VERSION")
+        )));
+        assert!(cef.contains("|rt=1705371232000 dvchost=myhost act=LIMIT BLOCK src=192.168.0.8 dst=127.0.111.111 spt=46468 dpt=22 proto=TCP cn1Label=len cn1=40 deviceInboundInterface=eth0 cs1Label=flags cs1=SYN ACK rawEvent=Jan 16"));
+        // escaped equal sign in raw event
+        assert!(cef.contains("IN\\=eth0 OUT\\= MAC\\="));
+    }
+
+    #[test]
+    fn test_config() {
+        let log = UfwLog::from_str(LOG).unwrap();
+        let mut severity = Severity::default();
+        severity.set(Policy::LimitBlock, 9);
+        let cef = Exporter::new()
+            .vendor("Acme|Corp")
+            .product("Fire\\wall")
+            .severity(severity.clone())
+            .convert(&log)
+            .unwrap();
+        assert!(cef.starts_with("CEF:0|Acme\\|Corp|Fire\\\\wall|"));
+        assert!(cef.contains("|UFW LIMIT BLOCK|9|"));
+
+        severity.set(Policy::Block, 200);
+        assert_eq!(severity.get(Policy::Block), 10);
+        let severity = Severity {
+            allow: 11,
+            ..Severity::default()
+        };
+        assert_eq!(severity.get(Policy::Allow), 10);
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape_header("a|b\\c=d"), "a\\|b\\\\c=d");
+        assert_eq!(escape_extension("a|b\\c=d\r\ne\n"), "a|b\\\\c\\=d\\ne\\n");
+    }
+}
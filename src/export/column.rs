@@ -0,0 +1,480 @@
+//! Column specification for tabular formats (e.g. csv).
+//!
+//! By default, tabular exporters output all fields in [`HEADER`](super::csv::HEADER) order. Use
+//! [`Columns`] to select, reorder and rename columns, and to choose how TCP flags are rendered.
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use std::str::FromStr;
+//! use ufwlog::export::column::{Columns, FlagStyle};
+//!
+//! // pick and rename columns, the syntax is `field` or `field=Header`
+//! let columns = Columns::from_str("time,src=Source,dst=Destination,dpt,policy,flags")?
+//!     .flag_style(FlagStyle::Separate);
+//! assert_eq!(
+//!     columns.header(),
+//!     ["Time", "Source", "Destination", "DPT", "policy", "SYN", "ACK", "FIN", "RST", "PSH", "CWR", "ECE", "URG"]
+//! );
+//! # Ok::<(), String>(())
+//! ```
+
+use crate::ufw_log::UfwLog;
+use std::str::FromStr;
+
+/// A field of [`UfwLog`] which can be a column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Month,
+    Day,
+    Time,
+    Hostname,
+    Uptime,
+    Policy,
+    In,
+    Out,
+    Mac,
+    Src,
+    Dst,
+    Len,
+    Tos,
+    Prec,
+    Ttl,
+    Id,
+    Df,
+    Proto,
+    Spt,
+    Dpt,
+    Window,
+    Res,
+    /// All TCP control bits / flags, see [`FlagStyle`] for how it is rendered.
+    Flags,
+    Syn,
+    Ack,
+    Fin,
+    Rst,
+    Psh,
+    Cwr,
+    Ece,
+    Urg,
+    Urgp,
+    Tc,
+    Hoplimit,
+    Flowlbl,
+    Type,
+    Code,
+    Seq,
+    Mtu,
+    Mark,
+    Physin,
+    Phyout,
+    Origin,
+}
+
+/// TCP flags in the order they are rendered.
+const FLAGS: [Field; 8] = [
+    Field::Syn,
+    Field::Ack,
+    Field::Fin,
+    Field::Rst,
+    Field::Psh,
+    Field::Cwr,
+    Field::Ece,
+    Field::Urg,
+];
+
+/// Fields of default columns, same order as [`HEADER`](super::csv::HEADER).
+const DEFAULT_FIELDS: [Field; 35] = [
+    Field::Month,
+    Field::Day,
+    Field::Time,
+    Field::Hostname,
+    Field::Uptime,
+    Field::Policy,
+    Field::In,
+    Field::Out,
+    Field::Mac,
+    Field::Src,
+    Field::Dst,
+    Field::Len,
+    Field::Tos,
+    Field::Prec,
+    Field::Ttl,
+    Field::Id,
+    Field::Df,
+    Field::Proto,
+    Field::Spt,
+    Field::Dpt,
+    Field::Window,
+    Field::Res,
+    Field::Flags,
+    Field::Urgp,
+    Field::Tc,
+    Field::Hoplimit,
+    Field::Flowlbl,
+    Field::Type,
+    Field::Code,
+    Field::Seq,
+    Field::Mtu,
+    Field::Mark,
+    Field::Physin,
+    Field::Phyout,
+    Field::Origin,
+];
+
+impl Field {
+    /// The name used to reference this field, same as the field name of [`UfwLog`].
+    pub fn name(&self) -> &'static str {
+        match self {
+            Field::Month => "month",
+            Field::Day => "day",
+            Field::Time => "time",
+            Field::Hostname => "hostname",
+            Field::Uptime => "uptime",
+            Field::Policy => "policy",
+            Field::In => "in",
+            Field::Out => "out",
+            Field::Mac => "mac",
+            Field::Src => "src",
+            Field::Dst => "dst",
+            Field::Len => "len",
+            Field::Tos => "tos",
+            Field::Prec => "prec",
+            Field::Ttl => "ttl",
+            Field::Id => "id",
+            Field::Df => "df",
+            Field::Proto => "proto",
+            Field::Spt => "spt",
+            Field::Dpt => "dpt",
+            Field::Window => "window",
+            Field::Res => "res",
+            Field::Flags => "flags",
+            Field::Syn => "syn",
+            Field::Ack => "ack",
+            Field::Fin => "fin",
+            Field::Rst => "rst",
+            Field::Psh => "psh",
+            Field::Cwr => "cwr",
+            Field::Ece => "ece",
+            Field::Urg => "urg",
+            Field::Urgp => "urgp",
+            Field::Tc => "tc",
+            Field::Hoplimit => "hoplimit",
+            Field::Flowlbl => "flowlbl",
+            Field::Type => "type",
+            Field::Code => "code",
+            Field::Seq => "seq",
+            Field::Mtu => "mtu",
+            Field::Mark => "mark",
+            Field::Physin => "physin",
+            Field::Phyout => "phyout",
+            Field::Origin => "origin",
+        }
+    }
+
+    /// The default header of this field, same as [`HEADER`](super::csv::HEADER).
+    pub fn header(&self) -> &'static str {
+        match DEFAULT_FIELDS.iter().position(|f| f == self) {
+            Some(i) => super::csv::HEADER[i],
+            // flags are not in default columns
+            None => match self {
+                Field::Syn => "SYN",
+                Field::Ack => "ACK",
+                Field::Fin => "FIN",
+                Field::Rst => "RST",
+                Field::Psh => "PSH",
+                Field::Cwr => "CWR",
+                Field::Ece => "ECE",
+                _ => "URG",
+            },
+        }
+    }
+
+    /// Get the value of this field from log as string.
+    ///
+    /// Absent optional value is an empty string. `df` is `DF` or empty, single TCP flag is
+    /// `true` or `false` and [`Field::Flags`] is the set flags joined by space, e.g. `SYN ACK`.
+    pub fn value(&self, log: &UfwLog) -> String {
+        match self {
+            Field::Month => log.month.to_string(),
+            Field::Day => log.day.to_string(),
+            Field::Time => log.time.clone(),
+            Field::Hostname => log.hostname.clone(),
+            Field::Uptime => log.uptime.clone(),
+            Field::Policy => log.policy.to_string(),
+            Field::In => log.r#in.clone(),
+            Field::Out => log.out.clone(),
+            Field::Mac => log.mac.clone(),
+            Field::Src => log.src.clone(),
+            Field::Dst => log.dst.clone(),
+            Field::Len => log.len.to_string(),
+            Field::Tos => unwrap_or_empty_then_to_string(log.tos.as_ref()),
+            Field::Prec => unwrap_or_empty_then_to_string(log.prec.as_ref()),
+            Field::Ttl => unwrap_or_empty_then_to_string(log.ttl),
+            Field::Id => unwrap_or_empty_then_to_string(log.id),
+            Field::Df => {
+                if log.df {
+                    "DF".to_string()
+                } else {
+                    "".to_string()
+                }
+            }
+            Field::Proto => log.proto.clone(),
+            Field::Spt => unwrap_or_empty_then_to_string(log.spt),
+            Field::Dpt => unwrap_or_empty_then_to_string(log.dpt),
+            Field::Window => unwrap_or_empty_then_to_string(log.window),
+            Field::Res => log.res.clone(),
+            Field::Flags => FLAGS
+                .iter()
+                .filter(|flag| flag.flag(log))
+                .map(|flag| flag.header())
+                .collect::<Vec<_>>()
+                .join(" "),
+            Field::Syn
+            | Field::Ack
+            | Field::Fin
+            | Field::Rst
+            | Field::Psh
+            | Field::Cwr
+            | Field::Ece
+            | Field::Urg => self.flag(log).to_string(),
+            // The value follows the flag, so it is empty when it does not appear, and it depends on the record value when it appears
+            Field::Urgp => unwrap_or_empty_then_to_string(log.urgp),
+            Field::Tc => unwrap_or_empty_then_to_string(log.tc),
+            Field::Hoplimit => unwrap_or_empty_then_to_string(log.hoplimit),
+            Field::Flowlbl => unwrap_or_empty_then_to_string(log.flowlbl),
+            Field::Type => unwrap_or_empty_then_to_string(log.r#type),
+            Field::Code => unwrap_or_empty_then_to_string(log.code),
+            Field::Seq => unwrap_or_empty_then_to_string(log.seq),
+            Field::Mtu => unwrap_or_empty_then_to_string(log.mtu),
+            Field::Mark => unwrap_or_empty_then_to_string(log.mark),
+            Field::Physin => unwrap_or_empty_then_to_string(log.physin.as_ref()),
+            Field::Phyout => unwrap_or_empty_then_to_string(log.phyout.as_ref()),
+            Field::Origin => log.get_origin().to_string(),
+        }
+    }
+
+    /// Whether the TCP flag is set, always false if this field is not a single flag.
+    fn flag(&self, log: &UfwLog) -> bool {
+        match self {
+            Field::Syn => log.syn,
+            Field::Ack => log.ack,
+            Field::Fin => log.fin,
+            Field::Rst => log.rst,
+            Field::Psh => log.psh,
+            Field::Cwr => log.cwr,
+            Field::Ece => log.ece,
+            Field::Urg => log.urg,
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    /// Parse field by its [name](Field::name) or default [header](Field::header), case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        DEFAULT_FIELDS
+            .iter()
+            .chain(FLAGS.iter())
+            .find(|f| f.name().eq_ignore_ascii_case(s) || f.header().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or(format!("Unknown field: {s}"))
+    }
+}
+
+/// How TCP flags ([`Field::Flags`]) are rendered.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FlagStyle {
+    /// One cell contains all set flags joined by space, e.g. `SYN ACK`.
+    #[default]
+    Combined,
+    /// One boolean column per flag.
+    Separate,
+}
+
+/// A column of tabular output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub field: Field,
+    /// Header of this column.
+    pub name: String,
+}
+
+impl From<Field> for Column {
+    fn from(field: Field) -> Self {
+        Self {
+            field,
+            name: field.header().to_string(),
+        }
+    }
+}
+
+/// Selected columns of tabular output, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Columns {
+    selected: Vec<Column>,
+    flag_style: FlagStyle,
+    /// `selected` with [`Field::Flags`] expanded by `flag_style`, resolved once for every row.
+    columns: Vec<Column>,
+}
+
+impl Default for Columns {
+    /// All fields in [`HEADER`](super::csv::HEADER) order with flags combined.
+    fn default() -> Self {
+        Self::new(DEFAULT_FIELDS.iter().map(|f| Column::from(*f)).collect())
+    }
+}
+
+impl Columns {
+    /// New columns with given order, flags are combined.
+    pub fn new(columns: Vec<Column>) -> Self {
+        Self {
+            selected: columns.clone(),
+            columns,
+            flag_style: FlagStyle::default(),
+        }
+    }
+
+    /// Set how [`Field::Flags`] is rendered.
+    ///
+    /// If flags are separated and the flags column is renamed, the name is used as prefix of
+    /// each flag column, e.g. `flags=TCP` becomes `TCP SYN`, `TCP ACK` and so on.
+    pub fn flag_style(mut self, flag_style: FlagStyle) -> Self {
+        self.flag_style = flag_style;
+        self.columns = self
+            .selected
+            .iter()
+            .flat_map(|column| match (column.field, flag_style) {
+                (Field::Flags, FlagStyle::Separate) => FLAGS
+                    .iter()
+                    .map(|flag| {
+                        let mut flag = Column::from(*flag);
+                        if column.name != Field::Flags.header() {
+                            flag.name = format!("{} {}", column.name, flag.name);
+                        }
+                        flag
+                    })
+                    .collect(),
+                _ => vec![column.clone()],
+            })
+            .collect();
+        self
+    }
+
+    /// Get the selected columns, [`Field::Flags`] is expanded if flags are separated.
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Get the header row.
+    pub fn header(&self) -> Vec<String> {
+        self.columns.iter().map(|c| c.name.clone()).collect()
+    }
+
+    /// Get the values of a log in column order.
+    pub fn row(&self, log: &UfwLog) -> Vec<String> {
+        self.columns
+            .iter()
+            .map(|column| column.field.value(log))
+            .collect()
+    }
+}
+
+impl FromStr for Columns {
+    type Err = String;
+
+    /// Parse comma separated columns, each column is `field` or `field=Header`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .filter(|column| !column.trim().is_empty())
+            .map(|column| match column.split_once('=') {
+                Some((field, name)) => Ok(Column {
+                    field: field.parse()?,
+                    name: name.trim().to_string(),
+                }),
+                None => Ok(Column::from(column.parse::<Field>()?)),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .and_then(|columns| {
+                if columns.is_empty() {
+                    Err("No column is selected".to_string())
+                } else {
+                    Ok(Columns::new(columns))
+                }
+            })
+    }
+}
+
+/// If value is none, return empty string, else return value that convert to string
+fn unwrap_or_empty_then_to_string<T: ToString>(value: Option<T>) -> String {
+    value.map_or("".to_string(), |v| v.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    mod test_unwrap_or_empty_then_to_string {
+        use super::super::unwrap_or_empty_then_to_string;
+
+        #[test]
+        fn test_input_none() {
+            assert_eq!(
+                unwrap_or_empty_then_to_string::<String>(None),
+                "".to_string()
+            );
+        }
+
+        #[test]
+        fn test_input_unsigned_integer() {
+            assert_eq!(unwrap_or_empty_then_to_string(Some(443)), "443".to_string());
+        }
+
+        #[test]
+        fn test_input_singed_integer() {
+            assert_eq!(unwrap_or_empty_then_to_string(Some(-1)), "-1".to_string());
+        }
+    }
+
+    mod test_columns {
+        use super::super::*;
+
+        #[test]
+        fn test_default_is_header() {
+            assert_eq!(
+                Columns::default().header(),
+                super::super::super::csv::HEADER
+            );
+        }
+
+        #[test]
+        fn test_parse() {
+            let columns = Columns::from_str("TIME, src=Source ,Control Bits / flags").unwrap();
+            assert_eq!(columns.header(), ["Time", "Source", "Control Bits / flags"]);
+            assert!(Columns::from_str("time,unknown").is_err());
+            assert!(Columns::from_str("").is_err());
+        }
+
+        #[test]
+        fn test_flag_style() {
+            let log = UfwLog::from_str("Jan 12 00:11:24 103213020 kernel: [3248415.842951] [UFW BLOCK] IN=es6 OUT= SRC=192.168.0.8 DST=127.0.111.111 LEN=40 PROTO=TCP SPT=46468 DPT=42313 WINDOW=1024 RES=0x00 ACK SYN URGP=0").unwrap();
+            let columns = Columns::from_str("dpt,flags").unwrap();
+            assert_eq!(columns.row(&log), ["42313", "SYN ACK"]);
+            let columns = columns.flag_style(FlagStyle::Separate);
+            assert_eq!(
+                columns.row(&log),
+                ["42313", "true", "true", "false", "false", "false", "false", "false", "false"]
+            );
+        }
+
+        #[test]
+        fn test_rename_separate_flags() {
+            let columns = Columns::from_str("dpt,flags=TCP")
+                .unwrap()
+                .flag_style(FlagStyle::Separate);
+            assert_eq!(columns.header()[..3], ["DPT", "TCP SYN", "TCP ACK"]);
+            assert_eq!(columns.columns().len(), 9);
+        }
+    }
+}
@@ -10,14 +10,29 @@
 //! use ufwlog::export::csv::Exporter as CsvExporter;
 //!
 //! let logs = UfwLog::from_file("./ufw.log")?;
-//! let exporter = CsvExporter;
+//! let exporter = CsvExporter::default();
 //! let mut file = std::fs::File::create("output.csv")?;
-//! let rows = exporter.export(&logs, &mut file)?;
+//! exporter.export(&logs, &mut file)?;
 //!
 //! # std::fs::remove_file("output.csv").unwrap();
 //! # Ok::<(), ufwlog::error::Error>(())
 //! ```
+//!
+//! Fields are quoted and escaped as described in [RFC 4180](https://datatracker.ietf.org/doc/html/rfc4180),
+//! so values that contain delimiter, quote or line break (e.g. `origin`) are safe.
+//!
+//! The delimiter, line terminator and header row are configurable, e.g. a TSV without header:
+//!
+//! ```rust
+//! use ufwlog::export::csv::{Exporter as CsvExporter, Terminator};
+//!
+//! let exporter = CsvExporter::new()
+//!     .delimiter('\t')
+//!     .terminator(Terminator::Crlf)
+//!     .has_header(false);
+//! ```
 
+use super::column::Columns;
 use crate::error::Error;
 use crate::ufw_log::UfwLog;
 use std::io::Write;
@@ -63,148 +78,225 @@ pub const HEADER: [&str; 35] = [
     "origin",
 ];
 
+/// Line terminator of csv record
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Terminator {
+    /// `\n`
+    Lf,
+    /// `\r\n`, which is required by RFC 4180 and some programs on Windows
+    Crlf,
+}
+
+impl Terminator {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Terminator::Lf => "\n",
+            Terminator::Crlf => "\r\n",
+        }
+    }
+}
+
 /// Exporter for csv format
-pub struct Exporter;
+///
+/// Default is comma delimited, `\n` terminated, has a header row and all columns in [`HEADER`]
+/// order.
+///
+/// Before 0.3 it was a unit struct, build it with [`Exporter::new`] or [`Exporter::default`]
+/// instead of the bare `Exporter`.
+#[derive(Debug, Clone)]
+pub struct Exporter {
+    delimiter: char,
+    terminator: Terminator,
+    has_header: bool,
+    columns: Columns,
+}
+
+impl Default for Exporter {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            terminator: Terminator::Lf,
+            has_header: true,
+            columns: Columns::default(),
+        }
+    }
+}
 
 impl super::Export for Exporter {
     fn get_extension(&self) -> &'static str {
-        "csv"
+        if self.delimiter == '\t' {
+            "tsv"
+        } else {
+            "csv"
+        }
     }
 
     fn convert(&self, log: &UfwLog) -> Result<String, Error> {
-        Ok(self.get_csv_row(log).join(",").to_string())
+        Ok(self.encode(&self.get_csv_row(log)))
     }
 
-    fn export(&self, logs: &[UfwLog], writer: &mut dyn Write) -> Result<(), Error> {
-        writeln!(writer, "{}", self.get_header().join(","))?;
-        for log in logs {
-            match self.convert(log) {
-                Ok(c) => writeln!(writer, "{}", c)?,
-                Err(e) => return Err(e),
-            }
+    /// Write the header row, do nothing if header is disabled.
+    fn begin(&self, writer: &mut dyn Write) -> Result<(), Error> {
+        if self.has_header {
+            write!(
+                writer,
+                "{}{}",
+                self.encode(&self.get_header()),
+                self.terminator.as_str()
+            )?;
         }
-        Ok(writer.flush()?)
+        Ok(())
+    }
+
+    fn write_record(
+        &self,
+        _index: usize,
+        log: &UfwLog,
+        writer: &mut dyn Write,
+    ) -> Result<(), Error> {
+        write!(writer, "{}{}", self.convert(log)?, self.terminator.as_str())?;
+        Ok(())
     }
 }
 
 impl Exporter {
+    /// New an exporter with default config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the field delimiter, e.g. `;` for Excel in some locales, or `\t` for TSV.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the delimiter is `"`, `\r` or `\n`, which can't be distinguished from quoting
+    /// or line terminator. Use [`try_delimiter`](Exporter::try_delimiter) for a delimiter from
+    /// user input.
+    pub fn delimiter(self, delimiter: char) -> Self {
+        match self.try_delimiter(delimiter) {
+            Ok(exporter) => exporter,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// Set the field delimiter, or return an error if the delimiter is `"`, `\r` or `\n`.
+    pub fn try_delimiter(mut self, delimiter: char) -> Result<Self, Error> {
+        if matches!(delimiter, '"' | '\r' | '\n') {
+            return Err(Error::Export(format!(
+                "Invalid csv delimiter: {delimiter:?}"
+            )));
+        }
+        self.delimiter = delimiter;
+        Ok(self)
+    }
+
+    /// Set the line terminator of each record.
+    pub fn terminator(mut self, terminator: Terminator) -> Self {
+        self.terminator = terminator;
+        self
+    }
+
+    /// Set whether write the header row or not.
+    pub fn has_header(mut self, has_header: bool) -> Self {
+        self.has_header = has_header;
+        self
+    }
+
+    /// Set the columns to output, see [column](super::column) module.
+    pub fn columns(mut self, columns: Columns) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Join fields into a record, quote the field if necessary.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let export = ufwlog::export::csv::Exporter::default();
+    /// assert_eq!(export.encode(&["a", "b,c", "say \"hi\""]), r#"a,"b,c","say ""hi""""#);
+    /// ```
+    pub fn encode<T: AsRef<str>>(&self, fields: &[T]) -> String {
+        // an empty line can't be distinguished from a record with one empty field
+        if let [field] = fields {
+            if field.as_ref().is_empty() {
+                return "\"\"".to_string();
+            }
+        }
+        let mut record = String::new();
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                record.push(self.delimiter);
+            }
+            let field = field.as_ref();
+            if field.contains([self.delimiter, '"', '\r', '\n']) {
+                record.push('"');
+                record.push_str(&field.replace('"', "\"\""));
+                record.push('"');
+            } else {
+                record.push_str(field);
+            }
+        }
+        record
+    }
+
     /// Get the header of output csv
     ///
     /// # Examples
     ///
     /// ```rust
-    /// let export = ufwlog::export::csv::Exporter;
+    /// let export = ufwlog::export::csv::Exporter::default();
     /// assert_eq!(export.get_header(), ["Month", "Day", "Time", "hostname", "uptime", "policy", "IN", "OUT", "MAC", "SRC", "DST", "LEN", "TOS", "PREC", "TTL", "ID", "DF", "PROTO", "SPT", "DPT", "WINDOW", "RES", "Control Bits / flags", "URGP", "TC", "HOPLIMIT", "FLOWLBL", "TYPE", "CODE", "SEQ", "MTU", "MARK", "PHYSIN", "PHYOUT", "origin"])
     /// ```
-    pub fn get_header(&self) -> [&'static str; 35] {
-        HEADER
+    pub fn get_header(&self) -> Vec<String> {
+        self.columns.header()
     }
 
     /// Get a vector of strings that represent a log in csv format order
     pub fn get_csv_row(&self, log: &UfwLog) -> Vec<String> {
-        let mut row = vec![];
-
-        // control bits / flags
-        let mut flags = vec![];
-        if log.syn {
-            flags.push("SYN");
-        }
-        if log.ack {
-            flags.push("ACK");
-        }
-        if log.fin {
-            flags.push("FIN");
-        }
-        if log.rst {
-            flags.push("RST");
-        }
-        if log.psh {
-            flags.push("PSH");
-        }
-        if log.cwr {
-            flags.push("CWR");
-        }
-        if log.ece {
-            flags.push("ECE");
-        }
-        if log.urg {
-            flags.push("URG");
-        }
-
-        // should push by "HEADER" order
-        row.push(log.month.to_string());
-        row.push(log.day.to_string());
-        row.push(log.time.clone());
-        row.push(log.hostname.clone());
-        row.push(log.uptime.clone());
-        row.push(log.policy.to_string());
-        row.push(log.r#in.clone());
-        row.push(log.out.clone());
-        row.push(log.mac.clone());
-        row.push(log.src.clone());
-        row.push(log.dst.clone());
-        row.push(log.len.to_string());
-        row.push(log.tos.clone().unwrap_or("".to_string()));
-        row.push(log.prec.clone().unwrap_or("".to_string()));
-        row.push(unwrap_or_empty_then_to_string(log.ttl));
-        row.push(unwrap_or_empty_then_to_string(log.id));
-        row.push(if log.df {
-            "DF".to_string()
-        } else {
-            "".to_string()
-        });
-        row.push(log.proto.clone());
-        row.push(unwrap_or_empty_then_to_string(log.spt));
-        row.push(unwrap_or_empty_then_to_string(log.dpt));
-        row.push(unwrap_or_empty_then_to_string(log.window));
-        row.push(log.res.clone());
-        row.push(flags.join(" "));
-        row.push(
-            // The value follows the flag, so it is empty when it does not appear, and it depends on the record value when it appears
-            log.urgp.map_or("".to_string(), |v| v.to_string()),
-        );
-        row.push(unwrap_or_empty_then_to_string(log.tc));
-        row.push(unwrap_or_empty_then_to_string(log.hoplimit));
-        row.push(unwrap_or_empty_then_to_string(log.flowlbl));
-        row.push(unwrap_or_empty_then_to_string(log.r#type));
-        row.push(unwrap_or_empty_then_to_string(log.code));
-        row.push(unwrap_or_empty_then_to_string(log.seq));
-        row.push(unwrap_or_empty_then_to_string(log.mtu));
-        row.push(unwrap_or_empty_then_to_string(log.mark));
-        row.push(unwrap_or_empty_then_to_string(log.physin.clone()));
-        row.push(unwrap_or_empty_then_to_string(log.phyout.clone()));
-        row.push(log.get_origin().to_string());
-
-        row
+        self.columns.row(log)
     }
 }
 
-/// If value is none, return empty string, else return value that convert to string
-fn unwrap_or_empty_then_to_string<T: ToString>(value: Option<T>) -> String {
-    value.map_or("".to_string(), |v| v.to_string())
-}
-
 #[cfg(test)]
 mod tests {
-    mod test_unwrap_or_empty_then_to_string {
-        use super::super::unwrap_or_empty_then_to_string;
+    mod test_encode {
+        use super::super::{Exporter, Terminator};
+        use crate::export::Export;
+        use std::str::FromStr;
 
         #[test]
-        fn test_input_none() {
-            assert_eq!(
-                unwrap_or_empty_then_to_string::<String>(None),
-                "".to_string()
-            );
+        fn test_quote() {
+            let exporter = Exporter::default();
+            assert_eq!(exporter.encode(&["a\nb", "c\rd", ""]), "\"a\nb\",\"c\rd\",");
+            assert_eq!(exporter.encode(&[""]), "\"\"");
         }
 
         #[test]
-        fn test_input_unsigned_integer() {
-            assert_eq!(unwrap_or_empty_then_to_string(Some(443)), "443".to_string());
+        fn test_delimiter() {
+            let exporter = Exporter::new().delimiter(';');
+            assert_eq!(exporter.encode(&["a,b", "c;d"]), "a,b;\"c;d\"");
+            for delimiter in ['"', '\r', '\n'] {
+                assert!(Exporter::new().try_delimiter(delimiter).is_err());
+            }
         }
 
         #[test]
-        fn test_input_singed_integer() {
-            assert_eq!(unwrap_or_empty_then_to_string(Some(-1)), "-1".to_string());
+        fn test_export() {
+            let log = crate::UfwLog::from_str("Jan 12 00:11:17 103213020 kernel: [3248409.197732] [UFW AUDIT] IN= OUT=lo,1 SRC=127.0.0.1 DST=127.0.0.1 LEN=84").unwrap();
+            let mut output = vec![];
+            Exporter::new()
+                .delimiter('\t')
+                .terminator(Terminator::Crlf)
+                .has_header(false)
+                .export(&[log], &mut output)
+                .unwrap();
+            let output = String::from_utf8(output).unwrap();
+            assert!(
+                output.starts_with("1\t12\t00:11:17\t103213020\t3248409.197732\tAUDIT\t\tlo,1\t")
+            );
+            assert!(output.ends_with("\tJan 12 00:11:17 103213020 kernel: [3248409.197732] [UFW AUDIT] IN= OUT=lo,1 SRC=127.0.0.1 DST=127.0.0.1 LEN=84\r\n"));
+            assert_eq!(output.lines().count(), 1);
         }
     }
 }
@@ -0,0 +1,275 @@
+//! ECS Exporter module
+//!
+//! Export logs as newline delimited JSON documents in
+//! [Elastic Common Schema](https://www.elastic.co/guide/en/ecs/current/index.html), so they can be
+//! shipped into Elasticsearch or OpenSearch without remapping.
+//!
+//! | ECS field                          | Source                                 |
+//! |------------------------------------|----------------------------------------|
+//! | `@timestamp`                       | date and time, see [`UfwLog::timestamp`] |
+//! | `event.action`                     | policy, e.g. `block`, `audit-invalid`  |
+//! | `event.original`                   | [`UfwLog::get_origin`]                 |
+//! | `host.hostname`                    | hostname                               |
+//! | `observer.ingress.interface.name`  | in                                     |
+//! | `observer.egress.interface.name`   | out                                    |
+//! | `source.ip`, `source.port`         | src, spt                               |
+//! | `destination.ip`, `destination.port` | dst, dpt                             |
+//! | `source.mac`, `destination.mac`    | mac                                    |
+//! | `network.transport`                | proto in lowercase                     |
+//! | `network.type`                     | `ipv4` or `ipv6`                       |
+//!
+//! Other packet fields are kept under `iptables`, the same as the iptables module of Filebeat.
+//! Absent and empty fields are omitted.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use ufwlog::UfwLog;
+//! use ufwlog::export::Export;
+//! use ufwlog::export::ecs::Exporter as EcsExporter;
+//!
+//! let logs = UfwLog::from_file("./ufw.log")?;
+//! EcsExporter::new().year(2024).export(&logs, &mut std::io::stdout())?;
+//!
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::column::Field;
+use super::json::Value;
+use crate::error::Error;
+use crate::ufw_log::{Policy, UfwLog};
+
+/// Version of ECS which the output follows
+pub const ECS_VERSION: &str = "8.11.0";
+
+/// Exporter for ECS format
+#[derive(Debug, Clone, Default)]
+pub struct Exporter {
+    year: Option<i32>,
+}
+
+impl super::Export for Exporter {
+    fn get_extension(&self) -> &'static str {
+        "ndjson"
+    }
+
+    fn convert(&self, log: &UfwLog) -> Result<String, Error> {
+        Ok(self.to_value(log).to_string())
+    }
+}
+
+impl Exporter {
+    /// New an exporter with default config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the year of logs, which is used to make `@timestamp`. See [`UfwLog::timestamp`] for
+    /// the default.
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+
+    /// Convert a log into ECS document.
+    pub(crate) fn to_value(&self, log: &UfwLog) -> Value {
+        let (destination_mac, source_mac) = split_mac(&log.mac);
+        let flags = Field::Flags.value(log);
+        let flags = flags.split_whitespace().collect::<Vec<_>>();
+
+        object(vec![
+            (
+                "@timestamp",
+                log.timestamp(self.year)
+                    .map(crate::time::format_rfc3339)
+                    .into(),
+            ),
+            ("ecs", object(vec![("version", ECS_VERSION.into())])),
+            (
+                "event",
+                object(vec![
+                    ("kind", "event".into()),
+                    ("category", vec!["network"].into()),
+                    ("type", vec!["connection", event_type(log.policy)].into()),
+                    ("action", action(log.policy).into()),
+                    ("module", "ufw".into()),
+                    ("dataset", "ufw.log".into()),
+                    ("original", log.get_origin().into()),
+                ]),
+            ),
+            (
+                "host",
+                object(vec![("hostname", log.hostname.as_str().into())]),
+            ),
+            (
+                "observer",
+                object(vec![
+                    ("type", "firewall".into()),
+                    ("product", "ufw".into()),
+                    ("ingress", interface(&log.r#in)),
+                    ("egress", interface(&log.out)),
+                ]),
+            ),
+            (
+                "source",
+                object(vec![
+                    ("ip", log.src.as_str().into()),
+                    ("port", log.spt.into()),
+                    ("mac", source_mac.into()),
+                ]),
+            ),
+            (
+                "destination",
+                object(vec![
+                    ("ip", log.dst.as_str().into()),
+                    ("port", log.dpt.into()),
+                    ("mac", destination_mac.into()),
+                ]),
+            ),
+            (
+                "network",
+                object(vec![
+                    ("transport", transport(&log.proto).into()),
+                    (
+                        "type",
+                        if log.src.contains(':') {
+                            "ipv6"
+                        } else {
+                            "ipv4"
+                        }
+                        .into(),
+                    ),
+                ]),
+            ),
+            (
+                "iptables",
+                object(vec![
+                    ("length", log.len.into()),
+                    ("tos", log.tos.as_deref().into()),
+                    ("precedence_bits", log.prec.as_deref().into()),
+                    ("ttl", log.ttl.into()),
+                    ("id", log.id.into()),
+                    (
+                        "fragment_flags",
+                        if log.df { vec!["DF"] } else { vec![] }.into(),
+                    ),
+                    ("flow_label", log.flowlbl.into()),
+                    ("hop_limit", log.hoplimit.into()),
+                    ("traffic_class", log.tc.into()),
+                    ("mark", log.mark.into()),
+                    (
+                        "tcp",
+                        object(vec![
+                            ("flags", flags.into()),
+                            ("window", log.window.into()),
+                            ("reserved_bits", log.res.as_str().into()),
+                            ("urgp", log.urgp.into()),
+                        ]),
+                    ),
+                    (
+                        "icmp",
+                        object(vec![
+                            ("type", log.r#type.into()),
+                            ("code", log.code.into()),
+                            ("seq", log.seq.into()),
+                            ("mtu", log.mtu.into()),
+                        ]),
+                    ),
+                ]),
+            ),
+        ])
+    }
+}
+
+/// Make an object and omit null, empty string, empty array and empty object values.
+fn object(pairs: Vec<(&str, Value)>) -> Value {
+    Value::Object(
+        pairs
+            .into_iter()
+            .filter(|(_, value)| match value {
+                Value::Null => false,
+                Value::String(s) => !s.is_empty(),
+                Value::Array(values) => !values.is_empty(),
+                Value::Object(pairs) => !pairs.is_empty(),
+                _ => true,
+            })
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    )
+}
+
+fn interface(name: &str) -> Value {
+    object(vec![("interface", object(vec![("name", name.into())]))])
+}
+
+/// `event.action` of policy, lowercase and joined by hyphen.
+fn action(policy: Policy) -> String {
+    policy.to_string().to_lowercase().replace(' ', "-")
+}
+
+/// The second `event.type` of policy.
+fn event_type(policy: Policy) -> &'static str {
+    match policy {
+        Policy::Block | Policy::LimitBlock => "denied",
+        Policy::Allow => "allowed",
+        _ => "info",
+    }
+}
+
+/// `network.transport` of protocol, which is the keyword of IANA protocol number.
+fn transport(proto: &str) -> String {
+    match proto.to_lowercase().as_str() {
+        "icmpv6" => "ipv6-icmp".to_string(),
+        p => p.to_string(),
+    }
+}
+
+/// Split `MAC` of log into destination and source MAC in ECS format (e.g. `00-00-5E-00-53-23`).
+///
+/// `MAC` of log is the destination MAC, source MAC and EtherType joined by colon.
+fn split_mac(mac: &str) -> (Option<String>, Option<String>) {
+    let octets = mac.split(':').collect::<Vec<_>>();
+    if octets.len() < 12 {
+        return (None, None);
+    }
+    let format = |octets: &[&str]| octets.join("-").to_uppercase();
+    (Some(format(&octets[..6])), Some(format(&octets[6..12])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::Export;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_convert() {
+        let log = UfwLog::from_str("Jan 16 02:13:52 myhost kernel: [3248415.842951] [UFW LIMIT BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN ACK URGP=0").unwrap();
+        let json = Exporter::new().year(2024).convert(&log).unwrap();
+        assert!(json.starts_with(
+            r#"{"@timestamp":"2024-01-16T02:13:52Z","ecs":{"version":"8.11.0"},"event":{"kind":"event","category":["network"],"type":["connection","denied"],"action":"limit-block","#
+        ));
+        assert!(json.contains(r#""host":{"hostname":"myhost"}"#));
+        assert!(json.contains(r#""ingress":{"interface":{"name":"eth0"}}}"#));
+        assert!(json
+            .contains(r#""source":{"ip":"192.168.0.8","port":46468,"mac":"CC-FF-20-19-01-01"}"#));
+        assert!(json.contains(
+            r#""destination":{"ip":"127.0.111.111","port":22,"mac":"00-FF-FF-FF-FF-FF"}"#
+        ));
+        assert!(json.contains(r#""network":{"transport":"tcp","type":"ipv4"}"#));
+        assert!(json.contains(r#""fragment_flags":["DF"]"#));
+        assert!(json.contains(r#""tcp":{"flags":["SYN","ACK"],"window":1024"#));
+        assert!(!json.contains("egress"));
+        assert!(!json.contains("icmp"));
+    }
+
+    #[test]
+    fn test_icmpv6() {
+        let log = UfwLog::from_str("Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW AUDIT] IN= OUT=lo SRC=::1 DST=::1 LEN=104 TC=0 HOPLIMIT=64 FLOWLBL=0 PROTO=ICMPv6 TYPE=128 CODE=0 ID=1 SEQ=1").unwrap();
+        let json = Exporter::new().year(2024).convert(&log).unwrap();
+        assert!(json.contains(r#""type":["connection","info"],"action":"audit""#));
+        assert!(json.contains(r#""network":{"transport":"ipv6-icmp","type":"ipv6"}"#));
+        assert!(json.contains(r#""icmp":{"type":128,"code":0,"seq":1}"#));
+        assert!(!json.contains("\"mac\""));
+    }
+}
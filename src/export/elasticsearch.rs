@@ -0,0 +1,420 @@
+//! Elasticsearch Indexer module
+//!
+//! Index logs into [Elasticsearch](https://www.elastic.co/guide/en/elasticsearch/reference/current/docs-bulk.html)
+//! or OpenSearch through the `_bulk` API. Documents are the same ECS JSON as
+//! [ecs](super::ecs) export, so files exported before can be loaded into the same index.
+//!
+//! The index of each document is made from a pattern with its timestamp in UTC, default is
+//! `ufw-%Y.%m.%d` (e.g. `ufw-2024.01.16`), supported specifiers are `%Y`, `%m`, `%d` and `%H`.
+//!
+//! The `_id` of each document is a hash of hostname and the original line, so a request resent
+//! after a timeout or a log indexed again overwrites the document instead of duplicating it.
+//!
+//! Documents are sent in batches limited by count and bytes. A bulk request succeeds even if some
+//! documents are rejected, so each item of response is checked: documents rejected by a busy
+//! cluster (`429` and `5xx`) are retried with backoff, the others (e.g. mapping conflict) are
+//! reported in [`Summary::failed`].
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use ufwlog::UfwLog;
+//! use ufwlog::export::elasticsearch::Indexer;
+//!
+//! let logs = UfwLog::from_file("./ufw.log")?;
+//! let summary = Indexer::new("http://localhost:9200")
+//!     .header("Authorization", "ApiKey bXlfa2V5")
+//!     .index_iter(&mut logs.into_iter().map(Ok))?;
+//! println!("{} indexed, {} failed", summary.indexed, summary.failed.len());
+//!
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::fnv1a;
+use super::json::Value;
+use super::Export;
+use crate::error::Error;
+use crate::http::Client;
+pub use crate::http::Retry;
+use crate::ufw_log::UfwLog;
+
+/// A document which is not indexed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    pub index: String,
+    /// Status of the bulk item, e.g. `400`.
+    pub status: u16,
+    /// Error type and reason, e.g. `mapper_parsing_exception: failed to parse`.
+    pub error: String,
+    pub document: String,
+}
+
+/// Result of indexing
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Summary {
+    /// How many documents are indexed.
+    pub indexed: usize,
+    pub failed: Vec<Failure>,
+}
+
+/// Index logs through the `_bulk` API
+///
+/// Default index pattern is `ufw-%Y.%m.%d`, and each request contains at most 500 documents or
+/// 5 MiB.
+#[derive(Debug, Clone)]
+pub struct Indexer {
+    url: String,
+    index: String,
+    headers: Vec<(String, String)>,
+    batch_size: usize,
+    batch_bytes: usize,
+    retry: Retry,
+    client: Client,
+    year: Option<i32>,
+}
+
+impl Indexer {
+    /// New an indexer of given cluster URL, e.g. `http://localhost:9200`.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            index: "ufw-%Y.%m.%d".to_string(),
+            headers: vec![(
+                "Content-Type".to_string(),
+                "application/x-ndjson".to_string(),
+            )],
+            batch_size: 500,
+            batch_bytes: 5 * 1024 * 1024,
+            retry: Retry::default(),
+            client: Client::default(),
+            year: None,
+        }
+    }
+
+    /// Set the index pattern, e.g. `ufw-%Y.%m` for monthly index, or a fixed name.
+    pub fn index(mut self, pattern: &str) -> Self {
+        self.index = pattern.to_string();
+        self
+    }
+
+    /// Add a header to each request, e.g. authorization.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Set the maximum documents of a request.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size is zero.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "Batch size should be larger than 0");
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Set the maximum bytes of a request body. A document larger than it is sent alone.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size is zero.
+    pub fn batch_bytes(mut self, batch_bytes: usize) -> Self {
+        assert!(batch_bytes > 0, "Batch bytes should be larger than 0");
+        self.batch_bytes = batch_bytes;
+        self
+    }
+
+    /// Set the retry policy of both failed requests and rejected documents, default is
+    /// [`Retry::default`].
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Set the year of logs, which is used to make timestamp. See [`UfwLog::timestamp`] for the
+    /// default.
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+
+    /// Index logs from an iterator.
+    ///
+    /// It stops at the first error of iterator or request, documents rejected by the cluster
+    /// don't stop it but are returned in [`Summary::failed`].
+    pub fn index_iter(
+        &self,
+        logs: &mut dyn Iterator<Item = Result<UfwLog, Error>>,
+    ) -> Result<Summary, Error> {
+        let mut exporter = super::ecs::Exporter::new();
+        if let Some(year) = self.year {
+            exporter = exporter.year(year);
+        }
+        let mut summary = Summary::default();
+        let mut batch = vec![];
+        let mut bytes = 0;
+        for log in logs {
+            let log = log?;
+            let timestamp = log.timestamp(self.year).unwrap_or_else(crate::time::now);
+            let index = crate::time::format_pattern(&self.index, timestamp);
+            let document = Document {
+                id: id(&log),
+                document: exporter.convert(&log)?,
+                index,
+            };
+            let size = document.action().len() + document.document.len() + 2;
+            if !batch.is_empty()
+                && (batch.len() >= self.batch_size || bytes + size > self.batch_bytes)
+            {
+                self.bulk(std::mem::take(&mut batch), &mut summary)?;
+                bytes = 0;
+            }
+            bytes += size;
+            batch.push(document);
+        }
+        if !batch.is_empty() {
+            self.bulk(batch, &mut summary)?;
+        }
+        Ok(summary)
+    }
+
+    /// Send documents, and retry the rejected ones.
+    fn bulk(&self, mut pending: Vec<Document>, summary: &mut Summary) -> Result<(), Error> {
+        let url = format!("{}/_bulk", self.url);
+        let mut attempt = 0;
+        loop {
+            let body = pending
+                .iter()
+                .map(|document| format!("{}\n{}\n", document.action(), document.document))
+                .collect::<String>();
+            let response = self
+                .client
+                .post(&url, &self.headers, body.as_bytes(), &self.retry)?;
+            let response = Value::parse(&response)
+                .ok_or_else(|| Error::Http(format!("Invalid bulk response: {response}")))?;
+            if response.get("errors") != Some(&Value::Bool(true)) {
+                summary.indexed += pending.len();
+                return Ok(());
+            }
+            let items = match response.get("items") {
+                Some(Value::Array(items)) if items.len() == pending.len() => items,
+                _ => {
+                    return Err(Error::Http(format!(
+                        "Items of bulk response don't match request: {response}"
+                    )))
+                }
+            };
+
+            let mut rejected = vec![];
+            for (item, document) in items.iter().zip(pending) {
+                // the only key is operation, e.g. `index`
+                let result = match item {
+                    Value::Object(pairs) => pairs.first().map(|(_, result)| result),
+                    _ => None,
+                };
+                let status = result
+                    .and_then(|r| r.get("status"))
+                    .and_then(Value::as_i64)
+                    .unwrap_or_default();
+                if (200..300).contains(&status) {
+                    summary.indexed += 1;
+                } else if (status == 429 || status >= 500) && attempt < self.retry.max_retries {
+                    rejected.push(document);
+                } else {
+                    let error = result.and_then(|r| r.get("error"));
+                    let reason = |key| error.and_then(|e| e.get(key)).and_then(Value::as_str);
+                    summary.failed.push(Failure {
+                        index: document.index,
+                        status: status as u16,
+                        error: match (reason("type"), reason("reason")) {
+                            (Some(kind), Some(reason)) => format!("{kind}: {reason}"),
+                            _ => error.map(Value::to_string).unwrap_or_default(),
+                        },
+                        document: document.document,
+                    });
+                }
+            }
+            if rejected.is_empty() {
+                return Ok(());
+            }
+            std::thread::sleep(self.retry.backoff(attempt, None));
+            attempt += 1;
+            pending = rejected;
+        }
+    }
+}
+
+/// A document to be indexed
+struct Document {
+    index: String,
+    id: String,
+    document: String,
+}
+
+impl Document {
+    /// Action line of the document.
+    fn action(&self) -> String {
+        Value::object([(
+            "index",
+            Value::object([
+                ("_index", self.index.as_str().into()),
+                ("_id", self.id.as_str().into()),
+            ]),
+        )])
+        .to_string()
+    }
+}
+
+/// Deterministic id of a log, so a retried request overwrites the documents instead of
+/// duplicating them.
+fn id(log: &UfwLog) -> String {
+    let mut bytes = log.hostname.as_bytes().to_vec();
+    bytes.push(0);
+    bytes.extend_from_slice(log.get_origin().as_bytes());
+    format!("{:016x}", fnv1a(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    fn logs(count: usize) -> impl Iterator<Item = Result<UfwLog, Error>> {
+        (0..count).map(|_| UfwLog::from_str(LOG))
+    }
+
+    fn retry() -> Retry {
+        Retry {
+            initial_backoff: Duration::from_millis(1),
+            ..Retry::default()
+        }
+    }
+
+    #[test]
+    fn test_id() {
+        let log = UfwLog::from_str(LOG).unwrap();
+        assert_eq!(id(&log).len(), 16);
+        assert_eq!(id(&log), id(&UfwLog::from_str(LOG).unwrap()));
+        let other = UfwLog::from_str(&LOG.replace("02:13:52", "02:13:53")).unwrap();
+        assert_ne!(id(&log), id(&other));
+    }
+
+    #[test]
+    fn test_index_iter() {
+        let (url, server) = crate::http::mock::serve_responses(vec![
+            (200, r#"{"took":3,"errors":false,"items":[]}"#.to_string()),
+            (200, r#"{"took":1,"errors":false,"items":[]}"#.to_string()),
+        ]);
+        let summary = Indexer::new(&format!("{url}/"))
+            .index("fw-%Y.%m")
+            .batch_size(2)
+            .year(2024)
+            .index_iter(&mut logs(3))
+            .unwrap();
+        assert_eq!(
+            summary,
+            Summary {
+                indexed: 3,
+                failed: vec![]
+            }
+        );
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests[0].path, "/_bulk");
+        assert_eq!(
+            requests[0].header("content-type"),
+            Some("application/x-ndjson")
+        );
+        let body = String::from_utf8(requests[0].body.clone()).unwrap();
+        let lines = body.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            format!(
+                r#"{{"index":{{"_index":"fw-2024.01","_id":"{}"}}}}"#,
+                id(&UfwLog::from_str(LOG).unwrap())
+            )
+        );
+        let document = super::super::ecs::Exporter::new()
+            .year(2024)
+            .convert(&UfwLog::from_str(LOG).unwrap())
+            .unwrap();
+        assert_eq!(lines[1], document);
+        assert_eq!(requests[1].body.iter().filter(|b| **b == b'\n').count(), 2);
+    }
+
+    #[test]
+    fn test_batch_bytes() {
+        let (url, server) = crate::http::mock::serve(vec![200, 200, 200]);
+        // each document is larger than half of the limit
+        let summary = Indexer::new(&url)
+            .batch_bytes(1000)
+            .index_iter(&mut logs(3))
+            .unwrap();
+        assert_eq!(summary.indexed, 3);
+        assert_eq!(server.join().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_partial_failure() {
+        let (url, server) = crate::http::mock::serve_responses(vec![
+            (
+                200,
+                r#"{"errors":true,"items":[
+                    {"index":{"status":201}},
+                    {"index":{"status":429,"error":{"type":"es_rejected_execution_exception","reason":"queue is full"}}},
+                    {"index":{"status":400,"error":{"type":"mapper_parsing_exception","reason":"failed to parse"}}}
+                ]}"#
+                .to_string(),
+            ),
+            (
+                200,
+                r#"{"errors":true,"items":[{"index":{"status":503,"error":{"type":"unavailable_shards_exception","reason":"primary shard is not active"}}}]}"#
+                    .to_string(),
+            ),
+            (
+                200,
+                r#"{"errors":false,"items":[{"index":{"status":201}}]}"#.to_string(),
+            ),
+        ]);
+        let summary = Indexer::new(&url)
+            .year(2024)
+            .retry(retry())
+            .index_iter(&mut logs(3))
+            .unwrap();
+        assert_eq!(summary.indexed, 2);
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(summary.failed[0].index, "ufw-2024.01.16");
+        assert_eq!(summary.failed[0].status, 400);
+        assert_eq!(
+            summary.failed[0].error,
+            "mapper_parsing_exception: failed to parse"
+        );
+
+        let requests = server.join().unwrap();
+        // only the rejected document is retried
+        assert_eq!(requests[1].body, requests[2].body);
+        assert_eq!(requests[1].body.iter().filter(|b| **b == b'\n').count(), 2);
+    }
+
+    #[test]
+    fn test_rejected_after_retries() {
+        let rejected = r#"{"errors":true,"items":[{"index":{"status":429}}]}"#.to_string();
+        let (url, server) =
+            crate::http::mock::serve_responses(vec![(200, rejected.clone()), (200, rejected)]);
+        let summary = Indexer::new(&url)
+            .retry(Retry {
+                max_retries: 1,
+                ..retry()
+            })
+            .index_iter(&mut logs(1))
+            .unwrap();
+        assert_eq!(summary.indexed, 0);
+        assert_eq!(summary.failed[0].status, 429);
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+}
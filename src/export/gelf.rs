@@ -0,0 +1,395 @@
+//! GELF Exporter module
+//!
+//! Export logs as [Graylog Extended Log Format](https://go2docs.graylog.org/current/getting_in_log_data/gelf.html)
+//! 1.1 messages, and send them to Graylog over UDP or TCP.
+//!
+//! `short_message` is a summary such as `UFW BLOCK TCP 192.168.0.8:46468 -> 127.0.111.111:22`,
+//! `full_message` is the original log line, and the other fields of [`UfwLog`] are additional
+//! fields prefixed with underscore (`_src`, `_dpt`, `_policy`, ...). `ID` is renamed to
+//! `_packet_id` because `_id` is reserved by GELF. `level` is syslog severity of policy.
+//!
+//! Written into file, each message is a line. Sent by [`UdpSender`], a message larger than chunk
+//! size is split into chunks; sent by [`TcpSender`], messages are delimited by null byte.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use std::io::BufReader;
+//! use ufwlog::UfwLog;
+//! use ufwlog::export::gelf::{Exporter as GelfExporter, UdpSender};
+//!
+//! let reader = BufReader::new(std::fs::File::open("./ufw.log")?);
+//! let mut logs = UfwLog::from_buf_reader(reader);
+//! let mut sender = UdpSender::connect("graylog.example.com:12201")?;
+//! GelfExporter::new().send_iter(&mut logs, &mut sender)?;
+//!
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::column::Field;
+use super::json::Value;
+use super::Export;
+use crate::error::Error;
+use crate::ufw_log::{Policy, UfwLog};
+use std::io::{BufWriter, Write};
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Exporter for GELF format
+#[derive(Debug, Clone, Default)]
+pub struct Exporter {
+    year: Option<i32>,
+}
+
+impl Export for Exporter {
+    fn get_extension(&self) -> &'static str {
+        "gelf"
+    }
+
+    fn convert(&self, log: &UfwLog) -> Result<String, Error> {
+        Ok(self.to_value(log).to_string())
+    }
+}
+
+impl Exporter {
+    /// New an exporter with default config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the year of logs, which is used to make `timestamp`. See [`UfwLog::timestamp`] for
+    /// the default.
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+
+    /// Send logs from an iterator with given transport, return how many logs are sent.
+    pub fn send_iter(
+        &self,
+        logs: &mut dyn Iterator<Item = Result<UfwLog, Error>>,
+        transport: &mut dyn Transport,
+    ) -> Result<usize, Error> {
+        let mut count = 0;
+        for log in logs {
+            transport.send(self.convert(&log?)?.as_bytes())?;
+            count += 1;
+        }
+        transport.flush()?;
+        Ok(count)
+    }
+
+    /// Convert a log into GELF message.
+    fn to_value(&self, log: &UfwLog) -> Value {
+        let endpoint = |ip: &str, port: Option<u16>| match port {
+            Some(port) => format!("{ip}:{port}"),
+            None => ip.to_string(),
+        };
+        let short_message = format!(
+            "UFW {} {} {} -> {}",
+            log.policy,
+            log.proto,
+            endpoint(&log.src, log.spt),
+            endpoint(&log.dst, log.dpt)
+        );
+        let flags = Field::Flags.value(log);
+
+        let pairs: Vec<(&str, Value)> = vec![
+            ("version", "1.1".into()),
+            ("host", log.hostname.as_str().into()),
+            ("short_message", short_message.into()),
+            ("full_message", log.get_origin().into()),
+            (
+                "timestamp",
+                log.timestamp(self.year).map_or(Value::Null, Value::Integer),
+            ),
+            ("level", level(log.policy).into()),
+            ("_policy", log.policy.to_string().into()),
+            ("_uptime", log.uptime.trim().into()),
+            ("_in", log.r#in.as_str().into()),
+            ("_out", log.out.as_str().into()),
+            ("_mac", log.mac.as_str().into()),
+            ("_src", log.src.as_str().into()),
+            ("_dst", log.dst.as_str().into()),
+            ("_len", log.len.into()),
+            ("_tos", log.tos.as_deref().into()),
+            ("_prec", log.prec.as_deref().into()),
+            ("_ttl", log.ttl.into()),
+            ("_packet_id", log.id.into()),
+            // additional fields can only be string or number
+            ("_df", u8::from(log.df).into()),
+            ("_proto", log.proto.as_str().into()),
+            ("_spt", log.spt.into()),
+            ("_dpt", log.dpt.into()),
+            ("_window", log.window.into()),
+            ("_res", log.res.as_str().into()),
+            ("_flags", flags.into()),
+            ("_urgp", log.urgp.into()),
+            ("_tc", log.tc.into()),
+            ("_hoplimit", log.hoplimit.into()),
+            ("_flowlbl", log.flowlbl.into()),
+            ("_type", log.r#type.into()),
+            ("_code", log.code.into()),
+            ("_seq", log.seq.into()),
+            ("_mtu", log.mtu.into()),
+            ("_mark", log.mark.into()),
+            ("_physin", log.physin.as_deref().into()),
+            ("_phyout", log.phyout.as_deref().into()),
+        ];
+        // GELF doesn't have null, omit absent and empty fields
+        Value::Object(
+            pairs
+                .into_iter()
+                .filter(|(_, value)| match value {
+                    Value::Null => false,
+                    Value::String(s) => !s.is_empty(),
+                    _ => true,
+                })
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+}
+
+/// Syslog severity of policy.
+fn level(policy: Policy) -> u8 {
+    match policy {
+        Policy::Block | Policy::LimitBlock => 4,     // warning
+        Policy::AuditInvalid | Policy::Unknown => 5, // notice
+        Policy::Allow | Policy::Audit => 6,          // informational
+    }
+}
+
+/// A way to send GELF messages to server
+pub trait Transport {
+    /// Send a message.
+    fn send(&mut self, message: &[u8]) -> Result<(), Error>;
+
+    /// Make sure all messages are sent.
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Magic bytes of chunked GELF message
+const CHUNK_MAGIC: [u8; 2] = [0x1e, 0x0f];
+
+/// Size of header of a chunk: magic, message id, sequence number and sequence count.
+const CHUNK_HEADER_SIZE: usize = 12;
+
+/// Maximum chunks of a message
+const MAX_CHUNKS: usize = 128;
+
+/// Send GELF messages over UDP
+///
+/// A message larger than chunk size is split into chunks, default chunk size is 1420 bytes which
+/// fits most networks. Messages can't be split into more than 128 chunks.
+#[derive(Debug)]
+pub struct UdpSender {
+    socket: UdpSocket,
+    chunk_size: usize,
+    /// Increased for each chunked message, to make message id unique.
+    counter: u64,
+}
+
+impl UdpSender {
+    /// Connect to GELF UDP input, e.g. `127.0.0.1:12201`.
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<Self, Error> {
+        let addr = addr.to_socket_addrs()?.next().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "no address to connect")
+        })?;
+        let local = if addr.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(addr)?;
+        Ok(Self {
+            socket,
+            chunk_size: 1420,
+            counter: 0,
+        })
+    }
+
+    /// Set the maximum size of a datagram, including chunk header.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size isn't larger than chunk header (12 bytes).
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(
+            chunk_size > CHUNK_HEADER_SIZE,
+            "Chunk size should be larger than {CHUNK_HEADER_SIZE}"
+        );
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Make an unique message id of chunks.
+    fn message_id(&mut self) -> [u8; 8] {
+        self.counter += 1;
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        (nanos ^ ((std::process::id() as u64) << 32))
+            .wrapping_add(self.counter)
+            .to_be_bytes()
+    }
+}
+
+impl Transport for UdpSender {
+    fn send(&mut self, message: &[u8]) -> Result<(), Error> {
+        if message.len() <= self.chunk_size {
+            self.socket.send(message)?;
+            return Ok(());
+        }
+
+        let payload_size = self.chunk_size - CHUNK_HEADER_SIZE;
+        let count = message.len().div_ceil(payload_size);
+        if count > MAX_CHUNKS {
+            return Err(Error::Export(format!(
+                "GELF message of {} bytes needs {count} chunks, more than {MAX_CHUNKS}",
+                message.len()
+            )));
+        }
+        let id = self.message_id();
+        let mut datagram = Vec::with_capacity(self.chunk_size);
+        for (sequence, payload) in message.chunks(payload_size).enumerate() {
+            datagram.clear();
+            datagram.extend_from_slice(&CHUNK_MAGIC);
+            datagram.extend_from_slice(&id);
+            datagram.push(sequence as u8);
+            datagram.push(count as u8);
+            datagram.extend_from_slice(payload);
+            self.socket.send(&datagram)?;
+        }
+        Ok(())
+    }
+}
+
+/// Send GELF messages over TCP, delimited by null byte
+#[derive(Debug)]
+pub struct TcpSender {
+    stream: BufWriter<TcpStream>,
+}
+
+impl TcpSender {
+    /// Connect to GELF TCP input, e.g. `127.0.0.1:12201`.
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<Self, Error> {
+        Ok(Self {
+            stream: BufWriter::new(TcpStream::connect(addr)?),
+        })
+    }
+}
+
+impl Transport for TcpSender {
+    fn send(&mut self, message: &[u8]) -> Result<(), Error> {
+        self.stream.write_all(message)?;
+        self.stream.write_all(&[0])?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(self.stream.flush()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::str::FromStr;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    #[test]
+    fn test_convert() {
+        let log = UfwLog::from_str(LOG).unwrap();
+        let gelf = Exporter::new().year(2024).convert(&log).unwrap();
+        assert!(gelf.starts_with(r#"{"version":"1.1","host":"myhost","short_message":"UFW BLOCK TCP 192.168.0.8:46468 -> 127.0.111.111:22","full_message":"Jan 16 02:13:52 myhost"#));
+        assert!(gelf.contains(r#""timestamp":1705371232,"level":4,"_policy":"BLOCK","_uptime":"1230.568282","_in":"eth0","_mac":"#));
+        assert!(gelf.contains(r#""_ttl":234,"_packet_id":17062,"_df":1,"#));
+        assert!(gelf.contains(r#""_dpt":22,"#));
+        assert!(gelf.contains(r#""_flags":"SYN","_urgp":0}"#));
+        assert!(!gelf.contains("\"_out\""));
+
+        // same order as csv
+        let log = UfwLog::from_str(&LOG.replace("SYN URGP", "FIN PSH ACK SYN URGP")).unwrap();
+        let gelf = Exporter::new().convert(&log).unwrap();
+        assert!(gelf.contains(r#""_flags":"SYN ACK FIN PSH","#));
+    }
+
+    #[test]
+    fn test_udp_chunked() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut sender = UdpSender::connect(server.local_addr().unwrap())
+            .unwrap()
+            .chunk_size(100);
+        let mut logs = vec![UfwLog::from_str(LOG)].into_iter();
+        let count = Exporter::new()
+            .year(2024)
+            .send_iter(&mut logs, &mut sender)
+            .unwrap();
+        assert_eq!(count, 1);
+
+        let expect = Exporter::new()
+            .year(2024)
+            .convert(&UfwLog::from_str(LOG).unwrap())
+            .unwrap();
+        let chunks = expect.len().div_ceil(100 - CHUNK_HEADER_SIZE);
+        let mut message = vec![vec![]; chunks];
+        let mut buffer = [0; 100];
+        let mut id = None;
+        for _ in 0..chunks {
+            let size = server.recv(&mut buffer).unwrap();
+            let datagram = &buffer[..size];
+            assert_eq!(datagram[..2], CHUNK_MAGIC);
+            // all chunks share the same message id
+            assert_eq!(*id.get_or_insert(datagram[2..10].to_vec()), datagram[2..10]);
+            assert_eq!(datagram[11] as usize, chunks);
+            message[datagram[10] as usize] = datagram[12..].to_vec();
+        }
+        assert_eq!(String::from_utf8(message.concat()).unwrap(), expect);
+    }
+
+    #[test]
+    fn test_udp_small_message_not_chunked() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut sender = UdpSender::connect(server.local_addr().unwrap()).unwrap();
+        sender.send(b"{}").unwrap();
+        let mut buffer = [0; 16];
+        let size = server.recv(&mut buffer).unwrap();
+        assert_eq!(&buffer[..size], b"{}");
+    }
+
+    #[test]
+    fn test_udp_too_many_chunks() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut sender = UdpSender::connect(server.local_addr().unwrap())
+            .unwrap()
+            .chunk_size(13);
+        assert!(sender.send(&[b'a'; 129]).is_err());
+    }
+
+    #[test]
+    fn test_tcp() {
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut sender = TcpSender::connect(server.local_addr().unwrap()).unwrap();
+        let (mut stream, _) = server.accept().unwrap();
+        let mut logs = vec![UfwLog::from_str(LOG), UfwLog::from_str(LOG)].into_iter();
+        Exporter::new().send_iter(&mut logs, &mut sender).unwrap();
+        drop(sender);
+
+        let mut received = vec![];
+        stream.read_to_end(&mut received).unwrap();
+        let messages = received.split(|b| *b == 0).collect::<Vec<_>>();
+        // trailing null byte
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].starts_with(b"{\"version\":\"1.1\""));
+        assert_eq!(messages[0], messages[1]);
+        assert!(messages[2].is_empty());
+    }
+}
@@ -0,0 +1,358 @@
+//! InfluxDB Exporter module
+//!
+//! Export logs as [InfluxDB line protocol](https://docs.influxdata.com/influxdb/v2/reference/syntax/line-protocol/),
+//! one point per line, which can be written to InfluxDB or read by Telegraf:
+//!
+//! ```text
+//! ufw,host=myhost,policy=BLOCK,proto=TCP,in=eth0 len=40i,ttl=234i,spt=46468i,dpt=22i,src="192.168.0.8",dst="127.0.111.111" 1705371232000000000
+//! ```
+//!
+//! Tags are `host`, `policy`, `proto`, `in` and `out`, empty tags are omitted. IP addresses are
+//! fields rather than tags to keep series cardinality low. The timestamp is in nanoseconds, and
+//! omitted if the log has no timestamp or it's out of the range of nanoseconds (after 2262).
+//!
+//! Points with the same tags and timestamp overwrite each other in InfluxDB, but ufw log only has
+//! second precision. So [`export`](super::Export::export) and
+//! [`export_iter`](super::Export::export_iter) add a sequence number of the log within its second
+//! as nanoseconds, which keeps points in the same second distinct, e.g. the third log of a second
+//! ends with `000000002`. The sequence counts consecutive logs, which are in time order as in
+//! `ufw.log`. [`Export::convert`] has no sequence and writes whole seconds.
+//!
+//! ## Aggregation
+//!
+//! With [`Exporter::interval`], [`export`](super::Export::export) and
+//! [`export_iter`](super::Export::export_iter) write one point per interval and tag set instead,
+//! whose fields are `count` of events and `bytes` of packet length, and timestamp is the start of
+//! interval:
+//!
+//! ```text
+//! ufw,host=myhost,policy=BLOCK,proto=TCP,in=eth0 count=12i,bytes=480i 1705371180000000000
+//! ```
+//!
+//! Logs are expected in time order as in `ufw.log`. The points of an interval are written once a
+//! log two intervals later arrives, so a log at most one interval late is still counted.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use ufwlog::UfwLog;
+//! use ufwlog::export::Export;
+//! use ufwlog::export::influx::Exporter as InfluxExporter;
+//!
+//! let logs = UfwLog::from_file("./ufw.log")?;
+//! // count events per minute
+//! InfluxExporter::new().interval(60).export(&logs, &mut std::io::stdout())?;
+//!
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::Export;
+use crate::error::Error;
+use crate::time::Sequence;
+use crate::ufw_log::UfwLog;
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// Exporter for InfluxDB line protocol
+///
+/// Default measurement is `ufw`, and each log is a point.
+#[derive(Debug, Clone)]
+pub struct Exporter {
+    measurement: String,
+    /// Seconds of aggregation interval, `None` means no aggregation.
+    interval: Option<i64>,
+    year: Option<i32>,
+}
+
+impl Default for Exporter {
+    fn default() -> Self {
+        Self {
+            measurement: "ufw".to_string(),
+            interval: None,
+            year: None,
+        }
+    }
+}
+
+impl Export for Exporter {
+    fn get_extension(&self) -> &'static str {
+        "lp"
+    }
+
+    /// Convert a log into a point, regardless of aggregation interval.
+    fn convert(&self, log: &UfwLog) -> Result<String, Error> {
+        Ok(self.point(log, log.timestamp(self.year).and_then(crate::time::nanos)))
+    }
+
+    fn export(&self, logs: &[UfwLog], writer: &mut dyn Write) -> Result<(), Error> {
+        self.export_points(logs.iter().map(Ok), writer).map(|_| ())
+    }
+
+    fn export_iter(
+        &self,
+        logs: &mut dyn Iterator<Item = Result<UfwLog, Error>>,
+        writer: &mut dyn Write,
+    ) -> Result<usize, Error> {
+        self.export_points(logs, writer)
+    }
+}
+
+impl Exporter {
+    /// New an exporter with default config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the measurement name.
+    pub fn measurement(mut self, measurement: &str) -> Self {
+        self.measurement = measurement.to_string();
+        self
+    }
+
+    /// Aggregate logs per given seconds instead of writing each log.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the interval is zero.
+    pub fn interval(mut self, seconds: u32) -> Self {
+        assert!(seconds > 0, "Interval should be larger than 0");
+        self.interval = Some(i64::from(seconds));
+        self
+    }
+
+    /// Set the year of logs, which is used to make timestamp. See [`UfwLog::timestamp`] for the
+    /// default.
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+
+    /// Convert a log into a point with timestamp in nanoseconds.
+    fn point(&self, log: &UfwLog, timestamp: Option<i64>) -> String {
+        let mut fields = vec![format!("len={}i", log.len)];
+        for (key, value) in [
+            ("ttl", log.ttl.map(u32::from)),
+            ("spt", log.spt.map(u32::from)),
+            ("dpt", log.dpt.map(u32::from)),
+        ] {
+            if let Some(value) = value {
+                fields.push(format!("{key}={value}i"));
+            }
+        }
+        for (key, value) in [("src", &log.src), ("dst", &log.dst)] {
+            if !value.is_empty() {
+                fields.push(format!("{key}=\"{}\"", escape_field(value)));
+            }
+        }
+
+        let mut line = format!("{} {}", self.series(log), fields.join(","));
+        if let Some(timestamp) = timestamp {
+            line += &format!(" {timestamp}");
+        }
+        line
+    }
+
+    /// Measurement and tag set of log.
+    fn series(&self, log: &UfwLog) -> String {
+        let mut series = escape_measurement(&self.measurement);
+        for (key, value) in [
+            ("host", log.hostname.as_str()),
+            ("policy", &log.policy.to_string()),
+            ("proto", &log.proto),
+            ("in", &log.r#in),
+            ("out", &log.out),
+        ] {
+            if !value.is_empty() {
+                series += &format!(",{key}={}", escape_tag(value));
+            }
+        }
+        series
+    }
+
+    /// Write a point per log, or aggregated points if interval is set, return how many logs are
+    /// read.
+    fn export_points<L: Borrow<UfwLog>>(
+        &self,
+        logs: impl Iterator<Item = Result<L, Error>>,
+        writer: &mut dyn Write,
+    ) -> Result<usize, Error> {
+        let interval = match self.interval {
+            Some(interval) => interval,
+            None => {
+                let mut count = 0;
+                let mut sequence = Sequence::default();
+                for log in logs {
+                    let log = log?;
+                    let log = log.borrow();
+                    let timestamp = log.timestamp(self.year).and_then(|t| sequence.next(t));
+                    writeln!(writer, "{}", self.point(log, timestamp))?;
+                    count += 1;
+                }
+                self.finish(writer)?;
+                return Ok(count);
+            }
+        };
+
+        // (start of interval, series) -> (count, bytes)
+        let mut windows: BTreeMap<(Option<i64>, String), (u64, u64)> = BTreeMap::new();
+        let mut latest = None;
+        let mut count = 0;
+        for log in logs {
+            let log = log?;
+            let log = log.borrow();
+            let start = log
+                .timestamp(self.year)
+                .map(|t| t.div_euclid(interval) * interval);
+            let point = windows.entry((start, self.series(log))).or_default();
+            point.0 += 1;
+            point.1 += u64::from(log.len);
+            count += 1;
+
+            if start > latest {
+                latest = start;
+                // keep the latest two intervals open, write the older ones
+                let open = windows.split_off(&(latest.map(|l| l - interval), String::new()));
+                for point in std::mem::replace(&mut windows, open) {
+                    self.write_point(point, writer)?;
+                }
+            }
+        }
+        for point in windows {
+            self.write_point(point, writer)?;
+        }
+        self.finish(writer)?;
+        Ok(count)
+    }
+
+    /// Write an aggregated point.
+    fn write_point(
+        &self,
+        ((start, series), (count, bytes)): ((Option<i64>, String), (u64, u64)),
+        writer: &mut dyn Write,
+    ) -> Result<(), Error> {
+        write!(writer, "{series} count={count}i,bytes={bytes}i")?;
+        if let Some(start) = start.and_then(crate::time::nanos) {
+            write!(writer, " {start}")?;
+        }
+        writeln!(writer)?;
+        Ok(())
+    }
+}
+
+/// Escape comma and space in measurement.
+fn escape_measurement(value: &str) -> String {
+    value.replace(',', "\\,").replace(' ', "\\ ")
+}
+
+/// Escape comma, equal sign and space in tag key or value.
+fn escape_tag(value: &str) -> String {
+    value
+        .replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
+
+/// Escape double quote and backslash in string field value.
+fn escape_field(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    #[test]
+    fn test_convert() {
+        let log = UfwLog::from_str(LOG).unwrap();
+        let line = Exporter::new().year(2024).convert(&log).unwrap();
+        assert_eq!(
+            line,
+            r#"ufw,host=myhost,policy=BLOCK,proto=TCP,in=eth0 len=40i,ttl=234i,spt=46468i,dpt=22i,src="192.168.0.8",dst="127.0.111.111" 1705371232000000000"#
+        );
+    }
+
+    #[test]
+    fn test_year_out_of_nanoseconds() {
+        let mut logs = [LOG, LOG].into_iter().map(UfwLog::from_str);
+        let mut output = vec![];
+        Exporter::new()
+            .year(2300)
+            .export_iter(&mut logs, &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), 2);
+        assert!(output
+            .lines()
+            .all(|line| line.ends_with(r#"dst="127.0.111.111""#)));
+    }
+
+    #[test]
+    fn test_sequence_in_second() {
+        let lines = [LOG, LOG, &LOG.replace("02:13:52", "02:13:53")];
+        let mut logs = lines.iter().map(|line| UfwLog::from_str(line));
+        let mut output = vec![];
+        Exporter::new()
+            .year(2024)
+            .export_iter(&mut logs, &mut output)
+            .unwrap();
+        let timestamps = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| line.rsplit(' ').next().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            timestamps,
+            [
+                "1705371232000000000",
+                "1705371232000000001",
+                "1705371233000000000"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        let mut log = UfwLog::from_str(LOG).unwrap();
+        log.hostname = "my host,1".to_string();
+        let line = Exporter::new()
+            .measurement("fire wall")
+            .year(2024)
+            .convert(&log)
+            .unwrap();
+        assert!(line.starts_with(r"fire\ wall,host=my\ host\,1,policy=BLOCK,"));
+        assert_eq!(escape_field(r#"a"b\"#), r#"a\"b\\"#);
+        assert_eq!(escape_tag("a=b"), r"a\=b");
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let lines = [
+            LOG.replace("02:13:52", "02:13:01"),
+            LOG.replace("02:13:52", "02:13:59"),
+            LOG.replace("02:13:52", "02:14:30")
+                .replace("LEN=40", "LEN=60"),
+            // late but within one interval
+            LOG.replace("02:13:52", "02:13:30"),
+            LOG.replace("02:13:52", "02:15:00")
+                .replace("[UFW BLOCK]", "[UFW ALLOW]"),
+        ];
+        let mut logs = lines.iter().map(|line| UfwLog::from_str(line));
+        let mut output = vec![];
+        let count = Exporter::new()
+            .year(2024)
+            .interval(60)
+            .export_iter(&mut logs, &mut output)
+            .unwrap();
+        assert_eq!(count, 5);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "ufw,host=myhost,policy=BLOCK,proto=TCP,in=eth0 count=3i,bytes=120i 1705371180000000000\n\
+             ufw,host=myhost,policy=BLOCK,proto=TCP,in=eth0 count=1i,bytes=60i 1705371240000000000\n\
+             ufw,host=myhost,policy=ALLOW,proto=TCP,in=eth0 count=1i,bytes=40i 1705371300000000000\n"
+        );
+    }
+}
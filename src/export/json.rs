@@ -0,0 +1,515 @@
+//! JSON Exporter module
+//!
+//! Export logs as a single JSON array. Each log is an object whose keys are the field names of
+//! [`UfwLog`], values keep their types: numbers are numbers, flags are booleans and absent
+//! optional fields are `null`.
+//!
+//! If you want to handle logs one by one (e.g. pipe to `jq` or other log shipper), see
+//! [ndjson](super::ndjson) instead.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use ufwlog::UfwLog;
+//! use ufwlog::export::Export;
+//! use ufwlog::export::json::Exporter as JsonExporter;
+//!
+//! let logs = UfwLog::from_file("./ufw.log")?;
+//! let mut file = std::fs::File::create("output.json")?;
+//! JsonExporter.export(&logs, &mut file)?;
+//!
+//! # std::fs::remove_file("output.json").unwrap();
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use crate::error::Error;
+use crate::ufw_log::UfwLog;
+use std::fmt::{Display, Formatter, Write as _};
+use std::io::Write;
+
+/// Exporter for JSON array format
+pub struct Exporter;
+
+impl super::Export for Exporter {
+    fn get_extension(&self) -> &'static str {
+        "json"
+    }
+
+    fn convert(&self, log: &UfwLog) -> Result<String, Error> {
+        Ok(to_value(log).to_string())
+    }
+
+    fn begin(&self, writer: &mut dyn Write) -> Result<(), Error> {
+        write!(writer, "[")?;
+        Ok(())
+    }
+
+    fn write_record(
+        &self,
+        index: usize,
+        log: &UfwLog,
+        writer: &mut dyn Write,
+    ) -> Result<(), Error> {
+        if index > 0 {
+            write!(writer, ",")?;
+        }
+        write!(writer, "\n{}", self.convert(log)?)?;
+        Ok(())
+    }
+
+    fn finish(&self, writer: &mut dyn Write) -> Result<(), Error> {
+        writeln!(writer, "\n]")?;
+        Ok(writer.flush()?)
+    }
+}
+
+/// A JSON value.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    /// Only decoded from input, e.g. JSON text or MessagePack.
+    Float(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// Key-value pairs, keep insertion order.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Make an object from key-value pairs.
+    pub(crate) fn object<const N: usize>(pairs: [(&str, Value); N]) -> Self {
+        Value::Object(pairs.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+    }
+}
+
+#[cfg(feature = "http")]
+impl Value {
+    /// Parse JSON text, e.g. response of a server. Return `None` if it is invalid.
+    pub(crate) fn parse(text: &str) -> Option<Self> {
+        let mut parser = Parser {
+            text: text.as_bytes(),
+            position: 0,
+            depth: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        (parser.position == text.len()).then_some(value)
+    }
+
+    /// Get value of given key if it is an object.
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(pairs) => pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Integer(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+/// Maximum depth of nested arrays and objects, deeper text is invalid so a broken response can't
+/// overflow the stack.
+#[cfg(feature = "http")]
+const MAX_DEPTH: usize = 128;
+
+/// A recursive descent JSON parser
+#[cfg(feature = "http")]
+struct Parser<'a> {
+    text: &'a [u8],
+    position: usize,
+    /// Depth of the array or object being parsed.
+    depth: usize,
+}
+
+#[cfg(feature = "http")]
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.position).copied()
+    }
+
+    /// Consume given literal.
+    fn expect(&mut self, literal: &str) -> Option<()> {
+        let end = self.position + literal.len();
+        (self.text.get(self.position..end)? == literal.as_bytes()).then(|| self.position = end)
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        if self.depth >= MAX_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let value = self.nested_value();
+        self.depth -= 1;
+        value
+    }
+
+    fn nested_value(&mut self) -> Option<Value> {
+        self.skip_whitespace();
+        match self.peek()? {
+            b'n' => self.expect("null").map(|_| Value::Null),
+            b't' => self.expect("true").map(|_| Value::Bool(true)),
+            b'f' => self.expect("false").map(|_| Value::Bool(false)),
+            b'"' => self.string().map(Value::String),
+            b'[' => {
+                self.position += 1;
+                let mut values = vec![];
+                self.skip_whitespace();
+                if self.peek() == Some(b']') {
+                    self.position += 1;
+                    return Some(Value::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.peek()? {
+                        b',' => self.position += 1,
+                        b']' => {
+                            self.position += 1;
+                            return Some(Value::Array(values));
+                        }
+                        _ => return None,
+                    }
+                }
+            }
+            b'{' => {
+                self.position += 1;
+                let mut pairs = vec![];
+                self.skip_whitespace();
+                if self.peek() == Some(b'}') {
+                    self.position += 1;
+                    return Some(Value::Object(pairs));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    self.expect(":")?;
+                    pairs.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.peek()? {
+                        b',' => self.position += 1,
+                        b'}' => {
+                            self.position += 1;
+                            return Some(Value::Object(pairs));
+                        }
+                        _ => return None,
+                    }
+                }
+            }
+            _ => self.number(),
+        }
+    }
+
+    fn number(&mut self) -> Option<Value> {
+        let start = self.position;
+        while matches!(
+            self.peek(),
+            Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+        ) {
+            self.position += 1;
+        }
+        let number = std::str::from_utf8(&self.text[start..self.position]).ok()?;
+        match number.parse() {
+            Ok(n) => Some(Value::Integer(n)),
+            Err(_) => number.parse().ok().map(Value::Float),
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.expect("\"")?;
+        let mut bytes = vec![];
+        loop {
+            match self.peek()? {
+                b'"' => {
+                    self.position += 1;
+                    return String::from_utf8(bytes).ok();
+                }
+                b'\\' => {
+                    self.position += 1;
+                    let escaped = match self.peek()? {
+                        b'u' => {
+                            let mut code = self.hex()?;
+                            // surrogate pair
+                            if (0xd800..0xdc00).contains(&code) {
+                                self.expect("\\")?;
+                                let low = self.hex()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return None;
+                                }
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            let mut buffer = [0; 4];
+                            bytes.extend_from_slice(
+                                char::from_u32(code)?.encode_utf8(&mut buffer).as_bytes(),
+                            );
+                            continue;
+                        }
+                        b'b' => 0x08,
+                        b'f' => 0x0c,
+                        b'n' => b'\n',
+                        b'r' => b'\r',
+                        b't' => b'\t',
+                        c @ (b'"' | b'\\' | b'/') => c,
+                        _ => return None,
+                    };
+                    bytes.push(escaped);
+                    self.position += 1;
+                }
+                c => {
+                    bytes.push(c);
+                    self.position += 1;
+                }
+            }
+        }
+    }
+
+    /// Parse 4 hex digits after `u`.
+    fn hex(&mut self) -> Option<u32> {
+        let digits = self.text.get(self.position + 1..self.position + 5)?;
+        let code = u32::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
+        self.position += 5;
+        Some(code)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+macro_rules! impl_from_integer {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Value {
+                fn from(value: $t) -> Self {
+                    Value::Integer(value as i64)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_integer!(u8, u16, u32);
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(value: Vec<T>) -> Self {
+        Value::Array(value.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Integer(n) => write!(f, "{n}"),
+            Value::Float(n) if n.is_finite() => write!(f, "{n:?}"),
+            Value::Float(_) => f.write_str("null"),
+            Value::String(s) => write_string(f, s),
+            Value::Array(values) => {
+                f.write_char('[')?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                f.write_char(']')
+            }
+            Value::Object(pairs) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+/// Write a quoted and escaped JSON string.
+fn write_string(f: &mut impl std::fmt::Write, s: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+/// Convert a log into JSON object, keys are the field names of [`UfwLog`].
+pub(crate) fn to_value(log: &UfwLog) -> Value {
+    Value::object([
+        ("month", log.month.into()),
+        ("day", log.day.into()),
+        ("time", log.time.as_str().into()),
+        ("hostname", log.hostname.as_str().into()),
+        ("uptime", log.uptime.as_str().into()),
+        ("policy", log.policy.to_string().into()),
+        ("in", log.r#in.as_str().into()),
+        ("out", log.out.as_str().into()),
+        ("mac", log.mac.as_str().into()),
+        ("src", log.src.as_str().into()),
+        ("dst", log.dst.as_str().into()),
+        ("len", log.len.into()),
+        ("tos", log.tos.as_deref().into()),
+        ("prec", log.prec.as_deref().into()),
+        ("ttl", log.ttl.into()),
+        ("id", log.id.into()),
+        ("df", log.df.into()),
+        ("proto", log.proto.as_str().into()),
+        ("spt", log.spt.into()),
+        ("dpt", log.dpt.into()),
+        ("window", log.window.into()),
+        ("res", log.res.as_str().into()),
+        ("cwr", log.cwr.into()),
+        ("ece", log.ece.into()),
+        ("urg", log.urg.into()),
+        ("ack", log.ack.into()),
+        ("psh", log.psh.into()),
+        ("rst", log.rst.into()),
+        ("syn", log.syn.into()),
+        ("fin", log.fin.into()),
+        ("urgp", log.urgp.into()),
+        ("tc", log.tc.into()),
+        ("hoplimit", log.hoplimit.into()),
+        ("flowlbl", log.flowlbl.into()),
+        ("type", log.r#type.into()),
+        ("code", log.code.into()),
+        ("seq", log.seq.into()),
+        ("mtu", log.mtu.into()),
+        ("mark", log.mark.into()),
+        ("physin", log.physin.as_deref().into()),
+        ("phyout", log.phyout.as_deref().into()),
+        ("origin", log.get_origin().into()),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::Export;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_escape() {
+        let value = Value::from("a \"quoted\" \\ string\n\u{1}");
+        assert_eq!(value.to_string(), r#""a \"quoted\" \\ string\n\u0001""#);
+    }
+
+    #[test]
+    #[cfg(feature = "http")]
+    fn test_parse() {
+        let text = r#" {"a": [1, -2.5e1, true, null], "b\"\u00e9\ud83d\ude00": {}, "c": []} "#;
+        let value = Value::parse(text).unwrap();
+        assert_eq!(
+            value,
+            Value::object([
+                (
+                    "a",
+                    Value::Array(vec![
+                        Value::Integer(1),
+                        Value::Float(-25.0),
+                        Value::Bool(true),
+                        Value::Null
+                    ])
+                ),
+                ("b\"é😀", Value::Object(vec![])),
+                ("c", Value::Array(vec![])),
+            ])
+        );
+        assert_eq!(value.get("c"), Some(&Value::Array(vec![])));
+        assert_eq!(Value::parse(&value.to_string()), Some(value));
+        assert_eq!(Value::parse(r#"{"a":1"#), None);
+        assert_eq!(Value::parse("[1] 2"), None);
+
+        // a high surrogate must be followed by a low surrogate
+        assert_eq!(Value::parse(r#""\ud83d\u0041""#), None);
+        assert_eq!(Value::parse(r#""\ud83d\ue000""#), None);
+
+        // deep nesting is rejected instead of overflowing the stack
+        let nested = |depth| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(Value::parse(&nested(MAX_DEPTH - 1)).is_some());
+        assert_eq!(Value::parse(&nested(MAX_DEPTH + 1)), None);
+        assert_eq!(Value::parse(&"[".repeat(1_000_000)), None);
+    }
+
+    #[test]
+    fn test_typed_value() {
+        let log = UfwLog::from_str("Jan 12 00:11:24 103213020 kernel: [3248415.842951] [UFW BLOCK] IN=es6 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:09:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 PROTO=TCP SPT=46468 DPT=42313 WINDOW=1024 RES=0x00 SYN URGP=0").unwrap();
+        let json = Exporter.convert(&log).unwrap();
+        assert!(json.starts_with(r#"{"month":1,"day":12,"time":"00:11:24","#));
+        assert!(json.contains(r#""policy":"BLOCK","in":"es6","out":"","#));
+        assert!(json.contains(r#""ttl":234,"id":17062,"df":false,"proto":"TCP","spt":46468,"#));
+        assert!(json.contains(r#""syn":true,"fin":false,"urgp":0,"tc":null,"#));
+    }
+
+    #[test]
+    fn test_export_iter() {
+        let logs = "Jan 12 00:11:17 103213020 kernel: [3248409.197732] [UFW AUDIT] IN= OUT=lo SRC=127.0.0.1 DST=127.0.0.1 LEN=84\nJan 12 00:11:18 103213020 kernel: [3248409.197732] [UFW AUDIT] IN= OUT=lo SRC=127.0.0.1 DST=127.0.0.1 LEN=84";
+        let mut iter = UfwLog::from_buf_reader(logs.as_bytes());
+        let mut output = vec![];
+        assert_eq!(Exporter.export_iter(&mut iter, &mut output).unwrap(), 2);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("[\n{\"month\":1,\"day\":12,\"time\":\"00:11:17\""));
+        assert!(output.contains("},\n{\"month\":1,\"day\":12,\"time\":\"00:11:18\""));
+        assert!(output.ends_with("}\n]\n"));
+    }
+
+    #[test]
+    fn test_export_empty() {
+        let mut output = vec![];
+        Exporter.export(&[], &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "[\n]\n");
+    }
+}
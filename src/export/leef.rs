@@ -0,0 +1,216 @@
+//! LEEF Exporter module
+//!
+//! Export logs as IBM QRadar [Log Event Extended Format](https://www.ibm.com/docs/en/dsm?topic=overview-leef-event-components)
+//! 2.0, one event per line:
+//!
+//! ```text
+//! LEEF:2.0|ufw|ufwlog|0.3.0|BLOCK|^|devTime=1705371232000^cat=BLOCK^sev=5^src=192.168.0.8^...
+//! ```
+//!
+//! Attributes are separated by tab by default, which is declared in header as `x09` (the example
+//! above uses `^`). Pipes and backslashes in header, and delimiters, backslashes and line breaks
+//! in attribute are escaped. Device vendor, product and [severity](super::cef::Severity) of each
+//! policy are configurable.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use ufwlog::UfwLog;
+//! use ufwlog::export::Export;
+//! use ufwlog::export::leef::Exporter as LeefExporter;
+//!
+//! let logs = UfwLog::from_file("./ufw.log")?;
+//! LeefExporter::new().delimiter('^').export(&logs, &mut std::io::stdout())?;
+//!
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::cef::Severity;
+use super::column::Field;
+use crate::error::Error;
+use crate::ufw_log::UfwLog;
+
+/// Exporter for LEEF 2.0 format
+///
+/// Default device vendor is `ufw`, device product is `ufwlog`, and delimiter is tab.
+#[derive(Debug, Clone)]
+pub struct Exporter {
+    vendor: String,
+    product: String,
+    severity: Severity,
+    delimiter: char,
+    year: Option<i32>,
+}
+
+impl Default for Exporter {
+    fn default() -> Self {
+        Self {
+            vendor: "ufw".to_string(),
+            product: "ufwlog".to_string(),
+            severity: Severity::default(),
+            delimiter: '\t',
+            year: None,
+        }
+    }
+}
+
+impl super::Export for Exporter {
+    fn get_extension(&self) -> &'static str {
+        "leef"
+    }
+
+    fn convert(&self, log: &UfwLog) -> Result<String, Error> {
+        let policy = log.policy.to_string();
+        let header = [
+            self.vendor.as_str(),
+            self.product.as_str(),
+            env!("CARGO_PKG_VERSION"),
+            &policy,
+        ]
+        .map(escape_header)
+        .join("|");
+        let delimiter = if self.delimiter.is_ascii_graphic() {
+            self.delimiter.to_string()
+        } else {
+            format!("x{:02X}", self.delimiter as u32)
+        };
+
+        let flags = Field::Flags.value(log);
+        let attributes = [
+            // epoch milliseconds is the default format of devTime without devTimeFormat
+            (
+                "devTime",
+                log.timestamp(self.year).map(|t| (t * 1000).to_string()),
+            ),
+            ("cat", Some(policy.clone())),
+            ("sev", Some(self.severity.get(log.policy).to_string())),
+            ("hostname", Some(log.hostname.clone())),
+            ("src", Some(log.src.clone())),
+            ("dst", Some(log.dst.clone())),
+            ("srcPort", log.spt.map(|p| p.to_string())),
+            ("dstPort", log.dpt.map(|p| p.to_string())),
+            ("proto", Some(log.proto.clone())),
+            ("srcBytes", Some(log.len.to_string())),
+            ("inInterface", Some(log.r#in.clone())),
+            ("outInterface", Some(log.out.clone())),
+            ("tcpFlags", Some(flags)),
+            ("ttl", log.ttl.map(|t| t.to_string())),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| match value {
+            Some(value) if !value.is_empty() => {
+                Some(format!("{key}={}", self.escape_attribute(&value)))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(&self.delimiter.to_string());
+
+        Ok(format!("LEEF:2.0|{header}|{delimiter}|{attributes}"))
+    }
+}
+
+impl Exporter {
+    /// New an exporter with default config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the device vendor.
+    pub fn vendor(mut self, vendor: &str) -> Self {
+        self.vendor = vendor.to_string();
+        self
+    }
+
+    /// Set the device product.
+    pub fn product(mut self, product: &str) -> Self {
+        self.product = product.to_string();
+        self
+    }
+
+    /// Set the severity of each policy.
+    pub fn severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Set the attribute delimiter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the delimiter is `=`, `|`, `\` or line break, which can't be escaped.
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        assert!(
+            !matches!(delimiter, '=' | '|' | '\\' | '\r' | '\n'),
+            "Delimiter can't be '=', '|', '\\\\' or line break"
+        );
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Set the year of logs, which is used to make `devTime`. See [`UfwLog::timestamp`] for the
+    /// default.
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+
+    /// Escape delimiter, backslash and line break in attribute value.
+    fn escape_attribute(&self, value: &str) -> String {
+        let mut escaped = String::with_capacity(value.len());
+        for c in value.chars() {
+            match c {
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                c if c == self.delimiter => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+}
+
+/// Escape pipe and backslash in header field.
+fn escape_header(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::Export;
+    use std::str::FromStr;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    #[test]
+    fn test_convert() {
+        let log = UfwLog::from_str(LOG).unwrap();
+        let leef = Exporter::new().year(2024).convert(&log).unwrap();
+        assert_eq!(
+            leef,
+            format!(
+                "LEEF:2.0|ufw|ufwlog|{}|BLOCK|x09|devTime=1705371232000\tcat=BLOCK\tsev=5\thostname=myhost\tsrc=192.168.0.8\tdst=127.0.111.111\tsrcPort=46468\tdstPort=22\tproto=TCP\tsrcBytes=40\tinInterface=eth0\ttcpFlags=SYN\tttl=234",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn test_delimiter_and_escape() {
+        let mut log = UfwLog::from_str(LOG).unwrap();
+        log.hostname = "my^host\\".to_string();
+        let leef = Exporter::new()
+            .delimiter('^')
+            .vendor("A|B")
+            .convert(&log)
+            .unwrap();
+        assert!(leef.starts_with("LEEF:2.0|A\\|B|ufwlog|"));
+        assert!(leef.contains("|BLOCK|^|"));
+        assert!(leef.contains("^hostname=my\\^host\\\\^src="));
+    }
+}
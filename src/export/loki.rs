@@ -0,0 +1,502 @@
+//! Loki Pusher module
+//!
+//! Push logs to [Grafana Loki](https://grafana.com/docs/loki/latest/reference/loki-http-api/#ingest-logs)
+//! through its push API. Each log is an entry whose line is the original log, grouped into
+//! streams by labels.
+//!
+//! Default labels are `job="ufw"`, `host`, `policy` and `proto`. IP addresses and source port
+//! can't be labels, because high cardinality labels make Loki slow, use LogQL parser instead,
+//! e.g. `{job="ufw"} | logfmt | DPT="22"`.
+//!
+//! Logs are pushed in batches, a batch is sent when it is full or the first log in it has waited
+//! for the batch wait time, so it is suitable for following a log file. The payload is JSON
+//! compressed by gzip, or protobuf compressed by snappy. Failed requests are retried with
+//! backoff, see [`Retry`].
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use ufwlog::export::loki::{Compression, Pusher};
+//! use ufwlog::follow::Follower;
+//!
+//! let mut logs = Follower::open("/var/log/ufw.log")?;
+//! Pusher::new("http://localhost:3100/loki/api/v1/push")
+//!     .compression(Compression::Snappy)
+//!     .push_iter(&mut logs)?;
+//!
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::json::Value;
+use crate::error::Error;
+use crate::http::Client;
+pub use crate::http::Retry;
+pub use crate::metrics::Label;
+use crate::time::Sequence;
+use crate::ufw_log::UfwLog;
+use std::io::Write;
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Payload encoding and compression
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Compression {
+    /// JSON without compression
+    None,
+    /// JSON compressed by gzip
+    #[default]
+    Gzip,
+    /// Protobuf compressed by snappy, which is used by Promtail
+    Snappy,
+}
+
+/// Push logs to Loki in batches
+///
+/// Default batch size is 1000 entries, and batch wait is 1 second.
+#[derive(Debug, Clone)]
+pub struct Pusher {
+    endpoint: String,
+    headers: Vec<(String, String)>,
+    static_labels: Vec<(String, String)>,
+    labels: Vec<Label>,
+    compression: Compression,
+    batch_size: usize,
+    batch_wait: Duration,
+    retry: Retry,
+    client: Client,
+    year: Option<i32>,
+}
+
+impl Pusher {
+    /// New a pusher of given endpoint, e.g. `http://localhost:3100/loki/api/v1/push`.
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            headers: vec![],
+            static_labels: vec![("job".to_string(), "ufw".to_string())],
+            labels: vec![Label::Hostname, Label::Policy, Label::Proto],
+            compression: Compression::default(),
+            batch_size: 1000,
+            batch_wait: Duration::from_secs(1),
+            retry: Retry::default(),
+            client: Client::default(),
+            year: None,
+        }
+    }
+
+    /// Set labels taken from log. Hostname is labeled as `host`, others are field names.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any label is [`Label::Src`], [`Label::Dst`] or [`Label::Spt`].
+    pub fn labels(mut self, labels: Vec<Label>) -> Self {
+        assert!(
+            !labels
+                .iter()
+                .any(|l| matches!(l, Label::Src | Label::Dst | Label::Spt)),
+            "src, dst and spt have too high cardinality to be labels"
+        );
+        self.labels = labels;
+        self
+    }
+
+    /// Add a label with fixed value, or replace the value of the same name. Default is
+    /// `job="ufw"`.
+    pub fn static_label(mut self, name: &str, value: &str) -> Self {
+        self.static_labels.retain(|(n, _)| n != name);
+        self.static_labels
+            .push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Set the payload encoding and compression.
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set the maximum entries of a batch.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size is zero.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "Batch size should be larger than 0");
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Set the maximum time to wait for more logs before sending a batch.
+    pub fn batch_wait(mut self, batch_wait: Duration) -> Self {
+        self.batch_wait = batch_wait;
+        self
+    }
+
+    /// Set the tenant of multi-tenant Loki, which is the `X-Scope-OrgID` header.
+    pub fn tenant(self, tenant: &str) -> Self {
+        self.header("X-Scope-OrgID", tenant)
+    }
+
+    /// Add a header to each request, e.g. authorization.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Set the retry policy, default is [`Retry::default`].
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Set the year of logs, which is used to make timestamp. See [`UfwLog::timestamp`] for the
+    /// default.
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+
+    /// Push logs from an iterator, return how many logs are pushed.
+    ///
+    /// Batches are sent by another thread, so a batch is sent on time even if the iterator is
+    /// waiting for new logs. It stops at the first error of iterator or push.
+    pub fn push_iter(
+        &self,
+        logs: &mut dyn Iterator<Item = Result<UfwLog, Error>>,
+    ) -> Result<usize, Error> {
+        std::thread::scope(|scope| {
+            let (sender, receiver) = sync_channel(self.batch_size);
+            let worker = scope.spawn(move || self.push_batches(receiver));
+            let mut count = 0;
+            let mut result = Ok(());
+            for log in logs {
+                match log {
+                    Ok(log) => {
+                        // the worker is stopped by error
+                        if sender.send(log).is_err() {
+                            break;
+                        }
+                        count += 1;
+                    }
+                    Err(e) => {
+                        result = Err(e);
+                        break;
+                    }
+                }
+            }
+            drop(sender);
+            let pushed = worker
+                .join()
+                .map_err(|_| Error::Export("Loki pusher panicked".to_string()))??;
+            result.map(|_| pushed.min(count))
+        })
+    }
+
+    /// Receive logs and send them in batches, return how many logs are pushed.
+    fn push_batches(&self, receiver: Receiver<UfwLog>) -> Result<usize, Error> {
+        let mut pushed = 0;
+        // continues across batches, so the logs of a second split into two batches are distinct
+        let mut sequence = Sequence::default();
+        // wait for the first log of a batch
+        while let Ok(first) = receiver.recv() {
+            let deadline = Instant::now() + self.batch_wait;
+            let mut batch = vec![first];
+            let mut closed = false;
+            while batch.len() < self.batch_size {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                match receiver.recv_timeout(timeout) {
+                    Ok(log) => batch.push(log),
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        closed = true;
+                        break;
+                    }
+                }
+            }
+            self.push(&batch, &mut sequence)?;
+            pushed += batch.len();
+            if closed {
+                break;
+            }
+        }
+        Ok(pushed)
+    }
+
+    /// Send a batch.
+    fn push(&self, logs: &[UfwLog], sequence: &mut Sequence) -> Result<(), Error> {
+        let streams = self.streams(logs, sequence);
+        let (content_type, body) = match self.compression {
+            Compression::None => ("application/json", json(&streams).into_bytes()),
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(json(&streams).as_bytes())?;
+                ("application/json", encoder.finish()?)
+            }
+            Compression::Snappy => (
+                "application/x-protobuf",
+                snap::raw::Encoder::new()
+                    .compress_vec(&protobuf(&streams))
+                    .map_err(|e| Error::Export(e.to_string()))?,
+            ),
+        };
+        let mut headers = vec![("Content-Type".to_string(), content_type.to_string())];
+        if self.compression == Compression::Gzip {
+            headers.push(("Content-Encoding".to_string(), "gzip".to_string()));
+        }
+        headers.extend(self.headers.iter().cloned());
+        self.client
+            .post(&self.endpoint, &headers, &body, &self.retry)
+            .map(|_| ())
+    }
+
+    /// Group logs into streams by labels, keep the order of logs in each stream.
+    ///
+    /// The timestamp of a log is its second plus its sequence in the second as nanoseconds, so
+    /// Loki keeps the order of logs and doesn't drop the same line in a second as duplicate.
+    /// The current time is used if the log has no timestamp or it's out of the range of
+    /// nanoseconds (after 2262).
+    fn streams(&self, logs: &[UfwLog], sequence: &mut Sequence) -> Vec<Stream> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as i64)
+            .unwrap_or_default();
+        let mut streams: Vec<Stream> = vec![];
+        for log in logs {
+            let labels = self
+                .static_labels
+                .iter()
+                .cloned()
+                .chain(self.labels.iter().map(|label| {
+                    let name = match label {
+                        Label::Hostname => "host",
+                        label => label.name(),
+                    };
+                    (name.to_string(), label.value(log))
+                }))
+                .filter(|(_, value)| !value.is_empty())
+                .collect::<Vec<_>>();
+            let timestamp = log
+                .timestamp(self.year)
+                .and_then(|t| sequence.next(t))
+                .unwrap_or(now);
+            let entry = (timestamp, log.get_origin().to_string());
+            match streams.iter_mut().find(|s| s.labels == labels) {
+                Some(stream) => stream.entries.push(entry),
+                None => streams.push(Stream {
+                    labels,
+                    entries: vec![entry],
+                }),
+            }
+        }
+        streams
+    }
+}
+
+/// A stream of entries with the same labels
+#[derive(Debug, PartialEq)]
+struct Stream {
+    labels: Vec<(String, String)>,
+    /// Timestamp in nanoseconds and line.
+    entries: Vec<(i64, String)>,
+}
+
+/// Encode streams as JSON push request.
+fn json(streams: &[Stream]) -> String {
+    let streams = streams
+        .iter()
+        .map(|stream| {
+            Value::object([
+                (
+                    "stream",
+                    Value::Object(
+                        stream
+                            .labels
+                            .iter()
+                            .map(|(name, value)| (name.clone(), value.as_str().into()))
+                            .collect(),
+                    ),
+                ),
+                (
+                    "values",
+                    Value::Array(
+                        stream
+                            .entries
+                            .iter()
+                            .map(|(timestamp, line)| {
+                                vec![timestamp.to_string(), line.clone()].into()
+                            })
+                            .collect(),
+                    ),
+                ),
+            ])
+        })
+        .collect();
+    Value::object([("streams", Value::Array(streams))]).to_string()
+}
+
+/// Encode streams as protobuf `logproto.PushRequest`.
+///
+/// ```text
+/// message PushRequest { repeated StreamAdapter streams = 1; }
+/// message StreamAdapter { string labels = 1; repeated EntryAdapter entries = 2; }
+/// message EntryAdapter { google.protobuf.Timestamp timestamp = 1; string line = 2; }
+/// message Timestamp { int64 seconds = 1; int32 nanos = 2; }
+/// ```
+fn protobuf(streams: &[Stream]) -> Vec<u8> {
+    let mut request = vec![];
+    for stream in streams {
+        let labels = stream
+            .labels
+            .iter()
+            .map(|(name, value)| format!("{name}=\"{}\"", crate::metrics::escape(value)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut message = vec![];
+        write_bytes(&mut message, 1, format!("{{{labels}}}").as_bytes());
+        for (timestamp, line) in &stream.entries {
+            let mut time = vec![];
+            write_varint_field(&mut time, 1, timestamp.div_euclid(1_000_000_000) as u64);
+            write_varint_field(&mut time, 2, timestamp.rem_euclid(1_000_000_000) as u64);
+            let mut entry = vec![];
+            write_bytes(&mut entry, 1, &time);
+            write_bytes(&mut entry, 2, line.as_bytes());
+            write_bytes(&mut message, 2, &entry);
+        }
+        write_bytes(&mut request, 1, &message);
+    }
+    request
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// Write a varint field, zero is omitted as default value.
+fn write_varint_field(buffer: &mut Vec<u8>, field: u64, value: u64) {
+    if value != 0 {
+        write_varint(buffer, field << 3);
+        write_varint(buffer, value);
+    }
+}
+
+/// Write a length-delimited field.
+fn write_bytes(buffer: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    write_varint(buffer, (field << 3) | 2);
+    write_varint(buffer, bytes.len() as u64);
+    buffer.extend_from_slice(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::str::FromStr;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    fn logs() -> Vec<UfwLog> {
+        vec![
+            UfwLog::from_str(LOG).unwrap(),
+            UfwLog::from_str(&LOG.replace("PROTO=TCP", "PROTO=UDP")).unwrap(),
+            UfwLog::from_str(LOG).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn test_json() {
+        let pusher = Pusher::new("").year(2024).static_label("env", "prod");
+        assert_eq!(
+            json(&pusher.streams(&logs(), &mut Sequence::default())),
+            format!(
+                r#"{{"streams":[{{"stream":{{"job":"ufw","env":"prod","host":"myhost","policy":"BLOCK","proto":"TCP"}},"values":[["1705371232000000000","{LOG}"],["1705371232000000002","{LOG}"]]}},{{"stream":{{"job":"ufw","env":"prod","host":"myhost","policy":"BLOCK","proto":"UDP"}},"values":[["1705371232000000001","{}"]]}}]}}"#,
+                LOG.replace("PROTO=TCP", "PROTO=UDP")
+            )
+        );
+    }
+
+    #[test]
+    fn test_protobuf() {
+        let streams = vec![Stream {
+            labels: vec![("job".to_string(), "u\"fw".to_string())],
+            entries: vec![(1_500_000_001, "hi".to_string())],
+        }];
+        let labels = br#"{job="u\"fw"}"#;
+        let mut expect = vec![0x0a, 31, 0x0a, labels.len() as u8];
+        expect.extend_from_slice(labels);
+        // entry: timestamp {seconds: 1, nanos: 500000001}, line
+        expect.extend_from_slice(&[
+            0x12, 0x0e, 0x0a, 0x08, 0x08, 0x01, 0x10, 0x81, 0xca, 0xb5, 0xee, 0x01,
+        ]);
+        expect.extend_from_slice(&[0x12, 0x02, b'h', b'i']);
+        assert_eq!(protobuf(&streams), expect);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_high_cardinality_label() {
+        let _ = Pusher::new("").labels(vec![Label::Policy, Label::Src]);
+    }
+
+    #[test]
+    fn test_push_gzip_with_retry() {
+        let (url, server) = crate::http::mock::serve(vec![503, 204, 204]);
+        let pusher = Pusher::new(&format!("{url}/loki/api/v1/push"))
+            .tenant("team-a")
+            .labels(vec![Label::Policy])
+            .batch_size(2)
+            .retry(Retry {
+                initial_backoff: Duration::from_millis(1),
+                ..Retry::default()
+            });
+        let mut logs = logs().into_iter().map(Ok);
+        assert_eq!(pusher.push_iter(&mut logs).unwrap(), 3);
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].path, "/loki/api/v1/push");
+        assert_eq!(requests[0].header("content-encoding"), Some("gzip"));
+        assert_eq!(requests[0].header("x-scope-orgid"), Some("team-a"));
+        // the first batch is retried
+        assert_eq!(requests[0].body, requests[1].body);
+        let mut body = String::new();
+        flate2::read::GzDecoder::new(&requests[2].body[..])
+            .read_to_string(&mut body)
+            .unwrap();
+        assert!(body
+            .starts_with(r#"{"streams":[{"stream":{"job":"ufw","policy":"BLOCK"},"values":[[""#));
+        assert_eq!(body.matches("kernel").count(), 1);
+    }
+
+    #[test]
+    fn test_push_snappy_on_batch_wait() {
+        let (url, server) = crate::http::mock::serve(vec![204]);
+        let pusher = Pusher::new(&url)
+            .compression(Compression::Snappy)
+            .batch_wait(Duration::from_millis(10));
+        // the iterator blocks until the batch is sent
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut logs = std::iter::once(UfwLog::from_str(LOG))
+            .chain(std::iter::from_fn(|| receiver.recv().ok()));
+        let server = std::thread::spawn(move || {
+            let requests = server.join().unwrap();
+            drop(sender);
+            requests
+        });
+        assert_eq!(pusher.push_iter(&mut logs).unwrap(), 1);
+
+        let requests = server.join().unwrap();
+        assert_eq!(
+            requests[0].header("content-type"),
+            Some("application/x-protobuf")
+        );
+        let body = snap::raw::Decoder::new()
+            .decompress_vec(&requests[0].body)
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains(LOG));
+    }
+}
@@ -0,0 +1,252 @@
+//! MessagePack Exporter module
+//!
+//! Export logs as a compact binary archive of [MessagePack](https://msgpack.org/) records, which
+//! can be read back by [msgpack importer](crate::import::msgpack).
+//!
+//! The file starts with the magic bytes `UFWM` and a header map, which carries the schema
+//! version and the field names of columns:
+//!
+//! ```text
+//! UFWM {"version": 1, "fields": ["month", "day", "time", ..., "origin"]}
+//! [1, 16, "02:13:52", "myhost", ...]
+//! [1, 16, "02:13:53", "myhost", ...]
+//! ```
+//!
+//! Then each record is an array of values in the order of fields. Numbers are integers, flags are
+//! booleans and absent values are nil. Because the fields are named in header, a reader can skip
+//! unknown fields written by newer version, and fill default value for missing fields.
+//!
+//! MessagePack is a binary format, so [`Export::convert`] is not supported.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use std::io::BufReader;
+//! use ufwlog::UfwLog;
+//! use ufwlog::export::Export;
+//! use ufwlog::export::msgpack::Exporter as MsgpackExporter;
+//!
+//! let reader = BufReader::new(std::fs::File::open("./ufw.log")?);
+//! let mut logs = UfwLog::from_buf_reader(reader);
+//! let mut file = std::io::BufWriter::new(std::fs::File::create("ufw.msgpack")?);
+//! MsgpackExporter::new().export_iter(&mut logs, &mut file)?;
+//!
+//! # std::fs::remove_file("ufw.msgpack").unwrap();
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::json::{to_value, Value};
+use super::Export;
+use crate::error::Error;
+use crate::ufw_log::UfwLog;
+use std::io::Write;
+
+/// Magic bytes at the start of file
+pub const MAGIC: [u8; 4] = *b"UFWM";
+
+/// Schema version of records, which is increased if the meaning of an existing field changes.
+/// Adding a field doesn't change the version.
+pub const VERSION: i64 = 1;
+
+/// Exporter for MessagePack archive
+#[derive(Debug, Clone, Default)]
+pub struct Exporter;
+
+impl Export for Exporter {
+    fn get_extension(&self) -> &'static str {
+        "msgpack"
+    }
+
+    fn convert(&self, _log: &UfwLog) -> Result<String, Error> {
+        Err(Error::Unsupported(
+            "msgpack is a binary format, a single log can't be converted to string".to_string(),
+        ))
+    }
+
+    fn begin(&self, writer: &mut dyn Write) -> Result<(), Error> {
+        // field names are the same for any log, take them from an empty log
+        let fields = match to_value(&UfwLog::from_hashmap(Default::default())?) {
+            Value::Object(fields) => fields
+                .into_iter()
+                .map(|(key, _)| Value::String(key))
+                .collect::<Vec<_>>(),
+            _ => unreachable!("log is converted into an object"),
+        };
+        let header = Value::object([
+            ("version", Value::Integer(VERSION)),
+            ("fields", Value::Array(fields)),
+        ]);
+        let mut buffer = MAGIC.to_vec();
+        encode(&header, &mut buffer);
+        writer.write_all(&buffer)?;
+        Ok(())
+    }
+
+    fn write_record(
+        &self,
+        _index: usize,
+        log: &UfwLog,
+        writer: &mut dyn Write,
+    ) -> Result<(), Error> {
+        let values = match to_value(log) {
+            Value::Object(fields) => fields.into_iter().map(|(_, value)| value).collect(),
+            _ => unreachable!("log is converted into an object"),
+        };
+        let mut buffer = vec![];
+        encode(&Value::Array(values), &mut buffer);
+        writer.write_all(&buffer)?;
+        Ok(())
+    }
+}
+
+impl Exporter {
+    /// New an exporter
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Encode a value in the smallest MessagePack representation.
+pub(crate) fn encode(value: &Value, buffer: &mut Vec<u8>) {
+    match value {
+        Value::Null => buffer.push(0xc0),
+        Value::Bool(false) => buffer.push(0xc2),
+        Value::Bool(true) => buffer.push(0xc3),
+        Value::Integer(n) => encode_integer(*n, buffer),
+        Value::Float(n) => {
+            buffer.push(0xcb);
+            buffer.extend_from_slice(&n.to_be_bytes());
+        }
+        Value::String(s) => {
+            encode_length(s.len(), 0xa0, 32, [0xd9, 0xda, 0xdb], buffer);
+            buffer.extend_from_slice(s.as_bytes());
+        }
+        Value::Array(values) => {
+            encode_length(values.len(), 0x90, 16, [0, 0xdc, 0xdd], buffer);
+            values.iter().for_each(|value| encode(value, buffer));
+        }
+        Value::Object(fields) => {
+            encode_length(fields.len(), 0x80, 16, [0, 0xde, 0xdf], buffer);
+            for (key, value) in fields {
+                encode(&Value::String(key.clone()), buffer);
+                encode(value, buffer);
+            }
+        }
+    }
+}
+
+fn encode_integer(n: i64, buffer: &mut Vec<u8>) {
+    match n {
+        0..=127 => buffer.push(n as u8),
+        -32..=-1 => buffer.push(n as i8 as u8),
+        128..=0xff => buffer.extend_from_slice(&[0xcc, n as u8]),
+        0x100..=0xffff => {
+            buffer.push(0xcd);
+            buffer.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            buffer.push(0xce);
+            buffer.extend_from_slice(&(n as u32).to_be_bytes());
+        }
+        -128..=-33 => buffer.extend_from_slice(&[0xd0, n as i8 as u8]),
+        -32768..=-129 => {
+            buffer.push(0xd1);
+            buffer.extend_from_slice(&(n as i16).to_be_bytes());
+        }
+        -2_147_483_648..=-32769 => {
+            buffer.push(0xd2);
+            buffer.extend_from_slice(&(n as i32).to_be_bytes());
+        }
+        _ => {
+            buffer.push(0xd3);
+            buffer.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+}
+
+/// Write the type and length of string, array or map. `markers` are of 8, 16 and 32 bits length,
+/// a zero marker means the size isn't available for the type.
+fn encode_length(len: usize, fix: u8, fix_max: usize, markers: [u8; 3], buffer: &mut Vec<u8>) {
+    if len < fix_max {
+        buffer.push(fix | len as u8);
+    } else if len <= 0xff && markers[0] != 0 {
+        buffer.extend_from_slice(&[markers[0], len as u8]);
+    } else if len <= 0xffff {
+        buffer.push(markers[1]);
+        buffer.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        buffer.push(markers[2]);
+        buffer.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    fn encoded(value: Value) -> Vec<u8> {
+        let mut buffer = vec![];
+        encode(&value, &mut buffer);
+        buffer
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encoded(Value::Null), [0xc0]);
+        assert_eq!(encoded(Value::Bool(true)), [0xc3]);
+        assert_eq!(encoded(Value::Integer(22)), [0x16]);
+        assert_eq!(encoded(Value::Integer(-1)), [0xff]);
+        assert_eq!(encoded(Value::Integer(234)), [0xcc, 0xea]);
+        assert_eq!(encoded(Value::Integer(46468)), [0xcd, 0xb5, 0x84]);
+        assert_eq!(encoded(Value::Integer(70000)), [0xce, 0, 1, 0x11, 0x70]);
+        assert_eq!(encoded(Value::Integer(-100)), [0xd0, 0x9c]);
+        assert_eq!(encoded(Value::Integer(-200)), [0xd1, 0xff, 0x38]);
+        assert_eq!(
+            encoded(Value::Integer(1 << 40)),
+            [0xd3, 0, 0, 1, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            encoded(Value::Float(1.5)),
+            [0xcb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(encoded("eth0".into()), b"\xa4eth0");
+        assert_eq!(encoded("a".repeat(32).into())[..2], [0xd9, 32]);
+        assert_eq!(encoded("a".repeat(256).into())[..3], [0xda, 1, 0]);
+        assert_eq!(
+            encoded(Value::Array(vec![Value::Null; 16]))[..3],
+            [0xdc, 0, 16]
+        );
+        assert_eq!(
+            encoded(Value::object([("a", Value::Bool(false))])),
+            b"\x81\xa1a\xc2"
+        );
+    }
+
+    #[test]
+    fn test_export() {
+        let logs = vec![UfwLog::from_str(LOG).unwrap()];
+        let mut output = vec![];
+        Exporter::new().export(&logs, &mut output).unwrap();
+
+        assert!(output.starts_with(b"UFWM\x82\xa7version\x01\xa6fields\xdc\x00\x2a\xa5month"));
+        let record = encoded(Value::Array(match to_value(&logs[0]) {
+            Value::Object(fields) => fields.into_iter().map(|(_, value)| value).collect(),
+            _ => unreachable!(),
+        }));
+        // month, day and time
+        assert!(record.starts_with(b"\xdc\x00\x2a\x01\x10\xa802:13:52"));
+        assert!(output.ends_with(&record));
+    }
+
+    #[test]
+    fn test_convert() {
+        let log = UfwLog::from_str(LOG).unwrap();
+        assert!(matches!(
+            Exporter::new().convert(&log),
+            Err(Error::Unsupported(_))
+        ));
+    }
+}
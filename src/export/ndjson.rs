@@ -0,0 +1,34 @@
+//! NDJSON Exporter module
+//!
+//! Export logs as [newline delimited JSON](https://github.com/ndjson/ndjson-spec), one object per
+//! line. The object is the same as [json](super::json) exporter, but each line is a complete JSON
+//! document, so the output can be processed line by line.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use ufwlog::UfwLog;
+//! use ufwlog::export::Export;
+//! use ufwlog::export::ndjson::Exporter as NdjsonExporter;
+//!
+//! let logs = UfwLog::from_file("./ufw.log")?;
+//! NdjsonExporter.export(&logs, &mut std::io::stdout())?;
+//!
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use crate::error::Error;
+use crate::ufw_log::UfwLog;
+
+/// Exporter for NDJSON format
+pub struct Exporter;
+
+impl super::Export for Exporter {
+    fn get_extension(&self) -> &'static str {
+        "ndjson"
+    }
+
+    fn convert(&self, log: &UfwLog) -> Result<String, Error> {
+        Ok(super::json::to_value(log).to_string())
+    }
+}
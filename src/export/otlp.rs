@@ -0,0 +1,488 @@
+//! OTLP Exporter module
+//!
+//! Export logs as [OpenTelemetry](https://opentelemetry.io/docs/specs/otlp/) log records in
+//! OTLP/JSON, one `ExportLogsServiceRequest` per line, which can be replayed by the `otlpjsonfile`
+//! receiver of OpenTelemetry Collector, or POST them to an OTLP/HTTP endpoint with
+//! [`HttpSender`] (requires `http` feature).
+//!
+//! Logs of the same host are grouped into a resource with `host.name` and `service.name`
+//! attributes. Each log record has:
+//!
+//! | Field                 | Source                                                       |
+//! |-----------------------|--------------------------------------------------------------|
+//! | `timeUnixNano`        | date and time, see [`UfwLog::timestamp`]                     |
+//! | `severityNumber`      | `WARN` (13) for block, `INFO2` (10) for invalid and unknown, `INFO` (9) for others |
+//! | `body`                | [`UfwLog::get_origin`]                                       |
+//! | `network.transport`   | `tcp` or `udp`, omitted for other protocols                  |
+//! | `network.type`        | `ipv4` or `ipv6`                                             |
+//! | `source.address`, `source.port` | src, spt                                           |
+//! | `destination.address`, `destination.port` | dst, dpt                                 |
+//!
+//! Other fields of [`UfwLog`] are attributes prefixed with `ufw.` (`ufw.policy`, `ufw.in`,
+//! `ufw.ttl`, ...), flags are an array in `ufw.flags`. Absent and empty attributes are omitted.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use ufwlog::UfwLog;
+//! use ufwlog::export::Export;
+//! use ufwlog::export::otlp::Exporter as OtlpExporter;
+//!
+//! let logs = UfwLog::from_file("./ufw.log")?;
+//! OtlpExporter::new().year(2024).export(&logs, &mut std::io::stdout())?;
+//!
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::column::Field;
+use super::json::Value;
+use super::Export;
+use crate::error::Error;
+use crate::ufw_log::{Policy, UfwLog};
+use std::borrow::Borrow;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "http")]
+use crate::http::Client;
+#[cfg(feature = "http")]
+pub use crate::http::Retry;
+
+/// Exporter for OTLP/JSON format
+///
+/// Default `service.name` is `ufw`, and each request contains at most 512 log records.
+#[derive(Debug, Clone)]
+pub struct Exporter {
+    service_name: String,
+    batch_size: usize,
+    year: Option<i32>,
+}
+
+impl Default for Exporter {
+    fn default() -> Self {
+        Self {
+            service_name: "ufw".to_string(),
+            batch_size: 512,
+            year: None,
+        }
+    }
+}
+
+impl Export for Exporter {
+    fn get_extension(&self) -> &'static str {
+        "jsonl"
+    }
+
+    /// Convert a log into a request which contains only this log record.
+    fn convert(&self, log: &UfwLog) -> Result<String, Error> {
+        Ok(self.request(&[log]).to_string())
+    }
+
+    fn export(&self, logs: &[UfwLog], writer: &mut dyn Write) -> Result<(), Error> {
+        for batch in logs.chunks(self.batch_size) {
+            writeln!(writer, "{}", self.request(batch))?;
+        }
+        self.finish(writer)
+    }
+
+    fn export_iter(
+        &self,
+        logs: &mut dyn Iterator<Item = Result<UfwLog, Error>>,
+        writer: &mut dyn Write,
+    ) -> Result<usize, Error> {
+        let count = self.for_each_batch(logs, &mut |batch| {
+            writeln!(writer, "{}", self.request(batch))?;
+            Ok(())
+        })?;
+        self.finish(writer)?;
+        Ok(count)
+    }
+}
+
+impl Exporter {
+    /// New an exporter with default config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `service.name` resource attribute.
+    pub fn service_name(mut self, service_name: &str) -> Self {
+        self.service_name = service_name.to_string();
+        self
+    }
+
+    /// Set the maximum log records of a request.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size is zero.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "Batch size should be larger than 0");
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Set the year of logs, which is used to make `timeUnixNano`. See [`UfwLog::timestamp`] for
+    /// the default.
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+
+    /// POST logs from an iterator to OTLP/HTTP endpoint in batches, return how many logs are
+    /// sent.
+    #[cfg(feature = "http")]
+    pub fn send_iter(
+        &self,
+        logs: &mut dyn Iterator<Item = Result<UfwLog, Error>>,
+        sender: &HttpSender,
+    ) -> Result<usize, Error> {
+        self.for_each_batch(logs, &mut |batch| {
+            sender.send(self.request(batch).to_string().as_bytes())
+        })
+    }
+
+    /// Call `f` with logs in batches, return how many logs are read.
+    fn for_each_batch(
+        &self,
+        logs: &mut dyn Iterator<Item = Result<UfwLog, Error>>,
+        f: &mut dyn FnMut(&[UfwLog]) -> Result<(), Error>,
+    ) -> Result<usize, Error> {
+        let mut count = 0;
+        let mut batch = Vec::with_capacity(self.batch_size);
+        for log in logs {
+            batch.push(log?);
+            count += 1;
+            if batch.len() == self.batch_size {
+                f(&batch)?;
+                batch.clear();
+            }
+        }
+        if !batch.is_empty() {
+            f(&batch)?;
+        }
+        Ok(count)
+    }
+
+    /// Make an `ExportLogsServiceRequest` of logs, which are grouped by hostname into resources.
+    fn request<L: Borrow<UfwLog>>(&self, logs: &[L]) -> Value {
+        let observed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos().to_string())
+            .unwrap_or_default();
+        let mut hosts: Vec<(&str, Vec<Value>)> = vec![];
+        for log in logs {
+            let log = log.borrow();
+            let record = self.log_record(log, &observed);
+            match hosts.iter_mut().find(|(host, _)| *host == log.hostname) {
+                Some((_, records)) => records.push(record),
+                None => hosts.push((&log.hostname, vec![record])),
+            }
+        }
+
+        let resource_logs = hosts
+            .into_iter()
+            .map(|(host, records)| {
+                Value::object([
+                    (
+                        "resource",
+                        Value::object([(
+                            "attributes",
+                            attributes(vec![
+                                ("host.name", host.into()),
+                                ("service.name", self.service_name.as_str().into()),
+                            ]),
+                        )]),
+                    ),
+                    (
+                        "scopeLogs",
+                        Value::Array(vec![Value::object([
+                            (
+                                "scope",
+                                Value::object([
+                                    ("name", "ufwlog".into()),
+                                    ("version", env!("CARGO_PKG_VERSION").into()),
+                                ]),
+                            ),
+                            ("logRecords", Value::Array(records)),
+                        ])]),
+                    ),
+                ])
+            })
+            .collect();
+        Value::object([("resourceLogs", Value::Array(resource_logs))])
+    }
+
+    /// Convert a log into OTLP `LogRecord`.
+    fn log_record(&self, log: &UfwLog, observed: &str) -> Value {
+        let flags = Field::Flags.value(log);
+        let flags = flags.split_whitespace().collect::<Vec<_>>();
+        // only tcp and udp are in the semantic conventions
+        let transport = match log.proto.to_lowercase().as_str() {
+            "tcp" => Some("tcp"),
+            "udp" => Some("udp"),
+            _ => None,
+        };
+        let (severity_number, severity_text) = severity(log.policy);
+
+        let mut pairs = vec![];
+        // 0 means unknown, omit it, as well as the time out of the range of nanoseconds
+        if let Some(timestamp) = log.timestamp(self.year).and_then(crate::time::nanos) {
+            pairs.push(("timeUnixNano".to_string(), timestamp.to_string().into()));
+        }
+        pairs.extend([
+            ("observedTimeUnixNano".to_string(), observed.into()),
+            ("severityNumber".to_string(), severity_number.into()),
+            ("severityText".to_string(), severity_text.into()),
+            (
+                "body".to_string(),
+                any_value(log.get_origin().into()).unwrap_or(Value::Null),
+            ),
+            (
+                "attributes".to_string(),
+                attributes(vec![
+                    ("network.transport", transport.into()),
+                    (
+                        "network.type",
+                        if log.src.contains(':') {
+                            "ipv6"
+                        } else {
+                            "ipv4"
+                        }
+                        .into(),
+                    ),
+                    ("source.address", log.src.as_str().into()),
+                    ("source.port", log.spt.into()),
+                    ("destination.address", log.dst.as_str().into()),
+                    ("destination.port", log.dpt.into()),
+                    ("ufw.policy", log.policy.to_string().into()),
+                    ("ufw.uptime", log.uptime.trim().into()),
+                    ("ufw.in", log.r#in.as_str().into()),
+                    ("ufw.out", log.out.as_str().into()),
+                    ("ufw.mac", log.mac.as_str().into()),
+                    ("ufw.len", log.len.into()),
+                    ("ufw.tos", log.tos.as_deref().into()),
+                    ("ufw.prec", log.prec.as_deref().into()),
+                    ("ufw.ttl", log.ttl.into()),
+                    ("ufw.id", log.id.into()),
+                    ("ufw.df", log.df.into()),
+                    ("ufw.window", log.window.into()),
+                    ("ufw.res", log.res.as_str().into()),
+                    ("ufw.flags", flags.into()),
+                    ("ufw.urgp", log.urgp.into()),
+                    ("ufw.tc", log.tc.into()),
+                    ("ufw.hoplimit", log.hoplimit.into()),
+                    ("ufw.flowlbl", log.flowlbl.into()),
+                    ("ufw.type", log.r#type.into()),
+                    ("ufw.code", log.code.into()),
+                    ("ufw.seq", log.seq.into()),
+                    ("ufw.mtu", log.mtu.into()),
+                    ("ufw.mark", log.mark.into()),
+                    ("ufw.physin", log.physin.as_deref().into()),
+                    ("ufw.phyout", log.phyout.as_deref().into()),
+                ]),
+            ),
+        ]);
+        Value::Object(pairs)
+    }
+}
+
+/// OTel severity number and text of policy.
+fn severity(policy: Policy) -> (u8, &'static str) {
+    match policy {
+        Policy::Block | Policy::LimitBlock => (13, "WARN"),
+        Policy::AuditInvalid | Policy::Unknown => (10, "INFO2"),
+        Policy::Allow | Policy::Audit => (9, "INFO"),
+    }
+}
+
+/// Make `KeyValue` list, absent and empty values are omitted.
+fn attributes(pairs: Vec<(&str, Value)>) -> Value {
+    Value::Array(
+        pairs
+            .into_iter()
+            .filter_map(|(key, value)| {
+                any_value(value).map(|value| Value::object([("key", key.into()), ("value", value)]))
+            })
+            .collect(),
+    )
+}
+
+/// Convert JSON value into OTLP `AnyValue`, return `None` if it is null or empty.
+///
+/// 64-bit integers are strings in OTLP/JSON.
+fn any_value(value: Value) -> Option<Value> {
+    let (key, value) = match value {
+        Value::Null => return None,
+        Value::Bool(b) => ("boolValue", Value::Bool(b)),
+        Value::Integer(n) => ("intValue", n.to_string().into()),
+        Value::Float(n) => ("doubleValue", Value::Float(n)),
+        Value::String(s) if s.is_empty() => return None,
+        Value::String(s) => ("stringValue", s.into()),
+        Value::Array(values) if values.is_empty() => return None,
+        Value::Array(values) => (
+            "arrayValue",
+            Value::object([(
+                "values",
+                Value::Array(values.into_iter().filter_map(any_value).collect()),
+            )]),
+        ),
+        Value::Object(_) => unreachable!("nested attributes are not used"),
+    };
+    Some(Value::object([(key, value)]))
+}
+
+/// Send OTLP/JSON requests to OTLP/HTTP endpoint
+#[cfg(feature = "http")]
+#[derive(Debug, Clone)]
+pub struct HttpSender {
+    endpoint: String,
+    headers: Vec<(String, String)>,
+    retry: Retry,
+    client: Client,
+}
+
+#[cfg(feature = "http")]
+impl HttpSender {
+    /// New a sender of given endpoint, e.g. `http://localhost:4318/v1/logs`.
+    ///
+    /// The endpoint is used as is, `/v1/logs` isn't appended.
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            retry: Retry::default(),
+            client: Client::default(),
+        }
+    }
+
+    /// Add a header to each request, e.g. authorization.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Set the retry policy, default is [`Retry::default`].
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// POST a request.
+    fn send(&self, request: &[u8]) -> Result<(), Error> {
+        self.client
+            .post(&self.endpoint, &self.headers, request, &self.retry)
+            .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    #[test]
+    fn test_convert() {
+        let log = UfwLog::from_str(LOG).unwrap();
+        let json = Exporter::new().year(2024).convert(&log).unwrap();
+        assert!(json.starts_with(r#"{"resourceLogs":[{"resource":{"attributes":[{"key":"host.name","value":{"stringValue":"myhost"}},{"key":"service.name","value":{"stringValue":"ufw"}}]},"scopeLogs":[{"scope":{"name":"ufwlog","version":""#));
+        assert!(json.contains(
+            r#""logRecords":[{"timeUnixNano":"1705371232000000000","observedTimeUnixNano":""#
+        ));
+        assert!(json.contains(r#""severityNumber":13,"severityText":"WARN","body":{"stringValue":"Jan 16 02:13:52 myhost"#));
+        assert!(json.contains(r#"{"key":"network.transport","value":{"stringValue":"tcp"}},{"key":"network.type","value":{"stringValue":"ipv4"}},{"key":"source.address","value":{"stringValue":"192.168.0.8"}},{"key":"source.port","value":{"intValue":"46468"}},{"key":"destination.address","value":{"stringValue":"127.0.111.111"}},{"key":"destination.port","value":{"intValue":"22"}}"#));
+        assert!(json.contains(r#"{"key":"ufw.df","value":{"boolValue":true}}"#));
+        assert!(json.contains(
+            r#"{"key":"ufw.flags","value":{"arrayValue":{"values":[{"stringValue":"SYN"}]}}}"#
+        ));
+        assert!(!json.contains("ufw.out"));
+        assert!(!json.contains('\n'));
+
+        // flags in the same order as csv, transport only for tcp and udp
+        let icmp = LOG
+            .replace("PROTO=TCP", "PROTO=ICMP")
+            .replace("SYN URGP", "FIN ACK SYN URGP");
+        let json = Exporter::new()
+            .convert(&UfwLog::from_str(&icmp).unwrap())
+            .unwrap();
+        assert!(
+            json.contains(r#"{"stringValue":"SYN"},{"stringValue":"ACK"},{"stringValue":"FIN"}"#)
+        );
+        assert!(!json.contains("network.transport"));
+
+        // out of the range of nanoseconds
+        let json = Exporter::new().year(2300).convert(&log).unwrap();
+        assert!(json.contains(r#""logRecords":[{"observedTimeUnixNano":""#));
+    }
+
+    #[test]
+    fn test_export_iter_batch() {
+        let other = LOG.replace("myhost", "other");
+        let mut logs = vec![
+            UfwLog::from_str(LOG),
+            UfwLog::from_str(&other),
+            UfwLog::from_str(LOG),
+        ]
+        .into_iter();
+        let mut output = vec![];
+        let count = Exporter::new()
+            .batch_size(2)
+            .service_name("edge")
+            .export_iter(&mut logs, &mut output)
+            .unwrap();
+        assert_eq!(count, 3);
+
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        // two hosts in the first batch
+        assert_eq!(lines[0].matches(r#""scopeLogs""#).count(), 2);
+        assert!(lines[0].contains(r#"{"stringValue":"other"}"#));
+        assert_eq!(lines[1].matches(r#""scopeLogs""#).count(), 1);
+        assert!(lines[1].contains(r#"{"key":"service.name","value":{"stringValue":"edge"}}"#));
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_send_iter() {
+        let (url, server) = crate::http::mock::serve(vec![200, 200]);
+        let sender = HttpSender::new(&format!("{url}/v1/logs")).header("Authorization", "Bearer t");
+        let mut logs = (0..3)
+            .map(|_| UfwLog::from_str(LOG))
+            .collect::<Vec<_>>()
+            .into_iter();
+        let count = Exporter::new()
+            .batch_size(2)
+            .send_iter(&mut logs, &sender)
+            .unwrap();
+        assert_eq!(count, 3);
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/v1/logs");
+        assert_eq!(requests[0].header("content-type"), Some("application/json"));
+        assert_eq!(requests[0].header("authorization"), Some("Bearer t"));
+        let body = String::from_utf8(requests[0].body.clone()).unwrap();
+        assert!(body.starts_with(r#"{"resourceLogs":"#));
+        assert_eq!(body.matches(r#""timeUnixNano""#).count(), 2);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_send_error_status() {
+        let (url, server) = crate::http::mock::serve(vec![503, 503]);
+        let mut logs = vec![UfwLog::from_str(LOG)].into_iter();
+        let retry = Retry {
+            max_retries: 1,
+            initial_backoff: std::time::Duration::from_millis(1),
+            ..Retry::default()
+        };
+        let sender = HttpSender::new(&url).retry(retry);
+        let result = Exporter::new().send_iter(&mut logs, &sender);
+        assert!(matches!(result, Err(Error::Http(_))));
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+}
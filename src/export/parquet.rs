@@ -0,0 +1,258 @@
+//! Parquet Exporter module
+//!
+//! Export logs as [Apache Parquet](https://parquet.apache.org/) with typed columns, the schema is
+//! the same as [arrow](crate::arrow) module.
+//!
+//! Parquet is a binary format, so [`Export::convert`] is not supported.
+//!
+//! This module requires `parquet` feature.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use std::io::BufReader;
+//! use ufwlog::UfwLog;
+//! use ufwlog::export::Export;
+//! use ufwlog::export::parquet::{Compression, Exporter as ParquetExporter};
+//!
+//! let reader = BufReader::new(std::fs::File::open("./ufw.log")?);
+//! let mut logs = UfwLog::from_buf_reader(reader);
+//! let mut file = std::fs::File::create("output.parquet")?;
+//! let exporter = ParquetExporter::new()
+//!     .compression(Compression::Zstd)
+//!     .row_group_size(100_000);
+//! exporter.export_iter(&mut logs, &mut file)?;
+//!
+//! # std::fs::remove_file("output.parquet").unwrap();
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::Export;
+use crate::arrow::{schema, to_record_batch, RecordBatches};
+use crate::error::Error;
+use crate::ufw_log::UfwLog;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression as ParquetCompression, GzipLevel, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use std::io::Write;
+
+/// Compression codec of parquet pages
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Compression {
+    None,
+    #[default]
+    Snappy,
+    Gzip,
+    Zstd,
+}
+
+impl From<Compression> for ParquetCompression {
+    fn from(value: Compression) -> Self {
+        match value {
+            Compression::None => ParquetCompression::UNCOMPRESSED,
+            Compression::Snappy => ParquetCompression::SNAPPY,
+            Compression::Gzip => ParquetCompression::GZIP(GzipLevel::default()),
+            Compression::Zstd => ParquetCompression::ZSTD(ZstdLevel::default()),
+        }
+    }
+}
+
+/// Exporter for parquet format
+///
+/// Default is snappy compressed, and 1,048,576 rows per row group.
+#[derive(Debug, Clone)]
+pub struct Exporter {
+    compression: Compression,
+    row_group_size: usize,
+    year: Option<i32>,
+}
+
+impl Default for Exporter {
+    fn default() -> Self {
+        Self {
+            compression: Compression::default(),
+            row_group_size: 1024 * 1024,
+            year: None,
+        }
+    }
+}
+
+impl Export for Exporter {
+    fn get_extension(&self) -> &'static str {
+        "parquet"
+    }
+
+    fn convert(&self, _log: &UfwLog) -> Result<String, Error> {
+        Err(Error::Unsupported(
+            "parquet is a binary format, a single log can't be converted to string".to_string(),
+        ))
+    }
+
+    fn export(&self, logs: &[UfwLog], writer: &mut dyn Write) -> Result<(), Error> {
+        let mut parquet = self.writer()?;
+        for chunk in logs.chunks(self.row_group_size) {
+            parquet.write(&to_record_batch(chunk, self.year)?)?;
+            parquet.flush()?;
+            drain(&mut parquet, writer)?;
+        }
+        writer.write_all(&parquet.into_inner()?)?;
+        Ok(writer.flush()?)
+    }
+
+    /// Export logs from an iterator, only one row group is kept in memory.
+    fn export_iter(
+        &self,
+        logs: &mut dyn Iterator<Item = Result<UfwLog, Error>>,
+        writer: &mut dyn Write,
+    ) -> Result<usize, Error> {
+        let mut parquet = self.writer()?;
+        let mut count = 0;
+        let mut batches = RecordBatches::new(logs, self.row_group_size);
+        if let Some(year) = self.year {
+            batches = batches.year(year);
+        }
+        for batch in batches {
+            let batch = batch?;
+            count += batch.num_rows();
+            parquet.write(&batch)?;
+            parquet.flush()?;
+            drain(&mut parquet, writer)?;
+        }
+        writer.write_all(&parquet.into_inner()?)?;
+        writer.flush()?;
+        Ok(count)
+    }
+}
+
+impl Exporter {
+    /// New an exporter with default config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the compression codec.
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set the maximum rows of a row group, which is also the maximum rows kept in memory when
+    /// exporting from iterator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size is zero.
+    pub fn row_group_size(mut self, row_group_size: usize) -> Self {
+        assert!(row_group_size > 0, "Row group size should be positive");
+        self.row_group_size = row_group_size;
+        self
+    }
+
+    /// Set the year of logs, which is used to make `timestamp` column. See
+    /// [`UfwLog::timestamp`] for the default.
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+
+    /// Make a parquet writer which writes into memory buffer.
+    fn writer(&self) -> Result<ArrowWriter<Vec<u8>>, Error> {
+        let properties = WriterProperties::builder()
+            .set_compression(self.compression.into())
+            .set_max_row_group_size(self.row_group_size)
+            .build();
+        Ok(ArrowWriter::try_new(vec![], schema(), Some(properties))?)
+    }
+}
+
+/// Move the written bytes from memory buffer into writer.
+///
+/// The parquet writer counts the written bytes itself, so the buffer can be drained after each
+/// row group to keep memory usage low.
+fn drain(parquet: &mut ArrowWriter<Vec<u8>>, writer: &mut dyn Write) -> Result<(), Error> {
+    let buffer = parquet.inner_mut();
+    writer.write_all(buffer)?;
+    buffer.clear();
+    Ok(())
+}
+
+impl From<parquet::errors::ParquetError> for Error {
+    fn from(value: parquet::errors::ParquetError) -> Self {
+        Self::Export(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::TimestampMillisecondType;
+    use arrow_array::RecordBatch;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use std::str::FromStr;
+
+    const LOG: &str = "Jan 16 02:13:52 hostname kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:09:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=42313 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    fn read(output: Vec<u8>) -> Vec<RecordBatch> {
+        ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::from(output))
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_export() {
+        let logs = (0..5)
+            .map(|_| UfwLog::from_str(LOG).unwrap())
+            .collect::<Vec<_>>();
+        let mut output = vec![];
+        Exporter::new()
+            .year(2024)
+            .row_group_size(2)
+            .export(&logs, &mut output)
+            .unwrap();
+
+        let batches = read(output);
+        // 5 rows in row groups of 2
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 5);
+        let batch = &batches[0];
+        assert_eq!(batch.schema().fields(), schema().fields());
+        let timestamp = batch
+            .column_by_name("timestamp")
+            .unwrap()
+            .as_primitive::<TimestampMillisecondType>();
+        assert_eq!(timestamp.value(0), 1705371232000);
+    }
+
+    #[test]
+    fn test_export_iter() {
+        let mut logs = std::iter::repeat_with(|| UfwLog::from_str(LOG)).take(7);
+        let mut output = vec![];
+        let count = Exporter::new()
+            .compression(Compression::Zstd)
+            .row_group_size(3)
+            .export_iter(&mut logs, &mut output)
+            .unwrap();
+        assert_eq!(count, 7);
+        assert_eq!(read(output).iter().map(|b| b.num_rows()).sum::<usize>(), 7);
+
+        // empty input still produces a valid file
+        let mut output = vec![];
+        let count = Exporter::new()
+            .export_iter(&mut std::iter::empty(), &mut output)
+            .unwrap();
+        assert_eq!(count, 0);
+        assert!(read(output).is_empty());
+    }
+
+    #[test]
+    fn test_convert_unsupported() {
+        let log = UfwLog::from_str(LOG).unwrap();
+        assert!(matches!(
+            Exporter::new().convert(&log),
+            Err(Error::Unsupported(_))
+        ));
+    }
+}
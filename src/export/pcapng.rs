@@ -0,0 +1,464 @@
+//! Pcapng Exporter module
+//!
+//! Export logs as synthetic packets in [pcapng](https://www.ietf.org/archive/id/draft-ietf-opsawg-pcapng-02.html)
+//! format, so they can be inspected in Wireshark or tcpdump.
+//!
+//! ufw log only has the headers of packet, so each packet is rebuilt with Ethernet, IPv4 or IPv6,
+//! and TCP, UDP or ICMP headers, without payload. The original length of packet is kept, so
+//! Wireshark shows them as truncated captures. Each packet has:
+//!
+//! - timestamp of log in seconds, see [`UfwLog::timestamp`]
+//! - a comment with policy, hostname and interfaces, e.g. `[UFW BLOCK] myhost IN=eth0 OUT=`
+//! - direction flag, inbound if it has `IN` only, outbound if it has `OUT` only
+//!
+//! MAC addresses are zero if the log has no `MAC`. The IPv4 header checksum is calculated, but
+//! the checksums of TCP, UDP and ICMP are zero because the payload is unknown.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use ufwlog::UfwLog;
+//! use ufwlog::export::Export;
+//! use ufwlog::export::pcapng::Exporter as PcapngExporter;
+//!
+//! let logs = UfwLog::from_file("./ufw.log")?;
+//! let mut file = std::fs::File::create("ufw.pcapng")?;
+//! PcapngExporter::new().year(2024).export(&logs, &mut file)?;
+//!
+//! # std::fs::remove_file("ufw.pcapng").unwrap();
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::Export;
+use crate::error::Error;
+use crate::ufw_log::UfwLog;
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+const SECTION_HEADER_BLOCK: u32 = 0x0A0D_0D0A;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 1;
+const ENHANCED_PACKET_BLOCK: u32 = 6;
+const LINKTYPE_ETHERNET: u16 = 1;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+
+/// Exporter for pcapng format
+#[derive(Debug, Clone, Default)]
+pub struct Exporter {
+    year: Option<i32>,
+}
+
+impl Export for Exporter {
+    fn get_extension(&self) -> &'static str {
+        "pcapng"
+    }
+
+    fn convert(&self, _log: &UfwLog) -> Result<String, Error> {
+        Err(Error::Unsupported(
+            "pcapng is a binary format, a single log can't be converted to string".to_string(),
+        ))
+    }
+
+    /// Write the section header and an Ethernet interface.
+    fn begin(&self, writer: &mut dyn Write) -> Result<(), Error> {
+        let mut body = vec![];
+        body.extend_from_slice(&0x1A2B_3C4Du32.to_le_bytes());
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        // section length is unknown
+        body.extend_from_slice(&(-1i64).to_le_bytes());
+        // shb_userappl
+        let application = format!("ufwlog {}", env!("CARGO_PKG_VERSION"));
+        write_option(&mut body, 4, application.as_bytes());
+        write_option(&mut body, 0, &[]);
+        write_block(writer, SECTION_HEADER_BLOCK, &body)?;
+
+        let mut body = vec![];
+        body.extend_from_slice(&LINKTYPE_ETHERNET.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        // no limit of snapshot length
+        body.extend_from_slice(&0u32.to_le_bytes());
+        write_block(writer, INTERFACE_DESCRIPTION_BLOCK, &body)?;
+        Ok(())
+    }
+
+    /// Write an enhanced packet block.
+    fn write_record(
+        &self,
+        _index: usize,
+        log: &UfwLog,
+        writer: &mut dyn Write,
+    ) -> Result<(), Error> {
+        let frame = frame(log);
+        let original_length = (14 + ip_length(log) as usize).max(frame.len());
+        // pcapng timestamp is unsigned, so the time before 1970 is written as 0
+        let timestamp = log
+            .timestamp(self.year)
+            .and_then(|t| u64::try_from(t).ok())
+            .unwrap_or_default()
+            .saturating_mul(1_000_000);
+
+        let mut body = vec![];
+        // interface ID
+        body.extend_from_slice(&0u32.to_le_bytes());
+        // timestamp in microseconds, high 32 bits first
+        body.extend_from_slice(&((timestamp >> 32) as u32).to_le_bytes());
+        body.extend_from_slice(&(timestamp as u32).to_le_bytes());
+        body.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        body.extend_from_slice(&(original_length as u32).to_le_bytes());
+        body.extend_from_slice(&frame);
+        pad(&mut body);
+
+        let comment = format!(
+            "[UFW {}] {} IN={} OUT={}",
+            log.policy, log.hostname, log.r#in, log.out
+        );
+        write_option(&mut body, 1, comment.as_bytes());
+        // epb_flags, bits 0-1 are direction
+        let direction: u32 = match (log.r#in.is_empty(), log.out.is_empty()) {
+            (false, true) => 1,
+            (true, false) => 2,
+            _ => 0,
+        };
+        if direction != 0 {
+            write_option(&mut body, 2, &direction.to_le_bytes());
+        }
+        write_option(&mut body, 0, &[]);
+        write_block(writer, ENHANCED_PACKET_BLOCK, &body)
+    }
+}
+
+impl Exporter {
+    /// New an exporter with default config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the year of logs, which is used to make timestamp of packets. See
+    /// [`UfwLog::timestamp`] for the default. The timestamp before 1970 is 0.
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+}
+
+/// Write a block with type and total length around the body, which should be padded to 32 bits.
+fn write_block(writer: &mut dyn Write, block_type: u32, body: &[u8]) -> Result<(), Error> {
+    let length = (body.len() as u32 + 12).to_le_bytes();
+    writer.write_all(&block_type.to_le_bytes())?;
+    writer.write_all(&length)?;
+    writer.write_all(body)?;
+    writer.write_all(&length)?;
+    Ok(())
+}
+
+/// Write an option with code and value padded to 32 bits.
+fn write_option(body: &mut Vec<u8>, code: u16, value: &[u8]) {
+    body.extend_from_slice(&code.to_le_bytes());
+    body.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    body.extend_from_slice(value);
+    pad(body);
+}
+
+fn pad(body: &mut Vec<u8>) {
+    body.resize(body.len().div_ceil(4) * 4, 0);
+}
+
+/// Values of a key in original log, in order.
+///
+/// Some keys appear twice, e.g. `LEN` of IP and UDP, `ID` of IP and ICMP echo, but the parsed
+/// log only keeps the last one.
+fn values<'a>(log: &'a UfwLog, key: &str) -> Vec<&'a str> {
+    log.get_origin()
+        .split_whitespace()
+        .filter_map(|pair| pair.split_once('='))
+        .filter(|(k, _)| *k == key)
+        .map(|(_, v)| v)
+        .collect()
+}
+
+/// Total length of IP packet, which is the first `LEN` of log.
+fn ip_length(log: &UfwLog) -> u32 {
+    values(log, "LEN")
+        .first()
+        .and_then(|len| len.parse().ok())
+        .unwrap_or(log.len)
+}
+
+/// IP protocol number of `PROTO`.
+fn protocol_number(proto: &str) -> u8 {
+    match proto.to_uppercase().as_str() {
+        "ICMP" => 1,
+        "IGMP" => 2,
+        "TCP" => 6,
+        "UDP" => 17,
+        "GRE" => 47,
+        "ESP" => 50,
+        "AH" => 51,
+        "ICMPV6" => 58,
+        "SCTP" => 132,
+        "UDPLITE" => 136,
+        p => p.parse().unwrap_or_default(),
+    }
+}
+
+/// Build an Ethernet frame with IP and transport headers of log.
+fn frame(log: &UfwLog) -> Vec<u8> {
+    let src = log.src.parse::<IpAddr>().ok();
+    let dst = log.dst.parse::<IpAddr>().ok();
+    let ipv6 = matches!(src, Some(IpAddr::V6(_))) || matches!(dst, Some(IpAddr::V6(_)));
+    let protocol = protocol_number(&log.proto);
+    let transport = transport(log, protocol);
+
+    let mut frame = vec![];
+    let mac = log
+        .mac
+        .split(':')
+        .map(|octet| u8::from_str_radix(octet, 16))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_default();
+    if mac.len() >= 12 {
+        frame.extend_from_slice(&mac[..12]);
+    } else {
+        frame.extend_from_slice(&[0; 12]);
+    }
+    let ethertype = if ipv6 { ETHERTYPE_IPV6 } else { ETHERTYPE_IPV4 };
+    frame.extend_from_slice(&ethertype.to_be_bytes());
+
+    let length = ip_length(log);
+    if ipv6 {
+        let address = |ip: Option<IpAddr>| match ip {
+            Some(IpAddr::V6(ip)) => ip,
+            _ => Ipv6Addr::UNSPECIFIED,
+        };
+        let tc = u32::from(log.tc.unwrap_or_default());
+        let flow_label = log.flowlbl.unwrap_or_default() & 0xF_FFFF;
+        frame.extend_from_slice(&((6 << 28) | (tc << 20) | flow_label).to_be_bytes());
+        frame.extend_from_slice(&(length.saturating_sub(40) as u16).to_be_bytes());
+        frame.push(protocol);
+        frame.push(log.hoplimit.unwrap_or(64));
+        frame.extend_from_slice(&address(src).octets());
+        frame.extend_from_slice(&address(dst).octets());
+    } else {
+        let address = |ip: Option<IpAddr>| match ip {
+            Some(IpAddr::V4(ip)) => ip,
+            _ => Ipv4Addr::UNSPECIFIED,
+        };
+        let hex = |value: &Option<String>| {
+            value
+                .as_deref()
+                .and_then(|v| u8::from_str_radix(v.trim_start_matches("0x"), 16).ok())
+                .unwrap_or_default()
+        };
+        let id = values(log, "ID")
+            .first()
+            .and_then(|id| id.parse::<u16>().ok())
+            .unwrap_or_default();
+        let mut header = vec![0x45, hex(&log.tos) | hex(&log.prec)];
+        header.extend_from_slice(&(length as u16).to_be_bytes());
+        header.extend_from_slice(&id.to_be_bytes());
+        header.extend_from_slice(&(if log.df { 0x4000u16 } else { 0 }).to_be_bytes());
+        header.push(log.ttl.map_or(64, |ttl| ttl.min(255) as u8));
+        header.push(protocol);
+        header.extend_from_slice(&[0, 0]);
+        header.extend_from_slice(&address(src).octets());
+        header.extend_from_slice(&address(dst).octets());
+        let checksum = checksum(&header);
+        header[10..12].copy_from_slice(&checksum.to_be_bytes());
+        frame.extend_from_slice(&header);
+    }
+    frame.extend_from_slice(&transport);
+    frame
+}
+
+/// Build the transport header, which is empty for unsupported protocols.
+fn transport(log: &UfwLog, protocol: u8) -> Vec<u8> {
+    let mut header = vec![];
+    match protocol {
+        // TCP
+        6 => {
+            header.extend_from_slice(&log.spt.unwrap_or_default().to_be_bytes());
+            header.extend_from_slice(&log.dpt.unwrap_or_default().to_be_bytes());
+            header.extend_from_slice(&log.seq.unwrap_or_default().to_be_bytes());
+            // acknowledgment number
+            header.extend_from_slice(&[0; 4]);
+            let reserved = u8::from_str_radix(log.res.trim_start_matches("0x"), 16).unwrap_or(0);
+            header.push((5 << 4) | (reserved & 0x0F));
+            let flags = [
+                log.cwr, log.ece, log.urg, log.ack, log.psh, log.rst, log.syn, log.fin,
+            ];
+            header.push(flags.iter().fold(0, |byte, flag| (byte << 1) | *flag as u8));
+            let window = log.window.unwrap_or_default().min(u32::from(u16::MAX)) as u16;
+            header.extend_from_slice(&window.to_be_bytes());
+            // checksum
+            header.extend_from_slice(&[0, 0]);
+            header.extend_from_slice(&log.urgp.unwrap_or_default().to_be_bytes());
+        }
+        // UDP and UDP-Lite
+        17 | 136 => {
+            header.extend_from_slice(&log.spt.unwrap_or_default().to_be_bytes());
+            header.extend_from_slice(&log.dpt.unwrap_or_default().to_be_bytes());
+            // the second `LEN` is length of UDP
+            let length = values(log, "LEN")
+                .get(1)
+                .and_then(|len| len.parse::<u16>().ok())
+                .unwrap_or_default();
+            header.extend_from_slice(&length.to_be_bytes());
+            // checksum
+            header.extend_from_slice(&[0, 0]);
+        }
+        // ICMP and ICMPv6
+        1 | 58 => {
+            let icmp_type = log.r#type.unwrap_or_default();
+            header.push(icmp_type);
+            header.push(log.code.unwrap_or_default());
+            // checksum
+            header.extend_from_slice(&[0, 0]);
+            let echo = match protocol {
+                1 => matches!(icmp_type, 0 | 8),
+                _ => matches!(icmp_type, 128 | 129),
+            };
+            let too_big = match protocol {
+                1 => icmp_type == 3 && log.code == Some(4),
+                _ => icmp_type == 2,
+            };
+            if echo {
+                // identifier of echo is the last `ID`, after `ID` of IPv4
+                let id = values(log, "ID")
+                    .last()
+                    .and_then(|id| id.parse::<u16>().ok())
+                    .unwrap_or_default();
+                header.extend_from_slice(&id.to_be_bytes());
+                let seq = log.seq.unwrap_or_default() as u16;
+                header.extend_from_slice(&seq.to_be_bytes());
+            } else if too_big {
+                let mtu = u32::from(log.mtu.unwrap_or_default());
+                header.extend_from_slice(&mtu.to_be_bytes());
+            } else {
+                header.extend_from_slice(&[0; 4]);
+            }
+        }
+        _ => {}
+    }
+    header
+}
+
+/// Internet checksum of header.
+fn checksum(header: &[u8]) -> u16 {
+    let mut sum = header
+        .chunks(2)
+        .map(|pair| u32::from(pair[0]) << 8 | u32::from(*pair.get(1).unwrap_or(&0)))
+        .sum::<u32>();
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const TCP: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    #[test]
+    fn test_tcp_frame() {
+        let frame = frame(&UfwLog::from_str(TCP).unwrap());
+        assert_eq!(frame.len(), 14 + 20 + 20);
+        assert_eq!(
+            frame[..14],
+            [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xcc, 0xff, 0x20, 0x19, 0x01, 0x01, 0x08, 0x00]
+        );
+        let ip = &frame[14..34];
+        assert_eq!(ip[..10], [0x45, 0, 0, 40, 0x42, 0xa6, 0x40, 0, 234, 6]);
+        assert_eq!(ip[12..], [192, 168, 0, 8, 127, 0, 111, 111]);
+        assert_eq!(checksum(ip), 0);
+        let tcp = &frame[34..];
+        assert_eq!(tcp[..4], [0xb5, 0x84, 0, 22]);
+        assert_eq!(tcp[12..16], [0x50, 0x02, 0x04, 0x00]);
+    }
+
+    #[test]
+    fn test_udp_frame() {
+        let log = UfwLog::from_str("Apr 22 09:21:07 7C56 kernel: [ 3353.096838] [UFW BLOCK] IN=enp42s0 OUT= MAC= SRC=192.168.1.147 DST=230.230.230.230 LEN=160 TOS=0x00 PREC=0x00 TTL=1 ID=54530 DF PROTO=UDP SPT=60948 DPT=8978 LEN=140").unwrap();
+        let frame = frame(&log);
+        assert_eq!(frame.len(), 14 + 20 + 8);
+        assert_eq!(frame[..12], [0; 12]);
+        // total length of IP and UDP
+        assert_eq!(frame[16..18], 160u16.to_be_bytes());
+        assert_eq!(frame[38..40], 140u16.to_be_bytes());
+        assert_eq!(ip_length(&log), 160);
+    }
+
+    #[test]
+    fn test_icmpv6_frame() {
+        let log = UfwLog::from_str("Jan 16 02:13:52 myhost kernel: [3601090.569259] [UFW AUDIT] IN= OUT=lo SRC=::1 DST=fe80::1 LEN=104 TC=0 HOPLIMIT=64 FLOWLBL=12345 PROTO=ICMPv6 TYPE=128 CODE=0 ID=10289 SEQ=1").unwrap();
+        let frame = frame(&log);
+        assert_eq!(frame.len(), 14 + 40 + 8);
+        assert_eq!(frame[12..14], [0x86, 0xdd]);
+        assert_eq!(frame[14..18], (0x6000_0000u32 | 12345).to_be_bytes());
+        // payload length, next header and hop limit
+        assert_eq!(frame[18..22], [0, 64, 58, 64]);
+        assert_eq!(
+            frame[38..54],
+            "fe80::1".parse::<Ipv6Addr>().unwrap().octets()
+        );
+        assert_eq!(frame[54..], [128, 0, 0, 0, 0x28, 0x31, 0, 1]);
+    }
+
+    #[test]
+    fn test_year_before_epoch() {
+        let logs = vec![UfwLog::from_str(TCP).unwrap()];
+        let mut output = vec![];
+        Exporter::new()
+            .year(1960)
+            .export(&logs, &mut output)
+            .unwrap();
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(output[offset..offset + 4].try_into().unwrap());
+        let idb = u32_at(4) as usize;
+        let epb = idb + u32_at(idb + 4) as usize;
+        assert_eq!(u32_at(epb), ENHANCED_PACKET_BLOCK);
+        assert_eq!((u32_at(epb + 12), u32_at(epb + 16)), (0, 0));
+    }
+
+    #[test]
+    fn test_export() {
+        let logs = vec![UfwLog::from_str(TCP).unwrap()];
+        let mut output = vec![];
+        Exporter::new()
+            .year(2024)
+            .export(&logs, &mut output)
+            .unwrap();
+
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(output[offset..offset + 4].try_into().unwrap());
+        // section header block
+        assert_eq!(u32_at(0), SECTION_HEADER_BLOCK);
+        assert_eq!(u32_at(8), 0x1A2B_3C4D);
+        let shb = u32_at(4) as usize;
+        assert_eq!(u32_at(shb - 4), shb as u32);
+        // interface description block
+        assert_eq!(u32_at(shb), INTERFACE_DESCRIPTION_BLOCK);
+        assert_eq!(output[shb + 8..shb + 10], LINKTYPE_ETHERNET.to_le_bytes());
+        let epb = shb + u32_at(shb + 4) as usize;
+        // enhanced packet block
+        assert_eq!(u32_at(epb), ENHANCED_PACKET_BLOCK);
+        let length = u32_at(epb + 4) as usize;
+        assert_eq!(length % 4, 0);
+        assert_eq!(epb + length, output.len());
+        let timestamp = (u64::from(u32_at(epb + 12)) << 32) | u64::from(u32_at(epb + 16));
+        assert_eq!(timestamp, 1_705_371_232_000_000);
+        assert_eq!(u32_at(epb + 20), 54);
+        assert_eq!(u32_at(epb + 24), 54);
+        let options = &output[epb + 28 + 56..epb + length - 4];
+        let comment = b"[UFW BLOCK] myhost IN=eth0 OUT=";
+        assert_eq!(options[..2], 1u16.to_le_bytes());
+        assert_eq!(options[2..4], (comment.len() as u16).to_le_bytes());
+        assert_eq!(options[4..4 + comment.len()], comment[..]);
+        // inbound
+        assert_eq!(options[36..44], [2, 0, 4, 0, 1, 0, 0, 0]);
+    }
+}
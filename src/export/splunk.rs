@@ -0,0 +1,370 @@
+//! Splunk Exporter module
+//!
+//! Export logs as [Splunk HTTP Event Collector](https://docs.splunk.com/Documentation/Splunk/latest/Data/FormateventsforHTTPEventCollector)
+//! events, one per line, or POST them to HEC with [`HecSender`] (requires `http` feature):
+//!
+//! ```text
+//! {"time":1705371232,"host":"myhost","sourcetype":"ufw","event":{"month":1,"day":16,...}}
+//! ```
+//!
+//! The event is the same object as [ndjson](super::ndjson) export, whose keys are the field names
+//! of [`UfwLog`]. `source` and `index` are only written if they are set, otherwise the defaults
+//! of HEC token are used.
+//!
+//! ## Acknowledgement
+//!
+//! If indexer acknowledgement is enabled on the HEC token, set a channel by
+//! [`HecSender::channel`]. Then the sender waits until each batch is indexed before sending the
+//! next one, so a batch is never lost silently.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use ufwlog::UfwLog;
+//! use ufwlog::export::Export;
+//! use ufwlog::export::splunk::Exporter as SplunkExporter;
+//!
+//! let logs = UfwLog::from_file("./ufw.log")?;
+//! SplunkExporter::new().index("firewall").export(&logs, &mut std::io::stdout())?;
+//!
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::json::Value;
+use super::Export;
+use crate::error::Error;
+use crate::ufw_log::UfwLog;
+
+#[cfg(feature = "http")]
+use crate::http::Client;
+#[cfg(feature = "http")]
+pub use crate::http::Retry;
+#[cfg(feature = "http")]
+use std::time::{Duration, Instant};
+
+/// Exporter for Splunk HEC event format
+///
+/// Default sourcetype is `ufw`, and each request contains at most 100 events.
+#[derive(Debug, Clone)]
+pub struct Exporter {
+    sourcetype: String,
+    source: Option<String>,
+    index: Option<String>,
+    batch_size: usize,
+    year: Option<i32>,
+}
+
+impl Default for Exporter {
+    fn default() -> Self {
+        Self {
+            sourcetype: "ufw".to_string(),
+            source: None,
+            index: None,
+            batch_size: 100,
+            year: None,
+        }
+    }
+}
+
+impl Export for Exporter {
+    fn get_extension(&self) -> &'static str {
+        "json"
+    }
+
+    fn convert(&self, log: &UfwLog) -> Result<String, Error> {
+        let mut event = vec![
+            (
+                "time",
+                log.timestamp(self.year).map_or(Value::Null, Value::Integer),
+            ),
+            ("host", log.hostname.as_str().into()),
+            ("source", self.source.as_deref().into()),
+            ("sourcetype", self.sourcetype.as_str().into()),
+            ("index", self.index.as_deref().into()),
+            ("event", super::json::to_value(log)),
+        ];
+        event.retain(|(_, value)| *value != Value::Null);
+        Ok(Value::Object(
+            event
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+        .to_string())
+    }
+}
+
+impl Exporter {
+    /// New an exporter with default config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the sourcetype of events.
+    pub fn sourcetype(mut self, sourcetype: &str) -> Self {
+        self.sourcetype = sourcetype.to_string();
+        self
+    }
+
+    /// Set the source of events, e.g. `/var/log/ufw.log`.
+    pub fn source(mut self, source: &str) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    /// Set the index of events, which should be allowed by the HEC token.
+    pub fn index(mut self, index: &str) -> Self {
+        self.index = Some(index.to_string());
+        self
+    }
+
+    /// Set the maximum events of a request.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size is zero.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "Batch size should be larger than 0");
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Set the year of logs, which is used to make `time`. See [`UfwLog::timestamp`] for the
+    /// default.
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+
+    /// POST logs from an iterator to HEC in batches, return how many logs are sent.
+    #[cfg(feature = "http")]
+    pub fn send_iter(
+        &self,
+        logs: &mut dyn Iterator<Item = Result<UfwLog, Error>>,
+        sender: &HecSender,
+    ) -> Result<usize, Error> {
+        let mut count = 0;
+        let mut batch = String::new();
+        let mut size = 0;
+        for log in logs {
+            batch += &self.convert(&log?)?;
+            batch.push('\n');
+            size += 1;
+            count += 1;
+            if size == self.batch_size {
+                sender.send(batch.as_bytes())?;
+                batch.clear();
+                size = 0;
+            }
+        }
+        if size > 0 {
+            sender.send(batch.as_bytes())?;
+        }
+        Ok(count)
+    }
+}
+
+/// Send events to Splunk HTTP Event Collector
+#[cfg(feature = "http")]
+#[derive(Debug, Clone)]
+pub struct HecSender {
+    url: String,
+    headers: Vec<(String, String)>,
+    channel: Option<String>,
+    ack_timeout: Duration,
+    ack_interval: Duration,
+    retry: Retry,
+    client: Client,
+}
+
+#[cfg(feature = "http")]
+impl HecSender {
+    /// New a sender of given HEC URL (e.g. `https://localhost:8088`) and token.
+    ///
+    /// Events are sent to `/services/collector/event` of the URL.
+    pub fn new(url: &str, token: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            headers: vec![
+                ("Content-Type".to_string(), "application/json".to_string()),
+                ("Authorization".to_string(), format!("Splunk {token}")),
+            ],
+            channel: None,
+            ack_timeout: Duration::from_secs(60),
+            ack_interval: Duration::from_secs(1),
+            retry: Retry::default(),
+            client: Client::default(),
+        }
+    }
+
+    /// Set the channel of requests, which is a GUID, and wait for indexer acknowledgement of
+    /// each request.
+    pub fn channel(mut self, channel: &str) -> Self {
+        self.headers
+            .push(("X-Splunk-Request-Channel".to_string(), channel.to_string()));
+        self.channel = Some(channel.to_string());
+        self
+    }
+
+    /// Set how long to wait for acknowledgement of a request, default is 60 seconds.
+    pub fn ack_timeout(mut self, timeout: Duration) -> Self {
+        self.ack_timeout = timeout;
+        self
+    }
+
+    /// Set how often to query acknowledgement, default is every second.
+    pub fn ack_interval(mut self, interval: Duration) -> Self {
+        self.ack_interval = interval;
+        self
+    }
+
+    /// Set the retry policy, default is [`Retry::default`].
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// POST events, and wait for acknowledgement if channel is set.
+    fn send(&self, events: &[u8]) -> Result<(), Error> {
+        let url = format!("{}/services/collector/event", self.url);
+        let response = self.client.post(&url, &self.headers, events, &self.retry)?;
+        let channel = match &self.channel {
+            Some(channel) => channel,
+            None => return Ok(()),
+        };
+        let ack_id = Value::parse(&response)
+            .and_then(|r| r.get("ackId").and_then(Value::as_i64))
+            .ok_or_else(|| {
+                Error::Http(format!(
+                    "No ackId in HEC response, is acknowledgement enabled? {response}"
+                ))
+            })?;
+
+        let url = format!("{}/services/collector/ack?channel={channel}", self.url);
+        let query = Value::object([("acks", vec![Value::Integer(ack_id)].into())]).to_string();
+        let deadline = Instant::now() + self.ack_timeout;
+        loop {
+            let response = self
+                .client
+                .post(&url, &self.headers, query.as_bytes(), &self.retry)?;
+            let acked = Value::parse(&response)
+                .and_then(|r| r.get("acks")?.get(&ack_id.to_string()).cloned());
+            if acked == Some(Value::Bool(true)) {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::Http(format!(
+                    "Events of ackId {ack_id} are not indexed in {:?}",
+                    self.ack_timeout
+                )));
+            }
+            std::thread::sleep(self.ack_interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    #[test]
+    fn test_convert() {
+        let log = UfwLog::from_str(LOG).unwrap();
+        let event = Exporter::new()
+            .index("firewall")
+            .year(2024)
+            .convert(&log)
+            .unwrap();
+        let expected = format!(
+            r#"{{"time":1705371232,"host":"myhost","sourcetype":"ufw","index":"firewall","event":{}}}"#,
+            super::super::json::to_value(&log)
+        );
+        assert_eq!(event, expected);
+
+        let event = Exporter::new()
+            .source("/var/log/ufw.log")
+            .sourcetype("linux:ufw")
+            .year(2024)
+            .convert(&log)
+            .unwrap();
+        assert!(event.starts_with(
+            r#"{"time":1705371232,"host":"myhost","source":"/var/log/ufw.log","sourcetype":"linux:ufw","event":{"month":1,"#
+        ));
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_send_iter() {
+        let (url, server) = crate::http::mock::serve_responses(vec![
+            (200, r#"{"text":"Success","code":0}"#.to_string()),
+            (200, r#"{"text":"Success","code":0}"#.to_string()),
+        ]);
+        let sender = HecSender::new(&url, "secret");
+        let mut logs = (0..3).map(|_| UfwLog::from_str(LOG));
+        let count = Exporter::new()
+            .batch_size(2)
+            .send_iter(&mut logs, &sender)
+            .unwrap();
+        assert_eq!(count, 3);
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests[0].path, "/services/collector/event");
+        assert_eq!(requests[0].header("authorization"), Some("Splunk secret"));
+        assert_eq!(requests[0].header("x-splunk-request-channel"), None);
+        assert_eq!(
+            String::from_utf8_lossy(&requests[0].body).lines().count(),
+            2
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&requests[1].body).lines().count(),
+            1
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_send_with_ack() {
+        let channel = "0aeeac95-ac74-4aa9-b30d-6c4c0ac581ba";
+        let (url, server) = crate::http::mock::serve_responses(vec![
+            (200, r#"{"text":"Success","code":0,"ackId":7}"#.to_string()),
+            (200, r#"{"acks":{"7":false}}"#.to_string()),
+            (200, r#"{"acks":{"7":true}}"#.to_string()),
+        ]);
+        let sender = HecSender::new(&url, "secret")
+            .channel(channel)
+            .ack_interval(Duration::from_millis(1));
+        let mut logs = std::iter::once(UfwLog::from_str(LOG));
+        Exporter::new().send_iter(&mut logs, &sender).unwrap();
+
+        let requests = server.join().unwrap();
+        assert_eq!(
+            requests[0].header("x-splunk-request-channel"),
+            Some(channel)
+        );
+        assert_eq!(
+            requests[1].path,
+            format!("/services/collector/ack?channel={channel}")
+        );
+        assert_eq!(requests[1].body, br#"{"acks":[7]}"#);
+        assert_eq!(requests[2].body, br#"{"acks":[7]}"#);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_ack_timeout() {
+        let (url, server) = crate::http::mock::serve_responses(vec![
+            (200, r#"{"text":"Success","code":0,"ackId":0}"#.to_string()),
+            (200, r#"{"acks":{"0":false}}"#.to_string()),
+        ]);
+        let sender = HecSender::new(&url, "secret")
+            .channel("0aeeac95-ac74-4aa9-b30d-6c4c0ac581ba")
+            .ack_timeout(Duration::ZERO);
+        let mut logs = std::iter::once(UfwLog::from_str(LOG));
+        let result = Exporter::new().send_iter(&mut logs, &sender);
+        assert!(matches!(result, Err(Error::Http(e)) if e.contains("ackId 0")));
+        server.join().unwrap();
+    }
+}
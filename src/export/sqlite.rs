@@ -0,0 +1,449 @@
+//! SQLite Exporter module
+//!
+//! Insert logs into a [SQLite](https://sqlite.org/) table with typed columns, so the logs can be
+//! queried with SQL. The table has indexes on `timestamp`, `src`, `dst`, `dpt` and `policy`.
+//!
+//! A database is not a stream, so this exporter writes into a [`Connection`] instead of
+//! implementing [`Export`](super::Export). Logs are inserted in transactions of
+//! [`batch_size`](Exporter::batch_size) rows.
+//!
+//! Each record is identified by hostname and the hash of original log line, a record already in
+//! the table is skipped. So it is safe to export the same log file again in
+//! [append](Exporter::append) mode, only the new records are inserted. The timestamp is not part
+//! of the key, since its year is inferred and may differ between exports of the same line.
+//!
+//! All fields of a log are in one table, one row per log. The fields are single values of a
+//! packet without repeating groups, and splitting e.g. hostnames or interfaces into lookup
+//! tables would save little space but need joins for every ad-hoc query. The columns are the
+//! same as other tabular formats, so a query can be moved between them.
+//!
+//! This module requires `sqlite` feature.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use std::io::BufReader;
+//! use ufwlog::UfwLog;
+//! use ufwlog::export::sqlite::{Connection, Exporter as SqliteExporter};
+//!
+//! let reader = BufReader::new(std::fs::File::open("./ufw.log")?);
+//! let mut logs = UfwLog::from_buf_reader(reader);
+//! let mut conn = Connection::open("fw.db")?;
+//! let summary = SqliteExporter::new()
+//!     .append(true)
+//!     .export_iter(&mut logs, &mut conn)?;
+//! println!("{} of {} records are inserted", summary.inserted, summary.read);
+//!
+//! let blocked: i64 = conn.query_row(
+//!     "SELECT count(*) FROM ufwlog WHERE policy = 'BLOCK'",
+//!     [],
+//!     |row| row.get(0),
+//! )?;
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::fnv1a;
+use crate::error::Error;
+use crate::ufw_log::UfwLog;
+pub use rusqlite::Connection;
+use rusqlite::{params, Transaction};
+use std::borrow::Borrow;
+
+/// Columns of table, except the auto increment `rowid`.
+///
+/// `timestamp` is unix timestamp in seconds (see [`UfwLog::timestamp`]), `uptime` is a real
+/// number, booleans are `0` or `1`, and `origin_hash` is the 64-bit FNV-1a hash of `origin`.
+/// Others are the same as fields of [`UfwLog`].
+pub const COLUMNS: [&str; 44] = [
+    "timestamp",
+    "month",
+    "day",
+    "time",
+    "hostname",
+    "uptime",
+    "policy",
+    "in",
+    "out",
+    "mac",
+    "src",
+    "dst",
+    "len",
+    "tos",
+    "prec",
+    "ttl",
+    "id",
+    "df",
+    "proto",
+    "spt",
+    "dpt",
+    "window",
+    "res",
+    "cwr",
+    "ece",
+    "urg",
+    "ack",
+    "psh",
+    "rst",
+    "syn",
+    "fin",
+    "urgp",
+    "tc",
+    "hoplimit",
+    "flowlbl",
+    "type",
+    "code",
+    "seq",
+    "mtu",
+    "mark",
+    "physin",
+    "phyout",
+    "origin",
+    "origin_hash",
+];
+
+/// Exporter for SQLite database
+///
+/// Default table name is `ufwlog`, 10,000 rows per transaction, and the existing table is
+/// replaced.
+#[derive(Debug, Clone)]
+pub struct Exporter {
+    table: String,
+    batch_size: usize,
+    append: bool,
+    year: Option<i32>,
+}
+
+/// Result of an export
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Summary {
+    /// How many records are read.
+    pub read: usize,
+    /// How many records are inserted, the others are already in the table.
+    pub inserted: usize,
+}
+
+impl Default for Exporter {
+    fn default() -> Self {
+        Self {
+            table: "ufwlog".to_string(),
+            batch_size: 10_000,
+            append: false,
+            year: None,
+        }
+    }
+}
+
+impl Exporter {
+    /// New an exporter with default config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the table name.
+    pub fn table(mut self, table: &str) -> Self {
+        self.table = table.to_string();
+        self
+    }
+
+    /// Set how many rows are inserted in a transaction.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size is zero.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "Batch size should be positive");
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Keep the existing table and insert new records only, instead of replacing the table.
+    pub fn append(mut self, append: bool) -> Self {
+        self.append = append;
+        self
+    }
+
+    /// Set the year of logs, which is used to make `timestamp` column. See
+    /// [`UfwLog::timestamp`] for the default.
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+
+    /// Get the statements to create table and indexes if they don't exist.
+    pub fn create_table_sql(&self) -> String {
+        let table = quote(&self.table);
+        let index = |name: &str| quote(&format!("{}_{name}", self.table));
+        format!(
+            "CREATE TABLE IF NOT EXISTS {table} (
+    rowid INTEGER PRIMARY KEY,
+    timestamp INTEGER,
+    month INTEGER NOT NULL,
+    day INTEGER NOT NULL,
+    time TEXT NOT NULL,
+    hostname TEXT NOT NULL,
+    uptime REAL,
+    policy TEXT NOT NULL,
+    \"in\" TEXT NOT NULL,
+    out TEXT NOT NULL,
+    mac TEXT NOT NULL,
+    src TEXT NOT NULL,
+    dst TEXT NOT NULL,
+    len INTEGER NOT NULL,
+    tos TEXT,
+    prec TEXT,
+    ttl INTEGER,
+    id INTEGER,
+    df INTEGER NOT NULL,
+    proto TEXT NOT NULL,
+    spt INTEGER,
+    dpt INTEGER,
+    window INTEGER,
+    res TEXT NOT NULL,
+    cwr INTEGER NOT NULL,
+    ece INTEGER NOT NULL,
+    urg INTEGER NOT NULL,
+    ack INTEGER NOT NULL,
+    psh INTEGER NOT NULL,
+    rst INTEGER NOT NULL,
+    syn INTEGER NOT NULL,
+    fin INTEGER NOT NULL,
+    urgp INTEGER,
+    tc INTEGER,
+    hoplimit INTEGER,
+    flowlbl INTEGER,
+    type INTEGER,
+    code INTEGER,
+    seq INTEGER,
+    mtu INTEGER,
+    mark INTEGER,
+    physin TEXT,
+    phyout TEXT,
+    origin TEXT NOT NULL,
+    origin_hash INTEGER NOT NULL
+);
+CREATE UNIQUE INDEX IF NOT EXISTS {} ON {table} (hostname, origin_hash);
+CREATE INDEX IF NOT EXISTS {} ON {table} (timestamp);
+CREATE INDEX IF NOT EXISTS {} ON {table} (src);
+CREATE INDEX IF NOT EXISTS {} ON {table} (dst);
+CREATE INDEX IF NOT EXISTS {} ON {table} (dpt);
+CREATE INDEX IF NOT EXISTS {} ON {table} (policy);
+",
+            index("record"),
+            index("timestamp"),
+            index("src"),
+            index("dst"),
+            index("dpt"),
+            index("policy"),
+        )
+    }
+
+    /// Export logs into database.
+    pub fn export(&self, logs: &[UfwLog], conn: &mut Connection) -> Result<Summary, Error> {
+        let mut logs = logs.iter().map(Ok);
+        self.export_records(&mut logs, conn)
+    }
+
+    /// Export logs from an iterator, only one transaction of logs is kept in memory.
+    pub fn export_iter(
+        &self,
+        logs: &mut dyn Iterator<Item = Result<UfwLog, Error>>,
+        conn: &mut Connection,
+    ) -> Result<Summary, Error> {
+        self.export_records(logs, conn)
+    }
+
+    fn export_records<L: Borrow<UfwLog>>(
+        &self,
+        logs: &mut dyn Iterator<Item = Result<L, Error>>,
+        conn: &mut Connection,
+    ) -> Result<Summary, Error> {
+        let tx = conn.transaction()?;
+        if !self.append {
+            tx.execute_batch(&format!("DROP TABLE IF EXISTS {};", quote(&self.table)))?;
+        }
+        tx.execute_batch(&self.create_table_sql())?;
+        tx.commit()?;
+
+        let sql = self.insert_sql();
+        let mut summary = Summary::default();
+        let mut tx = conn.transaction()?;
+        for log in logs {
+            summary.inserted += self.insert(&tx, &sql, log?.borrow())?;
+            summary.read += 1;
+            if summary.read % self.batch_size == 0 {
+                tx.commit()?;
+                tx = conn.transaction()?;
+            }
+        }
+        tx.commit()?;
+        Ok(summary)
+    }
+
+    /// Get the statement to insert a record, which skips the record already in table.
+    fn insert_sql(&self) -> String {
+        format!(
+            "INSERT OR IGNORE INTO {} ({}) VALUES ({})",
+            quote(&self.table),
+            COLUMNS.map(quote).join(", "),
+            (1..=COLUMNS.len())
+                .map(|i| format!("?{i}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    }
+
+    /// Insert a record with the statement of [`insert_sql`](Exporter::insert_sql), return how
+    /// many rows are inserted (0 or 1).
+    fn insert(&self, tx: &Transaction, sql: &str, log: &UfwLog) -> Result<usize, Error> {
+        let mut statement = tx.prepare_cached(sql)?;
+        Ok(statement.execute(params![
+            log.timestamp(self.year),
+            log.month,
+            log.day,
+            log.time,
+            log.hostname,
+            log.uptime.trim().parse::<f64>().ok(),
+            log.policy.to_string(),
+            log.r#in,
+            log.out,
+            log.mac,
+            log.src,
+            log.dst,
+            log.len,
+            log.tos,
+            log.prec,
+            log.ttl,
+            log.id,
+            log.df,
+            log.proto,
+            log.spt,
+            log.dpt,
+            log.window,
+            log.res,
+            log.cwr,
+            log.ece,
+            log.urg,
+            log.ack,
+            log.psh,
+            log.rst,
+            log.syn,
+            log.fin,
+            log.urgp,
+            log.tc,
+            log.hoplimit,
+            log.flowlbl,
+            log.r#type,
+            log.code,
+            log.seq,
+            log.mtu,
+            log.mark,
+            log.physin,
+            log.phyout,
+            log.get_origin(),
+            fnv1a(log.get_origin().as_bytes()) as i64,
+        ])?)
+    }
+}
+
+/// Quote an identifier of SQL.
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(value: rusqlite::Error) -> Self {
+        Self::Export(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const LOGS: [&str; 2] = [
+        "Jan 16 02:13:52 hostname kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:09:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=42313 WINDOW=1024 RES=0x00 SYN URGP=0",
+        "Jan 16 02:13:53 hostname kernel: [ 1231.000001] [UFW ALLOW] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:09:00 SRC=192.168.0.9 DST=127.0.111.111 LEN=76 TOS=0x00 PREC=0x00 TTL=64 ID=1 PROTO=UDP SPT=123 DPT=123 LEN=56",
+    ];
+
+    fn logs() -> Vec<UfwLog> {
+        LOGS.iter().map(|l| UfwLog::from_str(l).unwrap()).collect()
+    }
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_export() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let summary = Exporter::new()
+            .year(2024)
+            .batch_size(1)
+            .export(&logs(), &mut conn)
+            .unwrap();
+        assert_eq!(
+            summary,
+            Summary {
+                read: 2,
+                inserted: 2
+            }
+        );
+        let (timestamp, dpt, syn, policy): (i64, u16, bool, String) = conn
+            .query_row(
+                "SELECT timestamp, dpt, syn, policy FROM ufwlog WHERE src = '192.168.0.8'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            (timestamp, dpt, syn, policy.as_str()),
+            (1705371232, 42313, true, "BLOCK")
+        );
+        assert_eq!(
+            count(&conn, "SELECT count(*) FROM ufwlog WHERE window IS NULL"),
+            1
+        );
+        // indexes
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT count(*) FROM sqlite_master WHERE type = 'index' AND tbl_name = 'ufwlog'"
+            ),
+            6
+        );
+    }
+
+    #[test]
+    fn test_append_skip_existing() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let logs = logs();
+        Exporter::new().export(&logs[..1], &mut conn).unwrap();
+
+        let summary = Exporter::new()
+            .append(true)
+            .export(&logs, &mut conn)
+            .unwrap();
+        assert_eq!(
+            summary,
+            Summary {
+                read: 2,
+                inserted: 1
+            }
+        );
+        assert_eq!(count(&conn, "SELECT count(*) FROM ufwlog"), 2);
+
+        // the year doesn't change identity of record
+        let summary = Exporter::new()
+            .append(true)
+            .year(2023)
+            .export(&logs, &mut conn)
+            .unwrap();
+        assert_eq!(summary.inserted, 0);
+
+        // replace table without append
+        let mut logs = logs.into_iter().skip(1).map(Ok);
+        Exporter::new().export_iter(&mut logs, &mut conn).unwrap();
+        assert_eq!(count(&conn, "SELECT count(*) FROM ufwlog"), 1);
+    }
+}
@@ -0,0 +1,389 @@
+//! Template Exporter module
+//!
+//! Export each log as a line rendered from a user-defined [`Template`], for ad-hoc output which
+//! other formats don't cover, e.g. feeding a shell script or a chat message.
+//!
+//! ## Syntax
+//!
+//! - `{field}` is replaced by the value of field, a [field name](Field::name) of [`UfwLog`] or
+//!   its csv header (case-insensitive), e.g. `{src}`, `{DPT}`. Absent value is empty, see
+//!   [`Field::value`] for how each field is rendered.
+//! - `{field:spec}` pads the value to a width, `spec` is `[[fill]align]width` like Rust
+//!   formatting, where `align` is `<` (left, default), `>` (right) or `^` (center), e.g.
+//!   `{src:<15}`, `{dpt:0>5}`.
+//! - `{field|default}` writes `default` if the value is absent, e.g. `{dpt|-}`. It can be
+//!   combined with padding, e.g. `{dpt:>5|-}`.
+//! - `{?field}...{/field}` writes the section only if the value is present (a TCP flag is present
+//!   if it is set), and `{!field}...{/field}` only if it is absent. Sections can be nested.
+//! - `{{` and `}}` are literal braces, `\n`, `\t` and `\\` are line feed, tab and backslash.
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use std::str::FromStr;
+//! use ufwlog::export::template::{Exporter, Template};
+//! use ufwlog::export::Export;
+//! use ufwlog::UfwLog;
+//!
+//! let log = UfwLog::from_str("Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0")?;
+//! let template = Template::from_str("{policy:<6} {src:>15} -> {dst}:{dpt|?} via {in}{?out}/{out}{/out}")?;
+//! let exporter = Exporter::new().template(template);
+//!
+//! assert_eq!(
+//!     exporter.convert(&log)?,
+//!     "BLOCK      192.168.0.8 -> 127.0.111.111:22 via eth0"
+//! );
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use super::column::Field;
+use super::Export;
+use crate::error::Error;
+use crate::ufw_log::UfwLog;
+use std::str::FromStr;
+
+/// Template of [`Exporter::default`], which shows protocol, policy and addresses with ports.
+pub const DEFAULT_TEMPLATE: &str =
+    "{month:0>2}-{day:0>2} {time} {policy} {proto} {src}{?spt}:{spt}{/spt} -> {dst}{?dpt}:{dpt}{/dpt}";
+
+/// Exporter for user-defined template, one rendered log per line
+#[derive(Debug, Clone)]
+pub struct Exporter {
+    template: Template,
+}
+
+impl Default for Exporter {
+    /// Render logs with [`DEFAULT_TEMPLATE`].
+    fn default() -> Self {
+        Self {
+            template: Template::from_str(DEFAULT_TEMPLATE).expect("default template is valid"),
+        }
+    }
+}
+
+impl Export for Exporter {
+    fn get_extension(&self) -> &'static str {
+        "txt"
+    }
+
+    fn convert(&self, log: &UfwLog) -> Result<String, Error> {
+        Ok(self.template.render(log))
+    }
+}
+
+impl Exporter {
+    /// New an exporter with [`DEFAULT_TEMPLATE`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the template of each line.
+    pub fn template(mut self, template: Template) -> Self {
+        self.template = template;
+        self
+    }
+}
+
+/// A parsed template, see [module](self) for the syntax.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Field {
+        field: Field,
+        spec: Spec,
+        default: Option<String>,
+    },
+    /// Conditional section, which is written if the presence of field is not `inverted`.
+    Section {
+        field: Field,
+        inverted: bool,
+        nodes: Vec<Node>,
+    },
+}
+
+/// Opening tag of section, the field and whether it is inverted.
+type Open = (Field, bool);
+
+/// Padding of a value.
+#[derive(Debug, Clone, PartialEq)]
+struct Spec {
+    fill: char,
+    align: Align,
+    width: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+impl Template {
+    /// Render a log with this template.
+    pub fn render(&self, log: &UfwLog) -> String {
+        let mut output = String::new();
+        render(&self.nodes, log, &mut output);
+        output
+    }
+}
+
+fn render(nodes: &[Node], log: &UfwLog, output: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Field {
+                field,
+                spec,
+                default,
+            } => {
+                let value = field.value(log);
+                let value = match default {
+                    Some(default) if value.is_empty() => default.clone(),
+                    _ => value,
+                };
+                spec.pad(&value, output);
+            }
+            Node::Section {
+                field,
+                inverted,
+                nodes,
+            } => {
+                if is_present(*field, log) != *inverted {
+                    render(nodes, log, output);
+                }
+            }
+        }
+    }
+}
+
+/// Whether the field has value, a single TCP flag is present if it is set.
+fn is_present(field: Field, log: &UfwLog) -> bool {
+    match field.value(log).as_str() {
+        "" => false,
+        "false" => !matches!(
+            field,
+            Field::Syn
+                | Field::Ack
+                | Field::Fin
+                | Field::Rst
+                | Field::Psh
+                | Field::Cwr
+                | Field::Ece
+                | Field::Urg
+        ),
+        _ => true,
+    }
+}
+
+impl Spec {
+    fn pad(&self, value: &str, output: &mut String) {
+        let padding = self.width.saturating_sub(value.chars().count());
+        let (before, after) = match self.align {
+            Align::Left => (0, padding),
+            Align::Right => (padding, 0),
+            Align::Center => (padding / 2, padding - padding / 2),
+        };
+        output.extend(std::iter::repeat_n(self.fill, before));
+        output.push_str(value);
+        output.extend(std::iter::repeat_n(self.fill, after));
+    }
+}
+
+impl FromStr for Spec {
+    type Err = String;
+
+    /// Parse `[[fill]align]width`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let align = |c| match c {
+            '<' => Some(Align::Left),
+            '>' => Some(Align::Right),
+            '^' => Some(Align::Center),
+            _ => None,
+        };
+        let mut chars = s.chars();
+        let (fill, align, width) = match (chars.next(), chars.next()) {
+            (Some(fill), Some(c)) if align(c).is_some() => (fill, align(c), chars.as_str()),
+            (Some(c), _) if align(c).is_some() => (' ', align(c), &s[c.len_utf8()..]),
+            _ => (' ', Some(Align::Left), s),
+        };
+        match (align, width.parse::<usize>()) {
+            (Some(align), Ok(width)) => Ok(Self { fill, align, width }),
+            _ => Err(format!("Invalid format spec: {s}")),
+        }
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    /// Parse a template, return error if a field is unknown or a brace or section is unclosed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // the innermost section is at the end, the bottom is the template itself
+        let mut stack: Vec<(Option<Open>, Vec<Node>)> = vec![(None, vec![])];
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match (c, chars.peek()) {
+                ('{', Some('{')) | ('}', Some('}')) => {
+                    chars.next();
+                    text.push(c);
+                }
+                ('\\', Some(&escaped @ ('n' | 't' | '\\'))) => {
+                    chars.next();
+                    text.push(match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        _ => '\\',
+                    });
+                }
+                ('}', _) => return Err("Unmatched `}`, use `}}` for a literal brace".to_string()),
+                ('{', _) => {
+                    let mut tag = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => tag.push(c),
+                            None => return Err(format!("Unclosed `{{{tag}`")),
+                        }
+                    }
+                    let nodes = &mut stack.last_mut().unwrap().1;
+                    if !text.is_empty() {
+                        nodes.push(Node::Text(std::mem::take(&mut text)));
+                    }
+
+                    if let Some(name) = tag.strip_prefix('/') {
+                        let field = Field::from_str(name)?;
+                        match stack.pop() {
+                            Some((Some((open, inverted)), nodes)) if open == field => {
+                                stack.last_mut().unwrap().1.push(Node::Section {
+                                    field,
+                                    inverted,
+                                    nodes,
+                                });
+                            }
+                            _ => return Err(format!("Unexpected `{{{tag}}}`")),
+                        }
+                    } else if let Some(name) = tag.strip_prefix('?') {
+                        stack.push((Some((Field::from_str(name)?, false)), vec![]));
+                    } else if let Some(name) = tag.strip_prefix('!') {
+                        stack.push((Some((Field::from_str(name)?, true)), vec![]));
+                    } else {
+                        let (tag, default) = match tag.split_once('|') {
+                            Some((tag, default)) => (tag, Some(default.to_string())),
+                            None => (tag.as_str(), None),
+                        };
+                        let (name, spec) = match tag.split_once(':') {
+                            Some((name, spec)) => (name, spec.parse()?),
+                            None => (tag, Spec::default()),
+                        };
+                        nodes.push(Node::Field {
+                            field: Field::from_str(name)?,
+                            spec,
+                            default,
+                        });
+                    }
+                }
+                _ => text.push(c),
+            }
+        }
+
+        if !text.is_empty() {
+            stack.last_mut().unwrap().1.push(Node::Text(text));
+        }
+        match stack.pop() {
+            Some((None, nodes)) => Ok(Self { nodes }),
+            Some((Some((field, inverted)), _)) => Err(format!(
+                "Section `{{{}{}}}` is not closed",
+                if inverted { '!' } else { '?' },
+                field.name()
+            )),
+            None => unreachable!("the template itself is at the bottom"),
+        }
+    }
+}
+
+impl Default for Spec {
+    fn default() -> Self {
+        Self {
+            fill: ' ',
+            align: Align::Left,
+            width: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    fn render(template: &str) -> String {
+        let log = UfwLog::from_str(LOG).unwrap();
+        Template::from_str(template).unwrap().render(&log)
+    }
+
+    #[test]
+    fn test_field() {
+        assert_eq!(
+            render("{src} {DPT} {Control Bits / flags}"),
+            "192.168.0.8 22 SYN"
+        );
+        assert_eq!(render("{df}/{syn}/{ack}/{out}/{tc}"), "DF/true/false//");
+        assert_eq!(render("{{{hostname}}} \\t\\\\n\\x"), "{myhost} \t\\n\\x");
+    }
+
+    #[test]
+    fn test_padding_and_default() {
+        assert_eq!(
+            render("[{in:<6}][{dpt:>4}][{proto:*^7}]"),
+            "[eth0  ][  22][**TCP**]"
+        );
+        assert_eq!(render("{day:0>3}|{src:3}"), "016|192.168.0.8");
+        assert_eq!(render("{out|-}|{tc:>3|-}|{dpt|-}"), "-|  -|22");
+        assert_eq!(render("{mtu|n/a: none}"), "n/a: none");
+    }
+
+    #[test]
+    fn test_section() {
+        assert_eq!(render("{?dpt}:{dpt}{/dpt}{?tc}tc={tc}{/tc}"), ":22");
+        assert_eq!(render("{!out}no out{/out}{!in}no in{/in}"), "no out");
+        assert_eq!(render("{?syn}S{/syn}{?ack}A{/ack}{!fin}!F{/fin}"), "S!F");
+        assert_eq!(
+            render("{?src}{src}{?out} -> {out}{/out}{/src}"),
+            "192.168.0.8"
+        );
+    }
+
+    #[test]
+    fn test_default_template() {
+        let log = UfwLog::from_str(LOG).unwrap();
+        assert_eq!(
+            Exporter::new().convert(&log).unwrap(),
+            "01-16 02:13:52 BLOCK TCP 192.168.0.8:46468 -> 127.0.111.111:22"
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        for template in [
+            "{unknown}",
+            "{src",
+            "src}",
+            "{?src}never closed",
+            "{?src}{/dst}",
+            "{/src}",
+            "{dpt:>x}",
+            "{dpt:}",
+        ] {
+            assert!(Template::from_str(template).is_err(), "{template}");
+        }
+    }
+}
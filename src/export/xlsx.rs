@@ -0,0 +1,396 @@
+//! Xlsx Exporter module
+//!
+//! Export logs as an Excel workbook. Each field of [`UfwLog`] is a column with typed cells:
+//! numbers (e.g. `dpt`, `len`) are numeric, flags (e.g. `syn`) are boolean and absent or empty
+//! values are blank. The leading `timestamp` column is an Excel date time in UTC. The header row
+//! is frozen and has an autofilter.
+//!
+//! A worksheet has at most 1,048,576 rows, so the events continue on `Events 2`, `Events 3`, ...
+//! if there are more logs. With [`Exporter::summary`], a `Summary` sheet is added, which has the
+//! count of each policy and a pivot table of destination ports by policy.
+//!
+//! Xlsx is a binary format, so [`Export::convert`] is not supported, and the whole workbook is
+//! kept in memory until it's written.
+//!
+//! This module requires `xlsx` feature.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use std::io::BufReader;
+//! use ufwlog::UfwLog;
+//! use ufwlog::export::Export;
+//! use ufwlog::export::xlsx::Exporter as XlsxExporter;
+//!
+//! let reader = BufReader::new(std::fs::File::open("./ufw.log")?);
+//! let mut logs = UfwLog::from_buf_reader(reader);
+//! let mut file = std::fs::File::create("ufw.xlsx")?;
+//! XlsxExporter::new().summary(true).export_iter(&mut logs, &mut file)?;
+//!
+//! # std::fs::remove_file("ufw.xlsx").unwrap();
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::json::{to_value, Value};
+use super::Export;
+use crate::error::Error;
+use crate::stats::Stats;
+use crate::ufw_log::UfwLog;
+use rust_xlsxwriter::{ExcelDateTime, Format, Workbook, Worksheet, XlsxError};
+use std::borrow::Borrow;
+use std::io::Write;
+
+/// Column names of events sheet, which are `timestamp` and the field names of [`UfwLog`].
+pub const HEADER: [&str; 43] = [
+    "timestamp",
+    "month",
+    "day",
+    "time",
+    "hostname",
+    "uptime",
+    "policy",
+    "in",
+    "out",
+    "mac",
+    "src",
+    "dst",
+    "len",
+    "tos",
+    "prec",
+    "ttl",
+    "id",
+    "df",
+    "proto",
+    "spt",
+    "dpt",
+    "window",
+    "res",
+    "cwr",
+    "ece",
+    "urg",
+    "ack",
+    "psh",
+    "rst",
+    "syn",
+    "fin",
+    "urgp",
+    "tc",
+    "hoplimit",
+    "flowlbl",
+    "type",
+    "code",
+    "seq",
+    "mtu",
+    "mark",
+    "physin",
+    "phyout",
+    "origin",
+];
+
+/// Maximum rows of a worksheet, including header
+const MAX_ROWS: u32 = 1_048_576;
+
+/// Maximum characters of a cell, longer text is truncated.
+const MAX_CHARS: usize = 32_767;
+
+/// Exporter for xlsx format
+#[derive(Debug, Clone, Default)]
+pub struct Exporter {
+    summary: bool,
+    year: Option<i32>,
+}
+
+impl Export for Exporter {
+    fn get_extension(&self) -> &'static str {
+        "xlsx"
+    }
+
+    fn convert(&self, _log: &UfwLog) -> Result<String, Error> {
+        Err(Error::Unsupported(
+            "xlsx is a binary format, a single log can't be converted to string".to_string(),
+        ))
+    }
+
+    fn export(&self, logs: &[UfwLog], writer: &mut dyn Write) -> Result<(), Error> {
+        self.write_workbook(logs.iter().map(Ok), writer)?;
+        Ok(())
+    }
+
+    fn export_iter(
+        &self,
+        logs: &mut dyn Iterator<Item = Result<UfwLog, Error>>,
+        writer: &mut dyn Write,
+    ) -> Result<usize, Error> {
+        self.write_workbook(logs, writer)
+    }
+}
+
+impl Exporter {
+    /// New an exporter with default config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a `Summary` sheet of counts by policy and destination port.
+    pub fn summary(mut self, summary: bool) -> Self {
+        self.summary = summary;
+        self
+    }
+
+    /// Set the year of logs, which is used to make `timestamp` column. See
+    /// [`UfwLog::timestamp`] for the default.
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+
+    /// Write logs into a workbook, then write the workbook, return how many logs are written.
+    fn write_workbook<L: Borrow<UfwLog>>(
+        &self,
+        logs: impl Iterator<Item = Result<L, Error>>,
+        writer: &mut dyn Write,
+    ) -> Result<usize, Error> {
+        let header = Format::new().set_bold();
+        let datetime = Format::new().set_num_format("yyyy-mm-dd hh:mm:ss");
+        let mut workbook = Workbook::new();
+        let mut stats = match self.year {
+            Some(year) => Stats::new().year(year),
+            None => Stats::new(),
+        };
+
+        let mut sheet = events_sheet(1, &header)?;
+        let mut row = 0;
+        let mut count = 0;
+        for log in logs {
+            let log = log?;
+            let log = log.borrow();
+            if row + 1 == MAX_ROWS {
+                finish_events(&mut sheet, row)?;
+                workbook.push_worksheet(sheet);
+                sheet = events_sheet(workbook.worksheets().len() + 1, &header)?;
+                row = 0;
+            }
+            row += 1;
+            self.write_row(&mut sheet, row, log, &datetime)?;
+            if self.summary {
+                stats.add(log);
+            }
+            count += 1;
+        }
+        finish_events(&mut sheet, row)?;
+        workbook.push_worksheet(sheet);
+
+        if self.summary {
+            workbook.push_worksheet(summary_sheet(&stats, &header)?);
+        }
+        writer.write_all(&workbook.save_to_buffer()?)?;
+        writer.flush()?;
+        Ok(count)
+    }
+
+    fn write_row(
+        &self,
+        sheet: &mut Worksheet,
+        row: u32,
+        log: &UfwLog,
+        datetime: &Format,
+    ) -> Result<(), Error> {
+        if let Some(timestamp) = log.timestamp(self.year) {
+            sheet.write_datetime_with_format(
+                row,
+                0,
+                ExcelDateTime::from_timestamp(timestamp)?,
+                datetime,
+            )?;
+        }
+        let fields = match to_value(log) {
+            Value::Object(fields) => fields,
+            _ => unreachable!("log is converted into an object"),
+        };
+        for (col, (_, value)) in (1..).zip(fields) {
+            match value {
+                Value::Integer(n) => sheet.write_number(row, col, n as f64)?,
+                Value::Float(n) => sheet.write_number(row, col, n)?,
+                Value::Bool(b) => sheet.write_boolean(row, col, b)?,
+                Value::String(s) if !s.is_empty() => sheet.write_string(row, col, truncate(&s))?,
+                _ => continue,
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Make an events sheet with header row.
+fn events_sheet(index: usize, header: &Format) -> Result<Worksheet, Error> {
+    let mut sheet = Worksheet::new();
+    match index {
+        1 => sheet.set_name("Events")?,
+        n => sheet.set_name(format!("Events {n}"))?,
+    };
+    for (col, name) in (0..).zip(HEADER) {
+        sheet.write_string_with_format(0, col, name, header)?;
+    }
+    sheet.set_column_width(0, 20)?;
+    sheet.set_freeze_panes(1, 0)?;
+    Ok(sheet)
+}
+
+/// Add autofilter to the rows of events sheet.
+fn finish_events(sheet: &mut Worksheet, last_row: u32) -> Result<(), Error> {
+    sheet.autofilter(0, 0, last_row, HEADER.len() as u16 - 1)?;
+    Ok(())
+}
+
+/// Make a sheet of policy counts, and destination ports by policy.
+fn summary_sheet(stats: &Stats, header: &Format) -> Result<Worksheet, Error> {
+    let mut sheet = Worksheet::new();
+    sheet.set_name("Summary")?;
+    sheet.set_column_width(0, 16)?;
+
+    sheet.write_string_with_format(0, 0, "Policy", header)?;
+    sheet.write_string_with_format(0, 1, "Events", header)?;
+    let policies = stats.policies();
+    let mut row = 0;
+    for (policy, count) in &policies {
+        row += 1;
+        sheet.write_string(row, 0, policy.to_string())?;
+        sheet.write_number(row, 1, *count as f64)?;
+    }
+    row += 1;
+    sheet.write_string_with_format(row, 0, "Total", header)?;
+    sheet.write_number_with_format(row, 1, stats.total() as f64, header)?;
+
+    row += 2;
+    let total_col = policies.len() as u16 + 2;
+    sheet.write_string_with_format(row, 0, "Port", header)?;
+    sheet.write_string_with_format(row, 1, "Proto", header)?;
+    for (col, (policy, _)) in (2..).zip(&policies) {
+        sheet.write_string_with_format(row, col, policy.to_string(), header)?;
+    }
+    sheet.write_string_with_format(row, total_col, "Total", header)?;
+    for (port, total) in stats.top_ports(usize::MAX) {
+        row += 1;
+        sheet.write_number(row, 0, port.port)?;
+        sheet.write_string(row, 1, &port.proto)?;
+        for (col, (policy, _)) in (2..).zip(&policies) {
+            sheet.write_number(row, col, stats.port_policy(port, *policy) as f64)?;
+        }
+        sheet.write_number(row, total_col, total as f64)?;
+    }
+    Ok(sheet)
+}
+
+/// Truncate text to the maximum characters of a cell.
+fn truncate(text: &str) -> &str {
+    match text.char_indices().nth(MAX_CHARS) {
+        Some((index, _)) => &text[..index],
+        None => text,
+    }
+}
+
+impl From<XlsxError> for Error {
+    fn from(value: XlsxError) -> Self {
+        Self::Export(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read};
+    use std::str::FromStr;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    /// Read a file of the xlsx zip archive.
+    fn read(output: &[u8], name: &str) -> String {
+        let mut archive = zip::ZipArchive::new(Cursor::new(output)).unwrap();
+        let mut content = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    }
+
+    #[test]
+    fn test_header() {
+        let log = UfwLog::from_str(LOG).unwrap();
+        let keys = match to_value(&log) {
+            Value::Object(fields) => fields.into_iter().map(|(key, _)| key).collect::<Vec<_>>(),
+            _ => unreachable!(),
+        };
+        assert_eq!(HEADER[1..], keys);
+    }
+
+    #[test]
+    fn test_export() {
+        let logs = vec![
+            UfwLog::from_str(LOG).unwrap(),
+            UfwLog::from_str(LOG).unwrap(),
+        ];
+        let mut output = vec![];
+        Exporter::new()
+            .year(2024)
+            .export(&logs, &mut output)
+            .unwrap();
+        assert!(output.starts_with(b"PK"));
+
+        let workbook = read(&output, "xl/workbook.xml");
+        assert!(workbook.contains(r#"<sheet name="Events" sheetId="1""#));
+        assert!(!workbook.contains("Summary"));
+
+        let sheet = read(&output, "xl/worksheets/sheet1.xml");
+        assert!(sheet.contains(
+            r#"<pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/>"#
+        ));
+        assert!(sheet.contains(r#"<autoFilter ref="A1:AQ3"/>"#));
+        // timestamp of 2024-01-16T02:13:52Z
+        assert!(sheet.contains(r#"<c r="A2" s="2"><v>45307.09296296296</v></c>"#));
+        // dpt is a number, df is a boolean
+        assert!(sheet.contains(r#"<c r="U2"><v>22</v></c>"#));
+        assert!(sheet.contains(r#"<c r="R2" t="b"><v>1</v></c>"#));
+        // hostname is a shared string
+        assert!(sheet.contains(r#"<c r="E2" t="s">"#));
+        // tc is absent and out is empty, so they are blank
+        assert!(!sheet.contains(r#"<c r="AG2""#));
+        assert!(!sheet.contains(r#"<c r="I2""#));
+    }
+
+    #[test]
+    fn test_summary() {
+        let allow = LOG.replace("BLOCK", "ALLOW");
+        let mut logs = [LOG, LOG, &allow]
+            .into_iter()
+            .map(UfwLog::from_str)
+            .collect::<Vec<_>>()
+            .into_iter();
+        let mut output = vec![];
+        let count = Exporter::new()
+            .summary(true)
+            .export_iter(&mut logs, &mut output)
+            .unwrap();
+        assert_eq!(count, 3);
+
+        let workbook = read(&output, "xl/workbook.xml");
+        assert!(workbook.contains(r#"<sheet name="Summary" sheetId="2""#));
+        let sheet = read(&output, "xl/worksheets/sheet2.xml");
+        // policy counts
+        assert!(sheet.contains(r#"<c r="B2"><v>2</v></c>"#));
+        assert!(sheet.contains(r#"<c r="B3"><v>1</v></c>"#));
+        assert!(sheet.contains(r#"<c r="B4" s="1"><v>3</v></c>"#));
+        // port 22 by policy and total
+        assert!(sheet.contains(r#"<c r="A7"><v>22</v></c>"#));
+        assert!(
+            sheet.contains(r#"<c r="C7"><v>2</v></c><c r="D7"><v>1</v></c><c r="E7"><v>3</v></c>"#)
+        );
+    }
+
+    #[test]
+    fn test_truncate() {
+        let long = "é".repeat(MAX_CHARS + 1);
+        assert_eq!(truncate(&long).chars().count(), MAX_CHARS);
+        assert_eq!(truncate("short"), "short");
+    }
+}
@@ -0,0 +1,220 @@
+//! Zeek Exporter module
+//!
+//! Export logs as TSV in the format of [Zeek](https://docs.zeek.org/en/master/logs/conn.html)
+//! `conn.log`, with the `#fields` and `#types` headers, so it can be processed by `zeek-cut` and
+//! other Zeek tooling. Columns are separated by tab:
+//!
+//! ```text
+//! #separator \x09
+//! #set_separator  ,
+//! #empty_field  (empty)
+//! #unset_field  -
+//! #path  conn
+//! #open  2024-01-16-02-20-00
+//! #fields  ts  id.orig_h  id.orig_p  id.resp_h  id.resp_p  proto  ufw_policy  ufw_in  ufw_out  ufw_hostname
+//! #types  time  addr  port  addr  port  enum  string  string  string  string
+//! 1705371232.000000  192.168.0.8  46468  127.0.111.111  22  tcp  block  eth0  (empty)  myhost
+//! #close  2024-01-16-02-20-00
+//! ```
+//!
+//! As Zeek does, `proto` is `tcp`, `udp`, `icmp` (both ICMP and ICMPv6) or `unknown_transport`,
+//! and the ports of ICMP are the type and code. `ufw_policy` is lowercase and joined by hyphen,
+//! e.g. `audit-invalid`. Absent values are `-`, and empty interfaces are `(empty)`.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use ufwlog::UfwLog;
+//! use ufwlog::export::Export;
+//! use ufwlog::export::zeek::Exporter as ZeekExporter;
+//!
+//! let logs = UfwLog::from_file("./ufw.log")?;
+//! let mut file = std::fs::File::create("conn.log")?;
+//! ZeekExporter::new().year(2024).export(&logs, &mut file)?;
+//!
+//! # std::fs::remove_file("conn.log").unwrap();
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::Export;
+use crate::error::Error;
+use crate::ufw_log::UfwLog;
+use std::io::Write;
+
+/// Field names and types of columns
+pub const FIELDS: [(&str, &str); 10] = [
+    ("ts", "time"),
+    ("id.orig_h", "addr"),
+    ("id.orig_p", "port"),
+    ("id.resp_h", "addr"),
+    ("id.resp_p", "port"),
+    ("proto", "enum"),
+    ("ufw_policy", "string"),
+    ("ufw_in", "string"),
+    ("ufw_out", "string"),
+    ("ufw_hostname", "string"),
+];
+
+const UNSET: &str = "-";
+const EMPTY: &str = "(empty)";
+
+/// Exporter for Zeek conn.log TSV format
+#[derive(Debug, Clone, Default)]
+pub struct Exporter {
+    year: Option<i32>,
+}
+
+impl Export for Exporter {
+    fn get_extension(&self) -> &'static str {
+        "log"
+    }
+
+    fn convert(&self, log: &UfwLog) -> Result<String, Error> {
+        let proto = match log.proto.to_lowercase().as_str() {
+            "tcp" => "tcp",
+            "udp" => "udp",
+            "icmp" | "icmpv6" => "icmp",
+            _ => "unknown_transport",
+        };
+        let (orig_p, resp_p) = match proto {
+            "icmp" => (log.r#type.map(u16::from), log.code.map(u16::from)),
+            _ => (log.spt, log.dpt),
+        };
+        let port = |port: Option<u16>| port.map_or(UNSET.to_string(), |p| p.to_string());
+        let string = |value: &str| match value {
+            "" => EMPTY.to_string(),
+            value => escape(value),
+        };
+        let address = |value: &str| match value {
+            "" => UNSET.to_string(),
+            value => escape(value),
+        };
+
+        Ok([
+            log.timestamp(self.year)
+                .map_or(UNSET.to_string(), |t| format!("{t}.000000")),
+            address(&log.src),
+            port(orig_p),
+            address(&log.dst),
+            port(resp_p),
+            proto.to_string(),
+            log.policy.to_string().to_lowercase().replace(' ', "-"),
+            string(&log.r#in),
+            string(&log.out),
+            string(&log.hostname),
+        ]
+        .join("\t"))
+    }
+
+    fn begin(&self, writer: &mut dyn Write) -> Result<(), Error> {
+        let (names, types): (Vec<_>, Vec<_>) = FIELDS.iter().copied().unzip();
+        writeln!(writer, "#separator \\x09")?;
+        writeln!(writer, "#set_separator\t,")?;
+        writeln!(writer, "#empty_field\t{EMPTY}")?;
+        writeln!(writer, "#unset_field\t{UNSET}")?;
+        writeln!(writer, "#path\tconn")?;
+        writeln!(writer, "#open\t{}", now())?;
+        writeln!(writer, "#fields\t{}", names.join("\t"))?;
+        writeln!(writer, "#types\t{}", types.join("\t"))?;
+        Ok(())
+    }
+
+    fn finish(&self, writer: &mut dyn Write) -> Result<(), Error> {
+        writeln!(writer, "#close\t{}", now())?;
+        Ok(writer.flush()?)
+    }
+}
+
+impl Exporter {
+    /// New an exporter with default config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the year of logs, which is used to make `ts`. See [`UfwLog::timestamp`] for the
+    /// default.
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+}
+
+/// Current time in the format of `#open` and `#close`, e.g. `2024-01-16-02-20-00`.
+fn now() -> String {
+    crate::time::format_rfc3339(crate::time::now())
+        .trim_end_matches('Z')
+        .replace(['T', ':'], "-")
+}
+
+/// Escape tab, backslash and control characters as `\xNN`, as Zeek does.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            c if c == '\\' || c.is_ascii_control() => escaped += &format!("\\x{:02x}", c as u32),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    #[test]
+    fn test_convert() {
+        let log = UfwLog::from_str(LOG).unwrap();
+        assert_eq!(
+            Exporter::new().year(2024).convert(&log).unwrap(),
+            "1705371232.000000\t192.168.0.8\t46468\t127.0.111.111\t22\ttcp\tblock\teth0\t(empty)\tmyhost"
+        );
+    }
+
+    #[test]
+    fn test_icmp() {
+        let log = UfwLog::from_str("Jan 16 02:13:52 103213020 kernel: [3601090.569259] [UFW AUDIT INVALID] IN= OUT=lo SRC=::1 DST=::1 LEN=104 TC=0 HOPLIMIT=64 FLOWLBL=0 PROTO=ICMPv6 TYPE=128 CODE=0 ID=10289 SEQ=1").unwrap();
+        let row = Exporter::new().year(2024).convert(&log).unwrap();
+        assert_eq!(
+            row,
+            "1705371232.000000\t::1\t128\t::1\t0\ticmp\taudit-invalid\t(empty)\tlo\t103213020"
+        );
+
+        let mut log = UfwLog::from_str(LOG).unwrap();
+        log.proto = "2".to_string();
+        log.spt = None;
+        log.dpt = None;
+        let row = Exporter::new().year(2024).convert(&log).unwrap();
+        assert!(row.contains("\t-\t127.0.111.111\t-\tunknown_transport\t"));
+    }
+
+    #[test]
+    fn test_export() {
+        let logs = vec![UfwLog::from_str(LOG).unwrap()];
+        let mut output = vec![];
+        Exporter::new()
+            .year(2024)
+            .export(&logs, &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 10);
+        assert_eq!(lines[0], "#separator \\x09");
+        assert!(lines[5].starts_with("#open\t20"));
+        assert_eq!(lines[6], "#fields\tts\tid.orig_h\tid.orig_p\tid.resp_h\tid.resp_p\tproto\tufw_policy\tufw_in\tufw_out\tufw_hostname");
+        assert_eq!(
+            lines[7],
+            "#types\ttime\taddr\tport\taddr\tport\tenum\tstring\tstring\tstring\tstring"
+        );
+        assert!(lines[8].starts_with("1705371232.000000\t"));
+        assert!(lines[9].starts_with("#close\t20"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a\tb\\c,d\n"), "a\\x09b\\x5cc,d\\x0a");
+    }
+}
@@ -0,0 +1,217 @@
+//! Follow a growing log file, like `tail -F`.
+//!
+//! [`Follower`] is an endless iterator of logs appended to the file. It waits for new lines, and
+//! reopens the file from the beginning when the file is truncated or replaced (e.g. rotated by
+//! logrotate).
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use ufwlog::follow::Follower;
+//! use ufwlog::UfwPolicy;
+//!
+//! for log in Follower::open("/var/log/ufw.log")? {
+//!     let log = log?;
+//!     if log.policy == UfwPolicy::Block {
+//!         println!("{} -> {}:{:?}", log.src, log.dst, log.dpt);
+//!     }
+//! }
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use crate::error::Error;
+use crate::ufw_log::UfwLog;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// An endless iterator of logs appended to a file
+///
+/// It starts at the end of file by default, and checks new lines every 250 milliseconds.
+#[derive(Debug)]
+pub struct Follower {
+    path: PathBuf,
+    reader: BufReader<File>,
+    /// Bytes read from the current file.
+    position: u64,
+    /// Bytes of an incomplete line.
+    line: Vec<u8>,
+    interval: Duration,
+    #[cfg(unix)]
+    inode: u64,
+}
+
+impl Follower {
+    /// Open a file and follow it from the end.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be opened.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = File::open(&path)?;
+        let position = file.seek(SeekFrom::End(0))?;
+        Ok(Self {
+            #[cfg(unix)]
+            inode: inode(&file.metadata()?),
+            path,
+            reader: BufReader::new(file),
+            position,
+            line: vec![],
+            interval: Duration::from_millis(250),
+        })
+    }
+
+    /// Read the existing lines from the beginning of file first.
+    pub fn from_start(mut self) -> Result<Self, Error> {
+        self.reader.seek(SeekFrom::Start(0))?;
+        self.position = 0;
+        self.line.clear();
+        Ok(self)
+    }
+
+    /// Set how long to wait before checking new lines again.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Wait for the next complete line.
+    fn next_line(&mut self) -> Result<String, Error> {
+        loop {
+            let size = self.reader.read_until(b'\n', &mut self.line)?;
+            self.position += size as u64;
+            if self.line.ends_with(b"\n") {
+                return Ok(self.take_line());
+            }
+            if size == 0 && self.reopen_if_changed()? && !self.line.is_empty() {
+                // the last line of old file has no line break
+                return Ok(self.take_line());
+            }
+            if size == 0 {
+                std::thread::sleep(self.interval);
+            }
+        }
+    }
+
+    /// Take the buffered line without line break.
+    fn take_line(&mut self) -> String {
+        let line = String::from_utf8_lossy(&self.line)
+            .trim_end_matches(['\r', '\n'])
+            .to_string();
+        self.line.clear();
+        line
+    }
+
+    /// Reopen the file from the beginning if it is truncated or replaced, return whether it is
+    /// reopened. A missing file is treated as unchanged, to wait for it to be created again.
+    fn reopen_if_changed(&mut self) -> Result<bool, Error> {
+        let metadata = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        let truncated = metadata.len() < self.position;
+        #[cfg(unix)]
+        let replaced = inode(&metadata) != self.inode;
+        #[cfg(not(unix))]
+        let replaced = false;
+        if !truncated && !replaced {
+            return Ok(false);
+        }
+
+        let file = File::open(&self.path)?;
+        #[cfg(unix)]
+        {
+            self.inode = inode(&file.metadata()?);
+        }
+        self.reader = BufReader::new(file);
+        self.position = 0;
+        Ok(true)
+    }
+}
+
+impl Iterator for Follower {
+    type Item = Result<UfwLog, Error>;
+
+    /// Wait for the next log, it never returns `None`.
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(
+            self.next_line()
+                .and_then(|line| UfwLog::from_str(line.as_str())),
+        )
+    }
+}
+
+#[cfg(unix)]
+fn inode(metadata: &std::fs::Metadata) -> u64 {
+    std::os::unix::fs::MetadataExt::ino(metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    fn append(path: &Path, content: &str) {
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_follow() {
+        let path = std::env::temp_dir().join(format!("ufwlog-follow-{}.log", std::process::id()));
+        std::fs::write(&path, format!("{LOG}\n")).unwrap();
+        let mut follower = Follower::open(&path)
+            .unwrap()
+            .poll_interval(Duration::from_millis(10));
+
+        // start at the end, so the existing line is skipped
+        let other = LOG.replace("SPT=46468", "SPT=1234");
+        append(&path, &other[..50]);
+        let writer = {
+            let path = path.clone();
+            let other = other.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(50));
+                append(&path, &format!("{}\n", &other[50..]));
+            })
+        };
+        assert_eq!(follower.next().unwrap().unwrap().spt, Some(1234));
+        writer.join().unwrap();
+
+        // truncated, read from the beginning
+        std::fs::write(&path, format!("{LOG}\n")).unwrap();
+        assert_eq!(follower.next().unwrap().unwrap().spt, Some(46468));
+
+        // replaced by a new file
+        #[cfg(unix)]
+        {
+            let rotated = path.with_extension("log.1");
+            std::fs::rename(&path, &rotated).unwrap();
+            std::fs::write(&path, format!("{other}\n{other}\n")).unwrap();
+            assert_eq!(follower.next().unwrap().unwrap().spt, Some(1234));
+            assert_eq!(follower.next().unwrap().unwrap().spt, Some(1234));
+            std::fs::remove_file(&rotated).unwrap();
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_from_start() {
+        let path = std::env::temp_dir().join(format!("ufwlog-start-{}.log", std::process::id()));
+        std::fs::write(&path, format!("{LOG}\r\n")).unwrap();
+        let mut follower = Follower::open(&path).unwrap().from_start().unwrap();
+        assert_eq!(follower.next().unwrap().unwrap().get_origin(), LOG);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
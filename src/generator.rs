@@ -0,0 +1,675 @@
+//! Generate synthetic ufw log records.
+//!
+//! The generated lines follow the same layout as the kernel writes to `ufw.log`, so they can be
+//! parsed by [`UfwLog::from_str`](std::str::FromStr::from_str). This is useful for load tests,
+//! demos and fixtures when real logs are not available or cannot be shared.
+//!
+//! Output is fully determined by [`Config::seed`], so the same config always produces the same
+//! lines.
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use std::str::FromStr;
+//! use ufwlog::generator::{Config, Generator};
+//! use ufwlog::UfwLog;
+//!
+//! let config = Config {
+//!     seed: 5232,
+//!     ..Config::default()
+//! };
+//! for line in Generator::new(config).take(100) {
+//!     assert!(UfwLog::from_str(&line).is_ok());
+//! }
+//! ```
+
+use crate::ufw_log::Policy;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Days of each month, February is always treated as 28 days because ufw log has no year.
+const DAYS_OF_MONTH: [u8; 12] = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+
+/// Ports that commonly be found in ufw log.
+const COMMON_PORTS: [u16; 14] = [
+    22, 23, 25, 53, 80, 123, 137, 443, 445, 1900, 3306, 3389, 5353, 8080,
+];
+
+/// Network protocol of generated record.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Protocol {
+    Tcp,
+    Udp,
+    /// ICMP for IPv4.
+    ///
+    /// It will be generated as [`Protocol::Icmpv6`] if the record is an IPv6 packet.
+    Icmp,
+    /// ICMP for IPv6.
+    ///
+    /// It will be generated as [`Protocol::Icmp`] if the record is an IPv4 packet.
+    Icmpv6,
+}
+
+impl FromStr for Protocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "TCP" => Ok(Protocol::Tcp),
+            "UDP" => Ok(Protocol::Udp),
+            "ICMP" => Ok(Protocol::Icmp),
+            "ICMPV6" => Ok(Protocol::Icmpv6),
+            _ => Err(format!("Unknown protocol: {s}")),
+        }
+    }
+}
+
+impl Display for Protocol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Protocol::Tcp => "TCP",
+                Protocol::Udp => "UDP",
+                Protocol::Icmp => "ICMP",
+                Protocol::Icmpv6 => "ICMPv6",
+            }
+        )
+    }
+}
+
+/// The config of [`Generator`].
+///
+/// Weights are relative, e.g. `[(Policy::Block, 3), (Policy::Allow, 1)]` means 75% of records
+/// are blocked. Entries with zero weight will never be chosen.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Seed of the random number generator.
+    pub seed: u64,
+    /// Host names of the machines which write the log. Each record picks one randomly.
+    pub hostnames: Vec<String>,
+    /// Weights of policy for ordinary records.
+    pub policies: Vec<(Policy, u32)>,
+    /// Weights of protocol for ordinary records.
+    pub protocols: Vec<(Protocol, u32)>,
+    /// Probability that a record is an IPv6 packet, 0.0-1.0.
+    pub ipv6_ratio: f64,
+    /// Probability that a port scan starts at a record, 0.0-1.0.
+    ///
+    /// A port scan is a burst of blocked TCP SYN packets from one source to many ports.
+    pub scan_ratio: f64,
+    /// Probability that a brute-force attack starts at a record, 0.0-1.0.
+    ///
+    /// A brute-force attack is a burst of TCP SYN packets from one source to a single login
+    /// service port (e.g. SSH), most of them hit the rate limit.
+    pub brute_force_ratio: f64,
+    /// Month (1-12), day (1-31) and seconds since midnight of the first record.
+    pub start: (u8, u8, u32),
+    /// The maximum seconds between two records.
+    pub max_interval: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            hostnames: vec!["ubuntu".to_string()],
+            policies: vec![
+                (Policy::Block, 80),
+                (Policy::Allow, 10),
+                (Policy::Audit, 7),
+                (Policy::AuditInvalid, 2),
+                (Policy::LimitBlock, 1),
+            ],
+            protocols: vec![
+                (Protocol::Tcp, 70),
+                (Protocol::Udp, 20),
+                (Protocol::Icmp, 8),
+                (Protocol::Icmpv6, 2),
+            ],
+            ipv6_ratio: 0.1,
+            scan_ratio: 0.002,
+            brute_force_ratio: 0.002,
+            start: (1, 1, 0),
+            max_interval: 10,
+        }
+    }
+}
+
+/// A host which writes log.
+struct Host {
+    name: String,
+    interface: String,
+    mac: [u8; 6],
+    ipv4: [u8; 4],
+    ipv6: [u16; 8],
+}
+
+/// An ongoing attack which produces a burst of records.
+struct Burst {
+    /// index of attacked host
+    host: usize,
+    /// The attacker is IPv6 or not
+    ipv6: bool,
+    src: String,
+    mac: [u8; 6],
+    /// Remaining records of this burst
+    remaining: u32,
+    kind: BurstKind,
+}
+
+enum BurstKind {
+    /// Next port to scan.
+    Scan(u16),
+    /// The port to attack.
+    BruteForce(u16),
+}
+
+/// A packet to be written as a log line.
+struct Packet {
+    policy: Policy,
+    ipv6: bool,
+    incoming: bool,
+    protocol: Protocol,
+    src: String,
+    dst: String,
+    mac: Option<String>,
+    spt: u16,
+    dpt: u16,
+    syn_only: bool,
+}
+
+/// Synthetic ufw log generator.
+///
+/// It is an infinite iterator of log lines, use [`Iterator::take`] to limit the volume.
+pub struct Generator {
+    config: Config,
+    rng: Rng,
+    hosts: Vec<Host>,
+    month: u8,
+    day: u8,
+    /// seconds since midnight
+    seconds: u32,
+    /// uptime in microseconds
+    uptime: u64,
+    burst: Option<Burst>,
+}
+
+impl Generator {
+    /// New a generator with given config.
+    pub fn new(config: Config) -> Self {
+        let mut rng = Rng::new(config.seed);
+        let hostnames = if config.hostnames.is_empty() {
+            Config::default().hostnames
+        } else {
+            config.hostnames.clone()
+        };
+        let hosts = hostnames
+            .into_iter()
+            .map(|name| Host {
+                name,
+                interface: ["eth0", "enp3s0", "ens18", "wlan0"][rng.below(4) as usize].to_string(),
+                mac: rng.mac(),
+                ipv4: [192, 168, rng.below(8) as u8, 2 + rng.below(250) as u8],
+                ipv6: [
+                    0x2001,
+                    0x0db8,
+                    rng.below(0x10000) as u16,
+                    rng.below(0x10000) as u16,
+                    rng.below(0x10000) as u16,
+                    rng.below(0x10000) as u16,
+                    rng.below(0x10000) as u16,
+                    rng.below(0x10000) as u16,
+                ],
+            })
+            .collect();
+        let (month, day, seconds) = config.start;
+        let uptime = (3600 + rng.below(60 * 86400)) * 1_000_000 + rng.below(1_000_000);
+        Self {
+            month: month.clamp(1, 12),
+            day: day.clamp(1, DAYS_OF_MONTH[(month.clamp(1, 12) - 1) as usize]),
+            seconds: seconds % 86400,
+            uptime,
+            config,
+            rng,
+            hosts,
+            burst: None,
+        }
+    }
+
+    /// Move the clock forward.
+    fn tick(&mut self, max: u32) {
+        let elapsed = self.rng.below(max as u64 + 1);
+        // uptime stops at the maximum instead of overflow after a long run of large intervals
+        self.uptime = self
+            .uptime
+            .saturating_add(elapsed * 1_000_000 + self.rng.below(1_000_000));
+        // in u64 so a large interval doesn't overflow
+        let seconds = self.seconds as u64 + elapsed;
+        self.seconds = (seconds % 86400) as u32;
+        // the calendar has no leap day, so it repeats every 365 days
+        for _ in 0..seconds / 86400 % 365 {
+            self.day += 1;
+            if self.day > DAYS_OF_MONTH[(self.month - 1) as usize] {
+                self.day = 1;
+                self.month = self.month % 12 + 1;
+            }
+        }
+    }
+
+    /// Make a packet from the ongoing burst, or start a new one if the dice says so.
+    fn burst_packet(&mut self) -> Option<(usize, Packet)> {
+        if self.burst.is_none() {
+            let kind = if self.rng.chance(self.config.scan_ratio) {
+                BurstKind::Scan(1 + self.rng.below(1024) as u16)
+            } else if self.rng.chance(self.config.brute_force_ratio) {
+                BurstKind::BruteForce([22, 22, 22, 21, 3389][self.rng.below(5) as usize])
+            } else {
+                return None;
+            };
+            let ipv6 = self.rng.chance(self.config.ipv6_ratio);
+            self.burst = Some(Burst {
+                host: self.rng.below(self.hosts.len() as u64) as usize,
+                ipv6,
+                src: if ipv6 {
+                    self.rng.public_ipv6()
+                } else {
+                    self.rng.public_ipv4()
+                },
+                mac: self.rng.mac(),
+                remaining: 20 + self.rng.below(200) as u32,
+                kind,
+            });
+        }
+
+        let mut burst = self.burst.take().unwrap();
+        let host = &self.hosts[burst.host];
+        let (policy, dpt) = match burst.kind {
+            BurstKind::Scan(port) => {
+                burst.kind = BurstKind::Scan(port.wrapping_add(1).max(1));
+                (Policy::Block, port)
+            }
+            BurstKind::BruteForce(port) => {
+                // the first few attempts reach the service, then hit the rate limit
+                let policy = if self.rng.chance(0.8) {
+                    Policy::LimitBlock
+                } else {
+                    Policy::Allow
+                };
+                (policy, port)
+            }
+        };
+        let packet = Packet {
+            policy,
+            ipv6: burst.ipv6,
+            incoming: true,
+            protocol: Protocol::Tcp,
+            src: burst.src.clone(),
+            dst: if burst.ipv6 {
+                format_ipv6(&host.ipv6)
+            } else {
+                format_ipv4(&host.ipv4)
+            },
+            mac: Some(format_mac(&host.mac, &burst.mac, burst.ipv6)),
+            spt: 1024 + self.rng.below(64511) as u16,
+            dpt,
+            syn_only: true,
+        };
+        let index = burst.host;
+        burst.remaining -= 1;
+        if burst.remaining > 0 {
+            self.burst = Some(burst);
+        }
+        Some((index, packet))
+    }
+
+    /// Make an ordinary packet.
+    fn packet(&mut self) -> (usize, Packet) {
+        let index = self.rng.below(self.hosts.len() as u64) as usize;
+        let policy = self
+            .rng
+            .weighted(&self.config.policies)
+            .unwrap_or(Policy::Block);
+        let ipv6 = self.rng.chance(self.config.ipv6_ratio);
+        let protocol = match self.rng.weighted(&self.config.protocols) {
+            Some(Protocol::Icmp) | Some(Protocol::Icmpv6) if ipv6 => Protocol::Icmpv6,
+            Some(Protocol::Icmp) | Some(Protocol::Icmpv6) => Protocol::Icmp,
+            Some(p) => p,
+            None => Protocol::Tcp,
+        };
+        // allowed packets are mostly outgoing
+        let incoming = policy != Policy::Allow || self.rng.chance(0.3);
+        let host = &self.hosts[index];
+        let local = if ipv6 {
+            format_ipv6(&host.ipv6)
+        } else {
+            format_ipv4(&host.ipv4)
+        };
+        let remote = if ipv6 {
+            self.rng.public_ipv6()
+        } else {
+            self.rng.public_ipv4()
+        };
+        let well_known = COMMON_PORTS[self.rng.below(COMMON_PORTS.len() as u64) as usize];
+        let ephemeral = 32768 + self.rng.below(28232) as u16;
+        let host = &self.hosts[index];
+        let packet = if incoming {
+            Packet {
+                policy,
+                ipv6,
+                incoming,
+                protocol,
+                src: remote,
+                dst: local,
+                mac: Some(format_mac(&host.mac, &self.rng.mac(), ipv6)),
+                spt: ephemeral,
+                dpt: well_known,
+                syn_only: self.rng.chance(0.9),
+            }
+        } else {
+            Packet {
+                policy,
+                ipv6,
+                incoming,
+                protocol,
+                src: local,
+                dst: remote,
+                mac: None,
+                spt: ephemeral,
+                dpt: well_known,
+                syn_only: self.rng.chance(0.9),
+            }
+        };
+        (index, packet)
+    }
+
+    /// Render a packet to a log line.
+    fn render(&mut self, host: usize, packet: Packet) -> String {
+        let host = &self.hosts[host];
+        let mut line = format!(
+            "{} {:>2} {:02}:{:02}:{:02} {} kernel: [{:>5}.{:06}] [UFW {}] ",
+            crate::ufw_log::MONTH[(self.month - 1) as usize],
+            self.day,
+            self.seconds / 3600,
+            self.seconds / 60 % 60,
+            self.seconds % 60,
+            host.name,
+            self.uptime / 1_000_000,
+            self.uptime % 1_000_000,
+            packet.policy,
+        );
+        if packet.incoming {
+            line.push_str(&format!("IN={} OUT= ", host.interface));
+        } else {
+            line.push_str(&format!("IN= OUT={} ", host.interface));
+        }
+        if let Some(mac) = &packet.mac {
+            line.push_str(&format!("MAC={mac} "));
+        }
+        line.push_str(&format!("SRC={} DST={} ", packet.src, packet.dst));
+
+        let header_len: u32 = if packet.ipv6 { 40 } else { 20 };
+        let payload_len: u32 = match packet.protocol {
+            Protocol::Tcp if packet.syn_only => 40,
+            Protocol::Tcp => 20 + self.rng.below(1400) as u32,
+            Protocol::Udp => 8 + self.rng.below(512) as u32,
+            Protocol::Icmp | Protocol::Icmpv6 => 64,
+        };
+        let len = header_len + payload_len;
+        if packet.ipv6 {
+            line.push_str(&format!(
+                "LEN={} TC=0 HOPLIMIT={} FLOWLBL={} ",
+                len,
+                [64, 255, 52, 128][self.rng.below(4) as usize],
+                self.rng.below(0x100000)
+            ));
+        } else {
+            line.push_str(&format!(
+                "LEN={} TOS=0x00 PREC=0x00 TTL={} ID={} {}",
+                len,
+                [64, 128, 52, 245][self.rng.below(4) as usize],
+                self.rng.below(65536),
+                if self.rng.chance(0.6) { "DF " } else { "" }
+            ));
+        }
+
+        let protocol = match packet.protocol {
+            Protocol::Icmp | Protocol::Icmpv6 if packet.ipv6 => Protocol::Icmpv6,
+            Protocol::Icmp | Protocol::Icmpv6 => Protocol::Icmp,
+            p => p,
+        };
+        line.push_str(&format!("PROTO={protocol} "));
+        match protocol {
+            Protocol::Tcp => {
+                let flags = if packet.syn_only {
+                    "SYN"
+                } else {
+                    ["ACK", "ACK PSH", "ACK FIN", "RST", "ACK RST"][self.rng.below(5) as usize]
+                };
+                line.push_str(&format!(
+                    "SPT={} DPT={} WINDOW={} RES=0x00 {} URGP=0",
+                    packet.spt,
+                    packet.dpt,
+                    [1024, 29200, 64240, 65535][self.rng.below(4) as usize],
+                    flags
+                ));
+            }
+            Protocol::Udp => {
+                line.push_str(&format!(
+                    "SPT={} DPT={} LEN={}",
+                    packet.spt, packet.dpt, payload_len
+                ));
+            }
+            Protocol::Icmp => {
+                line.push_str(&format!(
+                    "TYPE=8 CODE=0 ID={} SEQ={}",
+                    self.rng.below(65536),
+                    1 + self.rng.below(16)
+                ));
+            }
+            Protocol::Icmpv6 => {
+                line.push_str(&format!(
+                    "TYPE=128 CODE=0 ID={} SEQ={}",
+                    self.rng.below(65536),
+                    1 + self.rng.below(16)
+                ));
+            }
+        }
+        line
+    }
+}
+
+impl Iterator for Generator {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (host, packet) = match self.burst_packet() {
+            Some(burst) => {
+                // attacks are fast
+                self.tick(1);
+                burst
+            }
+            None => {
+                self.tick(self.config.max_interval);
+                self.packet()
+            }
+        };
+        Some(self.render(host, packet))
+    }
+}
+
+/// Format IPv4 address.
+fn format_ipv4(ip: &[u8; 4]) -> String {
+    format!("{}.{}.{}.{}", ip[0], ip[1], ip[2], ip[3])
+}
+
+/// Format IPv6 address as the kernel does, i.e. full form without compression.
+fn format_ipv6(ip: &[u16; 8]) -> String {
+    ip.iter()
+        .map(|g| format!("{g:04x}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Format MAC field: destination MAC, source MAC, and EtherType.
+fn format_mac(dst: &[u8; 6], src: &[u8; 6], ipv6: bool) -> String {
+    dst.iter()
+        .chain(src.iter())
+        .chain(if ipv6 { [0x86, 0xdd] } else { [0x08, 0x00] }.iter())
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// A small, fast and reproducible pseudo random number generator (SplitMix64).
+///
+/// It is **not** cryptographically secure, but good enough for fake data.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Random number in `[0, n)`. Return 0 if `n` is 0.
+    fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            return 0;
+        }
+        self.next_u64() % n
+    }
+
+    /// Return true with given probability.
+    fn chance(&mut self, probability: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    /// Pick an item by weight, return `None` if all weights are zero.
+    fn weighted<T: Copy>(&mut self, items: &[(T, u32)]) -> Option<T> {
+        let total: u64 = items.iter().map(|(_, w)| *w as u64).sum();
+        let mut n = self.below(total);
+        for (item, weight) in items {
+            if n < *weight as u64 {
+                return Some(*item);
+            }
+            n -= *weight as u64;
+        }
+        None
+    }
+
+    /// Random unicast MAC address.
+    fn mac(&mut self) -> [u8; 6] {
+        let mut mac = [0u8; 6];
+        for byte in mac.iter_mut() {
+            *byte = self.below(256) as u8;
+        }
+        mac[0] &= 0xfe; // unicast
+        mac
+    }
+
+    /// Random IPv4 address which is not private, loopback or multicast.
+    fn public_ipv4(&mut self) -> String {
+        loop {
+            let ip = [
+                1 + self.below(223) as u8,
+                self.below(256) as u8,
+                self.below(256) as u8,
+                1 + self.below(254) as u8,
+            ];
+            let private = ip[0] == 10
+                || ip[0] == 127
+                || (ip[0] == 172 && (16..32).contains(&ip[1]))
+                || (ip[0] == 192 && ip[1] == 168);
+            if !private {
+                return format_ipv4(&ip);
+            }
+        }
+    }
+
+    /// Random global unicast IPv6 address.
+    fn public_ipv6(&mut self) -> String {
+        let mut ip = [0u16; 8];
+        ip[0] = 0x2000 + self.below(0x1000) as u16;
+        for group in ip.iter_mut().skip(1) {
+            *group = self.below(0x10000) as u16;
+        }
+        format_ipv6(&ip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UfwLog;
+
+    #[test]
+    fn test_output_can_be_parsed() {
+        let config = Config {
+            seed: 42,
+            hostnames: vec!["web-1".to_string(), "db-1".to_string()],
+            ipv6_ratio: 0.5,
+            scan_ratio: 0.01,
+            brute_force_ratio: 0.01,
+            ..Config::default()
+        };
+        for line in Generator::new(config).take(2000) {
+            let log = UfwLog::from_str(&line).unwrap();
+            assert_ne!(log.policy, Policy::Unknown, "{line}");
+            assert!(log.hostname == "web-1" || log.hostname == "db-1");
+            assert!(!log.src.is_empty() && !log.dst.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_output() {
+        let a: Vec<String> = Generator::new(Config::default()).take(100).collect();
+        let b: Vec<String> = Generator::new(Config::default()).take(100).collect();
+        assert_eq!(a, b);
+
+        let config = Config {
+            seed: 1,
+            ..Config::default()
+        };
+        let c: Vec<String> = Generator::new(config).take(100).collect();
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_mix() {
+        let config = Config {
+            policies: vec![(Policy::Allow, 1), (Policy::Audit, 0)],
+            protocols: vec![(Protocol::Udp, 1)],
+            ipv6_ratio: 0.0,
+            scan_ratio: 0.0,
+            brute_force_ratio: 0.0,
+            ..Config::default()
+        };
+        for line in Generator::new(config).take(200) {
+            let log = UfwLog::from_str(&line).unwrap();
+            assert_eq!(log.policy, Policy::Allow);
+            assert_eq!(log.proto, "UDP");
+            assert!(!log.src.contains(':'));
+        }
+    }
+
+    #[test]
+    fn test_large_interval() {
+        let config = Config {
+            max_interval: u32::MAX,
+            ..Config::default()
+        };
+        // the uptime would overflow after about 8,600 records
+        for line in Generator::new(config).take(20_000) {
+            UfwLog::from_str(&line).unwrap();
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! HTTP client shared by the exporters which push logs to server.
+//!
+//! Requests are retried with exponential backoff on connection error, `429 Too Many Requests`
+//! and `5xx` server error, see [`Retry`].
+
+use crate::error::Error;
+use std::time::Duration;
+
+/// Timeout of a whole request, including connecting and reading response.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Retry policy of failed request
+///
+/// The first retry waits `initial_backoff`, and the wait is doubled for each retry up to
+/// `max_backoff`. `Retry-After` of response is respected if it is given in seconds, but it is
+/// capped by `max_backoff` too, so a server can't make the sender wait for long.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Retry {
+    /// Maximum retries after the first attempt, zero means never retry.
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for Retry {
+    /// Retry 5 times, wait from 500 milliseconds up to 30 seconds.
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl Retry {
+    /// Never retry.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// How long to wait before the given retry, which starts from zero.
+    pub(crate) fn backoff(&self, retry: u32, retry_after: Option<Duration>) -> Duration {
+        let backoff = retry_after.unwrap_or_else(|| {
+            self.initial_backoff
+                .checked_mul(2u32.saturating_pow(retry))
+                .unwrap_or(self.max_backoff)
+        });
+        backoff.min(self.max_backoff)
+    }
+}
+
+/// HTTP client of a sender, which reuses connections between requests.
+#[derive(Debug, Clone)]
+pub(crate) struct Client {
+    agent: ureq::Agent,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self {
+            agent: ureq::Agent::config_builder()
+                .timeout_global(Some(TIMEOUT))
+                .http_status_as_error(false)
+                .build()
+                .into(),
+        }
+    }
+}
+
+impl Client {
+    /// POST body to given URL with headers, and retry on failure, return the body of response.
+    /// An error is returned if the status isn't 2xx after retries.
+    ///
+    /// Only connection errors, timeouts, `429` and `5xx` are retried, other errors (e.g. invalid
+    /// URL) are returned immediately.
+    pub(crate) fn post(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+        body: &[u8],
+        retry: &Retry,
+    ) -> Result<String, Error> {
+        let mut attempt = 0;
+        loop {
+            let mut request = self.agent.post(url);
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+            let (error, retry_after) = match request.send(body) {
+                Ok(mut response) => {
+                    let status = response.status().as_u16();
+                    let retry_after = response
+                        .headers()
+                        .get("retry-after")
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.trim().parse().ok())
+                        .map(Duration::from_secs);
+                    let body = response.body_mut().read_to_string().unwrap_or_default();
+                    if (200..300).contains(&status) {
+                        return Ok(body);
+                    }
+                    let error = Error::Http(format!("HTTP {status} from {url}: {}", body.trim()));
+                    if status != 429 && status < 500 {
+                        return Err(error);
+                    }
+                    (error, retry_after)
+                }
+                Err(e) if is_transient(&e) => (e.into(), None),
+                Err(e) => return Err(e.into()),
+            };
+            if attempt >= retry.max_retries {
+                return Err(error);
+            }
+            std::thread::sleep(retry.backoff(attempt, retry_after));
+            attempt += 1;
+        }
+    }
+}
+
+/// Whether the request may succeed if it is sent again.
+fn is_transient(error: &ureq::Error) -> bool {
+    matches!(
+        error,
+        ureq::Error::Io(_)
+            | ureq::Error::Timeout(_)
+            | ureq::Error::HostNotFound
+            | ureq::Error::ConnectionFailed
+            | ureq::Error::BodyStalled
+    )
+}
+
+impl From<ureq::Error> for Error {
+    fn from(value: ureq::Error) -> Self {
+        Error::Http(value.to_string())
+    }
+}
+
+/// A mock HTTP server for tests, which records requests and replies with given status.
+#[cfg(test)]
+pub(crate) mod mock {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    /// A request received by mock server
+    #[derive(Debug)]
+    pub(crate) struct Request {
+        pub(crate) method: String,
+        pub(crate) path: String,
+        /// Header names are in lowercase.
+        pub(crate) headers: Vec<(String, String)>,
+        pub(crate) body: Vec<u8>,
+    }
+
+    impl Request {
+        pub(crate) fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+        }
+    }
+
+    /// Serve one request per status in order, return the base URL (e.g. `http://127.0.0.1:8080`)
+    /// and a handle to get received requests.
+    pub(crate) fn serve(statuses: Vec<u16>) -> (String, JoinHandle<Vec<Request>>) {
+        serve_responses(
+            statuses
+                .into_iter()
+                .map(|s| (s, "{}".to_string()))
+                .collect(),
+        )
+    }
+
+    /// Serve one request per status and body in order, see [`serve`].
+    pub(crate) fn serve_responses(
+        responses: Vec<(u16, String)>,
+    ) -> (String, JoinHandle<Vec<Request>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = vec![];
+            for (status, response) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let mut parts = line.split_whitespace();
+                let method = parts.next().unwrap_or_default().to_string();
+                let path = parts.next().unwrap_or_default().to_string();
+                let mut headers = vec![];
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    match line.trim_end().split_once(':') {
+                        Some((name, value)) => {
+                            headers.push((name.to_lowercase(), value.trim().to_string()))
+                        }
+                        None => break,
+                    }
+                }
+                let request = Request {
+                    method,
+                    path,
+                    headers,
+                    body: vec![],
+                };
+                let length = request
+                    .header("content-length")
+                    .map_or(0, |l| l.parse().unwrap());
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {status} Mock\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+                    response.len()
+                )
+                .unwrap();
+                requests.push(Request { body, ..request });
+            }
+            requests
+        });
+        (url, handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let retry = Retry {
+            max_retries: 10,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+        };
+        assert_eq!(retry.backoff(0, None), Duration::from_secs(1));
+        assert_eq!(retry.backoff(2, None), Duration::from_secs(4));
+        assert_eq!(retry.backoff(3, None), Duration::from_secs(5));
+        assert_eq!(retry.backoff(40, None), Duration::from_secs(5));
+        assert_eq!(
+            retry.backoff(0, Some(Duration::from_secs(3))),
+            Duration::from_secs(3)
+        );
+        assert_eq!(
+            retry.backoff(0, Some(Duration::from_secs(u64::MAX))),
+            Duration::from_secs(5)
+        );
+    }
+
+    #[test]
+    fn test_post_retry() {
+        let (url, server) = mock::serve(vec![503, 429, 200]);
+        let retry = Retry {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(1),
+            ..Retry::default()
+        };
+        let client = Client::default();
+        assert_eq!(client.post(&url, &[], b"hello", &retry).unwrap(), "{}");
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(requests.iter().all(|r| r.body == b"hello"));
+    }
+
+    #[test]
+    fn test_post_client_error_not_retried() {
+        let (url, server) = mock::serve(vec![400]);
+        let result = Client::default().post(&url, &[], b"", &Retry::default());
+        assert!(matches!(result, Err(Error::Http(e)) if e.starts_with("HTTP 400")));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_post_invalid_url_not_retried() {
+        let retry = Retry {
+            initial_backoff: Duration::from_secs(60),
+            ..Retry::default()
+        };
+        let result = Client::default().post("not a url", &[], b"", &retry);
+        assert!(matches!(result, Err(Error::Http(_))));
+    }
+}
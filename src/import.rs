@@ -0,0 +1,21 @@
+//! Load UFW logs back from exported formats.
+//!
+//! This module is the counterpart of [export](crate::export), so the archived output can be
+//! analyzed again as [`UfwLog`](crate::UfwLog).
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use std::io::BufReader;
+//! use ufwlog::{UfwLog, UfwPolicy};
+//!
+//! let reader = BufReader::new(std::fs::File::open("./ufwlog.csv")?);
+//! let blocked = ufwlog::import::csv::Reader::new(reader)
+//!     .filter_map(|log| log.ok())
+//!     .filter(|log| log.policy == UfwPolicy::Block)
+//!     .collect::<Vec<UfwLog>>();
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+pub mod csv;
+pub mod msgpack;
@@ -0,0 +1,318 @@
+//! Csv Importer module
+//!
+//! Read csv produced by [csv exporter](crate::export::csv) back into [`UfwLog`].
+//!
+//! Columns are matched by header, with either the default [`HEADER`](crate::export::csv::HEADER)
+//! or the field name of [`UfwLog`] (case-insensitive), so a subset of columns in any order is
+//! fine. Unknown columns are ignored, and fields without column get default value.
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use ufwlog::import::csv::Reader;
+//!
+//! let csv = "Time,SRC,DST,DPT,policy,Control Bits / flags\n\
+//!            00:11:24,192.168.0.8,127.0.111.111,42313,BLOCK,SYN ACK\n";
+//! let logs = Reader::new(csv.as_bytes()).collect::<Result<Vec<_>, _>>()?;
+//!
+//! assert_eq!(logs[0].src, "192.168.0.8");
+//! assert_eq!(logs[0].dpt, Some(42313));
+//! assert!(logs[0].syn && logs[0].ack && !logs[0].fin);
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use crate::error::Error;
+use crate::export::column::Field;
+use crate::ufw_log::{ParseError, UfwLog, MONTH};
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::str::FromStr;
+
+/// Reader for csv format
+///
+/// It is an iterator of [`UfwLog`] or errors, records are read lazily.
+pub struct Reader<R: BufRead> {
+    reader: R,
+    delimiter: char,
+    /// Field of each column, `None` if the column is unknown. Read from first record.
+    columns: Option<Vec<Option<Field>>>,
+    /// Line number of the next line to read, used for error message.
+    line: usize,
+}
+
+impl<R: BufRead> Reader<R> {
+    /// New a reader, the first record should be header. Default delimiter is comma.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            delimiter: ',',
+            columns: None,
+            line: 1,
+        }
+    }
+
+    /// Set the field delimiter, e.g. `\t` for TSV.
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Read a record, a quoted field may contain line breaks so a record may span multiple
+    /// lines. Return `None` if there is no more record.
+    fn read_record(&mut self) -> Option<Result<Vec<String>, Error>> {
+        let mut fields = vec![];
+        let mut field = String::new();
+        let mut in_quotes = false;
+        let mut line = String::new();
+        let start = self.line;
+
+        loop {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) if fields.is_empty() && field.is_empty() && !in_quotes => return None,
+                Ok(0) => {
+                    if in_quotes {
+                        return Some(Err(ParseError::InvalidFormat {
+                            field: "csv",
+                            description: format!("unclosed quote in the record at line {start}"),
+                        }
+                        .into()));
+                    }
+                    break;
+                }
+                Ok(_) => self.line += 1,
+                Err(e) => return Some(Err(e.into())),
+            }
+            // skip empty line between records
+            if fields.is_empty() && field.is_empty() && !in_quotes && line.trim_end().is_empty() {
+                continue;
+            }
+
+            let mut chars = line.chars().peekable();
+            while let Some(c) = chars.next() {
+                if in_quotes {
+                    if c == '"' {
+                        if chars.peek() == Some(&'"') {
+                            // escaped quote
+                            field.push(chars.next().unwrap());
+                        } else {
+                            in_quotes = false;
+                        }
+                    } else {
+                        field.push(c);
+                    }
+                } else if c == '"' {
+                    in_quotes = true;
+                } else if c == self.delimiter {
+                    fields.push(std::mem::take(&mut field));
+                } else if c == '\n' || (c == '\r' && chars.peek() == Some(&'\n')) {
+                    break;
+                } else {
+                    field.push(c);
+                }
+            }
+            if !in_quotes {
+                break;
+            }
+        }
+
+        fields.push(field);
+        Some(Ok(fields))
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<UfwLog, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.columns.is_none() {
+            let header = match self.read_record()? {
+                Ok(header) => header,
+                Err(e) => return Some(Err(e)),
+            };
+            self.columns = Some(
+                header
+                    .iter()
+                    .map(|name| Field::from_str(name).ok())
+                    .collect(),
+            );
+        }
+
+        let record = match self.read_record()? {
+            Ok(record) => record,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(to_ufwlog(self.columns.as_ref().unwrap(), record))
+    }
+}
+
+/// Convert a record to UfwLog with the columns, empty value means absent.
+///
+/// It's shared with other importers whose values are converted into string.
+pub(crate) fn to_ufwlog(columns: &[Option<Field>], record: Vec<String>) -> Result<UfwLog, Error> {
+    let mut data: HashMap<&str, String> = HashMap::new();
+    for (field, value) in columns.iter().zip(record) {
+        let field = match field {
+            Some(f) => *f,
+            None => continue,
+        };
+        // empty string means absent optional value
+        if value.is_empty() {
+            continue;
+        }
+        match field {
+            Field::Month => {
+                // exported as number, but accept abbreviation as well
+                let month = match value.parse::<usize>() {
+                    Ok(n) if (1..=12).contains(&n) => MONTH[n - 1].to_string(),
+                    Ok(_) => {
+                        return Err(ParseError::InvalidNumber {
+                            field: "month",
+                            value,
+                        }
+                        .into())
+                    }
+                    Err(_) => value,
+                };
+                data.insert("month", month);
+            }
+            Field::Policy => {
+                data.insert("event", value);
+            }
+            Field::Df => {
+                let df = matches!(value.to_uppercase().as_str(), "DF" | "TRUE" | "1");
+                data.insert("df", if df { "1" } else { "0" }.to_string());
+            }
+            Field::Flags => {
+                for flag in value.split_whitespace() {
+                    match Field::from_str(flag) {
+                        Ok(
+                            f @ (Field::Syn
+                            | Field::Ack
+                            | Field::Fin
+                            | Field::Rst
+                            | Field::Psh
+                            | Field::Cwr
+                            | Field::Ece
+                            | Field::Urg),
+                        ) => {
+                            data.insert(f.name(), "1".to_string());
+                        }
+                        _ => {
+                            return Err(ParseError::InvalidFormat {
+                                field: "flags",
+                                description: format!("unknown flag '{flag}'"),
+                            }
+                            .into())
+                        }
+                    }
+                }
+            }
+            Field::Syn
+            | Field::Ack
+            | Field::Fin
+            | Field::Rst
+            | Field::Psh
+            | Field::Cwr
+            | Field::Ece
+            | Field::Urg => {
+                let set = matches!(value.to_lowercase().as_str(), "true" | "1");
+                data.insert(field.name(), if set { "1" } else { "0" }.to_string());
+            }
+            _ => {
+                data.insert(field.name(), value);
+            }
+        }
+    }
+    UfwLog::from_hashmap(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::column::{Columns, FlagStyle};
+    use crate::export::csv::Exporter;
+    use crate::export::Export;
+    use crate::UfwPolicy;
+
+    const LOG: &str = "Jan 12 00:11:24 103213020 kernel: [3248415.842951] [UFW LIMIT BLOCK] IN=es6 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:09:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=42313 WINDOW=1024 RES=0x00 SYN ACK URGP=0";
+
+    #[test]
+    fn test_round_trip() {
+        let log = UfwLog::from_str(LOG).unwrap();
+        let mut output = vec![];
+        Exporter::default().export(&[log], &mut output).unwrap();
+
+        let logs = Reader::new(output.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(logs.len(), 1);
+        let log = &logs[0];
+        assert_eq!((log.month, log.day, log.time.as_str()), (1, 12, "00:11:24"));
+        assert_eq!(log.policy, UfwPolicy::LimitBlock);
+        assert_eq!(log.r#in, "es6");
+        assert_eq!(log.out, "");
+        assert_eq!(log.ttl, Some(234));
+        assert!(log.df && log.syn && log.ack && !log.fin);
+        assert_eq!(log.urgp, Some(0));
+        assert_eq!(log.tc, None);
+        assert_eq!(log.get_origin(), LOG);
+    }
+
+    #[test]
+    fn test_subset_reordered_and_separate_flags() {
+        let log = UfwLog::from_str(LOG).unwrap();
+        let mut output = vec![];
+        Exporter::new()
+            .delimiter('\t')
+            .columns(
+                Columns::from_str("flags,dpt,src=ignored")
+                    .unwrap()
+                    .flag_style(FlagStyle::Separate),
+            )
+            .export(&[log], &mut output)
+            .unwrap();
+
+        let logs = Reader::new(output.as_slice())
+            .delimiter('\t')
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let log = &logs[0];
+        assert!(log.syn && log.ack && !log.rst);
+        assert_eq!(log.dpt, Some(42313));
+        // renamed column is unknown
+        assert_eq!(log.src, "");
+    }
+
+    #[test]
+    fn test_quoted_line_break() {
+        let csv = "SRC,origin\r\n1.1.1.1,\"multi\r\nline \"\"origin\"\"\"\r\n2.2.2.2,\r\n";
+        let logs = Reader::new(csv.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].get_origin(), "multi\r\nline \"origin\"");
+        assert_eq!(logs[1].src, "2.2.2.2");
+    }
+
+    #[test]
+    fn test_skip_empty_line() {
+        let csv = "\nSRC\n\n1.1.1.1\n\n\"\"\n";
+        let logs = Reader::new(csv.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].src, "1.1.1.1");
+        assert_eq!(logs[1].src, "");
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut reader = Reader::new("SRC,origin\n1.1.1.1,\"unclosed\n".as_bytes());
+        assert!(reader.next().unwrap().is_err());
+        let mut reader = Reader::new("Month,DPT\n13,22\n1,port\n".as_bytes());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+}
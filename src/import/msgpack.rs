@@ -0,0 +1,372 @@
+//! MessagePack Importer module
+//!
+//! Read archive produced by [msgpack exporter](crate::export::msgpack) back into [`UfwLog`].
+//!
+//! Values of records are matched by the field names in file header, so an archive written by an
+//! older version lacks some fields (which get default value), and fields added by a newer version
+//! are ignored. An archive whose schema version is newer than
+//! [`VERSION`](crate::export::msgpack::VERSION) is rejected.
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use std::str::FromStr;
+//! use ufwlog::export::Export;
+//! use ufwlog::import::msgpack::Reader;
+//! use ufwlog::UfwLog;
+//!
+//! let log = UfwLog::from_str("Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0")?;
+//! let mut archive = vec![];
+//! ufwlog::export::msgpack::Exporter::new().export(&[log], &mut archive)?;
+//!
+//! let logs = Reader::new(archive.as_slice()).collect::<Result<Vec<_>, _>>()?;
+//! assert_eq!(logs[0].src, "192.168.0.8");
+//! assert_eq!(logs[0].dpt, Some(22));
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::csv::to_ufwlog;
+use crate::error::Error;
+use crate::export::column::Field;
+use crate::export::json::Value;
+use crate::export::msgpack::{MAGIC, VERSION};
+use crate::ufw_log::{ParseError, UfwLog};
+use std::io::{BufRead, ErrorKind, Read};
+use std::str::FromStr;
+
+/// Maximum depth of nested arrays and maps, records are flat so it's only for broken input.
+const MAX_DEPTH: usize = 8;
+
+/// Reader for MessagePack archive
+///
+/// It is an iterator of [`UfwLog`] or errors, records are read lazily. The iteration stops after
+/// an error, since the position of next record is unknown in a broken archive.
+pub struct Reader<R: BufRead> {
+    reader: R,
+    /// Field of each value, `None` if the field is unknown. Read from file header.
+    columns: Option<Vec<Option<Field>>>,
+    done: bool,
+}
+
+impl<R: BufRead> Reader<R> {
+    /// New a reader, the input should start with file header.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            columns: None,
+            done: false,
+        }
+    }
+
+    /// Check magic bytes and schema version, then get the fields of header.
+    fn read_header(&mut self) -> Result<Vec<Option<Field>>, Error> {
+        let mut magic = [0; 4];
+        read_exact(&mut self.reader, &mut magic)?;
+        if magic != MAGIC {
+            return Err(invalid("not an ufwlog msgpack archive"));
+        }
+
+        let header = match decode(&mut self.reader, 0)? {
+            Some(Value::Object(pairs)) => pairs,
+            _ => return Err(invalid("file header should be a map")),
+        };
+        let mut version = None;
+        let mut fields = None;
+        for (key, value) in header {
+            match (key.as_str(), value) {
+                ("version", Value::Integer(n)) => version = Some(n),
+                ("fields", Value::Array(names)) => {
+                    fields = names
+                        .into_iter()
+                        .map(|name| match name {
+                            Value::String(name) => Some(Field::from_str(&name).ok()),
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<_>>>();
+                }
+                // keys added by newer version
+                _ => {}
+            }
+        }
+        match version {
+            Some(version) if version > VERSION => Err(invalid(&format!(
+                "schema version {version} is not supported, the newest is {VERSION}"
+            ))),
+            Some(_) => fields.ok_or_else(|| invalid("fields of file header should be strings")),
+            None => Err(invalid("file header has no schema version")),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<UfwLog, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.read_log();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+impl<R: BufRead> Reader<R> {
+    fn read_log(&mut self) -> Option<Result<UfwLog, Error>> {
+        if self.columns.is_none() {
+            match self.read_header() {
+                Ok(columns) => self.columns = Some(columns),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+
+        let values = match decode(&mut self.reader, 0) {
+            Ok(Some(Value::Array(values))) => values,
+            Ok(Some(_)) => return Some(Err(invalid("record should be an array"))),
+            Ok(None) => return None,
+            Err(e) => return Some(Err(e)),
+        };
+        let record = match values
+            .into_iter()
+            .map(|value| match value {
+                Value::Null => Some(String::new()),
+                Value::Bool(b) => Some(if b { "1" } else { "0" }.to_string()),
+                Value::Integer(n) => Some(n.to_string()),
+                Value::Float(n) => Some(n.to_string()),
+                Value::String(s) => Some(s),
+                Value::Array(_) | Value::Object(_) => None,
+            })
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(record) => record,
+            None => return Some(Err(invalid("value of record should not be nested"))),
+        };
+        Some(to_ufwlog(self.columns.as_ref().unwrap(), record))
+    }
+}
+
+fn invalid(description: &str) -> Error {
+    ParseError::InvalidFormat {
+        field: "msgpack",
+        description: description.to_string(),
+    }
+    .into()
+}
+
+/// Read exactly the buffer, a truncated archive is invalid.
+fn read_exact(reader: &mut impl Read, buffer: &mut [u8]) -> Result<(), Error> {
+    reader.read_exact(buffer).map_err(|e| match e.kind() {
+        ErrorKind::UnexpectedEof => invalid("unexpected end of archive"),
+        _ => e.into(),
+    })
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N], Error> {
+    let mut buffer = [0; N];
+    read_exact(reader, &mut buffer)?;
+    Ok(buffer)
+}
+
+/// Decode a value, return `None` if the input ends before the value at top level.
+fn decode(reader: &mut impl BufRead, depth: usize) -> Result<Option<Value>, Error> {
+    if depth == 0 && reader.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+    if depth > MAX_DEPTH {
+        return Err(invalid("values are nested too deep"));
+    }
+
+    let [marker] = read_array(reader)?;
+    let value = match marker {
+        0x00..=0x7f => Value::Integer(marker as i64),
+        0x80..=0x8f => decode_map(reader, (marker & 0x0f) as usize, depth)?,
+        0x90..=0x9f => decode_array(reader, (marker & 0x0f) as usize, depth)?,
+        0xa0..=0xbf => decode_string(reader, (marker & 0x1f) as usize)?,
+        0xc0 => Value::Null,
+        0xc2 => Value::Bool(false),
+        0xc3 => Value::Bool(true),
+        0xca => Value::Float(f32::from_be_bytes(read_array(reader)?) as f64),
+        0xcb => Value::Float(f64::from_be_bytes(read_array(reader)?)),
+        0xcc => Value::Integer(u8::from_be_bytes(read_array(reader)?) as i64),
+        0xcd => Value::Integer(u16::from_be_bytes(read_array(reader)?) as i64),
+        0xce => Value::Integer(u32::from_be_bytes(read_array(reader)?) as i64),
+        0xcf => Value::Integer(
+            i64::try_from(u64::from_be_bytes(read_array(reader)?))
+                .map_err(|_| invalid("integer is out of range"))?,
+        ),
+        0xd0 => Value::Integer(i8::from_be_bytes(read_array(reader)?) as i64),
+        0xd1 => Value::Integer(i16::from_be_bytes(read_array(reader)?) as i64),
+        0xd2 => Value::Integer(i32::from_be_bytes(read_array(reader)?) as i64),
+        0xd3 => Value::Integer(i64::from_be_bytes(read_array(reader)?)),
+        0xd9 => {
+            let len = u8::from_be_bytes(read_array(reader)?) as usize;
+            decode_string(reader, len)?
+        }
+        0xda | 0xdc | 0xde => {
+            let len = u16::from_be_bytes(read_array(reader)?) as usize;
+            decode_sized(reader, marker, len, depth)?
+        }
+        0xdb | 0xdd | 0xdf => {
+            let len = u32::from_be_bytes(read_array(reader)?) as usize;
+            decode_sized(reader, marker, len, depth)?
+        }
+        0xe0..=0xff => Value::Integer(marker as i8 as i64),
+        _ => return Err(invalid(&format!("unsupported type 0x{marker:02x}"))),
+    };
+    Ok(Some(value))
+}
+
+/// Decode string, array or map of 16 or 32 bits length.
+fn decode_sized(
+    reader: &mut impl BufRead,
+    marker: u8,
+    len: usize,
+    depth: usize,
+) -> Result<Value, Error> {
+    match marker {
+        0xda | 0xdb => decode_string(reader, len),
+        0xdc | 0xdd => decode_array(reader, len, depth),
+        _ => decode_map(reader, len, depth),
+    }
+}
+
+fn decode_string(reader: &mut impl BufRead, len: usize) -> Result<Value, Error> {
+    // don't trust the length for allocation, the archive may be broken
+    let mut bytes = vec![];
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() < len {
+        return Err(invalid("unexpected end of archive"));
+    }
+    String::from_utf8(bytes)
+        .map(Value::String)
+        .map_err(|_| invalid("string is not valid UTF-8"))
+}
+
+fn decode_array(reader: &mut impl BufRead, len: usize, depth: usize) -> Result<Value, Error> {
+    let mut values = vec![];
+    for _ in 0..len {
+        values.push(decode_nested(reader, depth)?);
+    }
+    Ok(Value::Array(values))
+}
+
+fn decode_map(reader: &mut impl BufRead, len: usize, depth: usize) -> Result<Value, Error> {
+    let mut pairs = vec![];
+    for _ in 0..len {
+        let key = match decode_nested(reader, depth)? {
+            Value::String(key) => key,
+            _ => return Err(invalid("key of map should be a string")),
+        };
+        pairs.push((key, decode_nested(reader, depth)?));
+    }
+    Ok(Value::Object(pairs))
+}
+
+fn decode_nested(reader: &mut impl BufRead, depth: usize) -> Result<Value, Error> {
+    decode(reader, depth + 1)?.ok_or_else(|| invalid("unexpected end of archive"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::msgpack::{encode, Exporter};
+    use crate::export::Export;
+    use crate::UfwPolicy;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    fn archive(header: Value, records: Vec<Value>) -> Vec<u8> {
+        let mut buffer = MAGIC.to_vec();
+        encode(&header, &mut buffer);
+        records
+            .iter()
+            .for_each(|record| encode(record, &mut buffer));
+        buffer
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let second = LOG
+            .replace("02:13:52", "02:13:53")
+            .replace("BLOCK", "ALLOW");
+        let logs = [LOG, &second].map(|line| UfwLog::from_str(line).unwrap());
+        let mut output = vec![];
+        Exporter::new().export(&logs, &mut output).unwrap();
+
+        let loaded = Reader::new(output.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(loaded.len(), 2);
+        let log = &loaded[0];
+        assert_eq!((log.month, log.day, log.time.as_str()), (1, 16, "02:13:52"));
+        assert_eq!(
+            (log.hostname.as_str(), log.uptime.as_str()),
+            ("myhost", "1230.568282")
+        );
+        assert_eq!(log.policy, UfwPolicy::Block);
+        assert_eq!((log.r#in.as_str(), log.out.as_str()), ("eth0", ""));
+        assert_eq!(
+            (log.ttl, log.spt, log.dpt),
+            (Some(234), Some(46468), Some(22))
+        );
+        assert!(log.df && log.syn && !log.ack);
+        assert_eq!(log.tc, None);
+        assert_eq!(log.get_origin(), LOG);
+        assert_eq!(loaded[1].policy, UfwPolicy::Allow);
+        assert_eq!(loaded[1].get_origin(), second);
+    }
+
+    #[test]
+    fn test_unknown_and_missing_fields() {
+        let header = Value::object([
+            ("version", Value::Integer(1)),
+            ("fields", Value::Array(vec!["src".into(), "score".into()])),
+            ("comment", "added later".into()),
+        ]);
+        let record = Value::Array(vec!["1.1.1.1".into(), Value::Integer(-5)]);
+        let logs = Reader::new(archive(header, vec![record]).as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(logs[0].src, "1.1.1.1");
+        assert_eq!(logs[0].dpt, None);
+    }
+
+    #[test]
+    fn test_empty() {
+        let header = Value::object([
+            ("version", Value::Integer(1)),
+            ("fields", Value::Array(vec![])),
+        ]);
+        assert_eq!(Reader::new(archive(header, vec![]).as_slice()).count(), 0);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let header = |version| {
+            Value::object([
+                ("version", Value::Integer(version)),
+                ("fields", Value::Array(vec!["dpt".into()])),
+            ])
+        };
+
+        let mut reader = Reader::new("Jan 16 02:13:52 myhost".as_bytes());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+
+        let newer = archive(header(VERSION + 1), vec![]);
+        let error = Reader::new(newer.as_slice()).next().unwrap().unwrap_err();
+        assert!(error.to_string().contains("schema version 2"));
+
+        let record = Value::Array(vec!["port".into()]);
+        let mut bytes = archive(header(1), vec![record]);
+        assert!(Reader::new(bytes.as_slice()).next().unwrap().is_err());
+
+        // truncated record
+        bytes.pop();
+        let mut reader = Reader::new(bytes.as_slice());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+}
@@ -35,9 +35,22 @@
 //! # Ok::<(), ufwlog::error::Error>(())
 //! ```
 
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "polars")]
+pub mod dataframe;
 pub mod error;
 pub mod export;
+pub mod follow;
+pub mod generator;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod import;
+pub mod metrics;
 mod parser;
+pub mod report;
+pub mod stats;
+mod time;
 mod ufw_log;
 
 pub use ufw_log::Policy as UfwPolicy;
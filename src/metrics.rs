@@ -0,0 +1,554 @@
+//! Aggregate logs into firewall counters for Prometheus.
+//!
+//! [`Metrics`] counts logs by a set of [labels](Label), and writes them in Prometheus text
+//! exposition or OpenMetrics format, which can be saved for the textfile collector of
+//! node_exporter, or served on `/metrics` with [`serve`].
+//!
+//! ```text
+//! # HELP ufw_events_total Number of UFW log events.
+//! # TYPE ufw_events_total counter
+//! ufw_events_total{policy="BLOCK",proto="TCP",in="eth0",dpt="22"} 3
+//! ```
+//!
+//! | Metric                       | Description                                       |
+//! |------------------------------|---------------------------------------------------|
+//! | `ufw_events_total`           | number of events by labels                        |
+//! | `ufw_packet_bytes_total`     | sum of packet length (`LEN`) by labels            |
+//! | `ufw_series_overflow_total`  | events which exceed the series limit              |
+//! | `ufw_parse_errors_total`     | lines which can't be parsed                       |
+//!
+//! Default labels are `policy`, `proto`, `in` and `dpt`. To keep cardinality under control, at
+//! most 1000 label sets are tracked, events of other label sets are counted into a series whose
+//! labels are all `other`.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use std::io::BufReader;
+//! use ufwlog::metrics::{Exposition, Metrics};
+//! use ufwlog::UfwLog;
+//!
+//! let reader = BufReader::new(std::fs::File::open("./ufw.log")?);
+//! let mut metrics = Metrics::new();
+//! metrics.extend(UfwLog::from_buf_reader(reader));
+//! let mut file = std::fs::File::create("ufw.prom")?;
+//! metrics.write(&mut file, Exposition::Prometheus)?;
+//!
+//! # std::fs::remove_file("ufw.prom").unwrap();
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use crate::error::Error;
+use crate::ufw_log::UfwLog;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Read and write timeout of a connection, so an idle client can't stall the server.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Maximum bytes of request line and headers.
+const MAX_REQUEST_HEAD: u64 = 16 * 1024;
+
+/// Label value of the series which counts events exceed the series limit
+const OVERFLOW: &str = "other";
+
+/// A field of log used as metric label
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Label {
+    Hostname,
+    Policy,
+    Proto,
+    In,
+    Out,
+    /// Source IP, it may have very high cardinality.
+    Src,
+    /// Destination IP, it may have very high cardinality.
+    Dst,
+    /// Source port, it may have very high cardinality.
+    Spt,
+    Dpt,
+}
+
+impl Label {
+    const ALL: [Label; 9] = [
+        Label::Hostname,
+        Label::Policy,
+        Label::Proto,
+        Label::In,
+        Label::Out,
+        Label::Src,
+        Label::Dst,
+        Label::Spt,
+        Label::Dpt,
+    ];
+
+    /// Label name, which is the field name of [`UfwLog`].
+    pub fn name(&self) -> &'static str {
+        match self {
+            Label::Hostname => "hostname",
+            Label::Policy => "policy",
+            Label::Proto => "proto",
+            Label::In => "in",
+            Label::Out => "out",
+            Label::Src => "src",
+            Label::Dst => "dst",
+            Label::Spt => "spt",
+            Label::Dpt => "dpt",
+        }
+    }
+
+    /// Label value of log, absent port is empty.
+    pub(crate) fn value(&self, log: &UfwLog) -> String {
+        let port = |port: Option<u16>| port.map(|p| p.to_string()).unwrap_or_default();
+        match self {
+            Label::Hostname => log.hostname.clone(),
+            Label::Policy => log.policy.to_string(),
+            Label::Proto => log.proto.clone(),
+            Label::In => log.r#in.clone(),
+            Label::Out => log.out.clone(),
+            Label::Src => log.src.clone(),
+            Label::Dst => log.dst.clone(),
+            Label::Spt => port(log.spt),
+            Label::Dpt => port(log.dpt),
+        }
+    }
+}
+
+impl FromStr for Label {
+    type Err = String;
+
+    /// Parse label by its [name](Label::name), case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        Label::ALL
+            .iter()
+            .find(|l| l.name().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or(format!("Unknown label: {s}"))
+    }
+}
+
+/// Text format of metrics
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Exposition {
+    /// Prometheus text exposition format 0.0.4
+    #[default]
+    Prometheus,
+    /// OpenMetrics text format 1.0.0
+    OpenMetrics,
+}
+
+impl Exposition {
+    /// `Content-Type` of this format.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Exposition::Prometheus => "text/plain; version=0.0.4; charset=utf-8",
+            Exposition::OpenMetrics => "application/openmetrics-text; version=1.0.0; charset=utf-8",
+        }
+    }
+}
+
+/// Counters of a label set
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Counter {
+    events: u64,
+    bytes: u64,
+}
+
+/// Firewall counters aggregated from logs
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    labels: Vec<Label>,
+    max_series: usize,
+    /// Counters keyed by label values, in the order of `labels`.
+    series: BTreeMap<Vec<String>, Counter>,
+    overflow: Counter,
+    parse_errors: u64,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            labels: vec![Label::Policy, Label::Proto, Label::In, Label::Dpt],
+            max_series: 1000,
+            series: BTreeMap::new(),
+            overflow: Counter::default(),
+            parse_errors: 0,
+        }
+    }
+}
+
+impl Metrics {
+    /// New metrics with default labels and series limit
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set labels of metrics, in output order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any log is added, because the existing series can't be relabeled.
+    pub fn labels(mut self, labels: Vec<Label>) -> Self {
+        assert!(
+            self.series.is_empty() && self.overflow == Counter::default(),
+            "Labels can't be changed after logs are added"
+        );
+        self.labels = labels;
+        self
+    }
+
+    /// Set the maximum number of label sets.
+    pub fn max_series(mut self, max_series: usize) -> Self {
+        self.max_series = max_series;
+        self
+    }
+
+    /// Count a log.
+    pub fn add(&mut self, log: &UfwLog) {
+        let values = self
+            .labels
+            .iter()
+            .map(|label| label.value(log))
+            .collect::<Vec<_>>();
+        let counter = if self.series.contains_key(&values) || self.series.len() < self.max_series {
+            self.series.entry(values).or_default()
+        } else {
+            &mut self.overflow
+        };
+        counter.events += 1;
+        counter.bytes += u64::from(log.len);
+    }
+
+    /// Count a line which can't be parsed.
+    pub fn add_error(&mut self) {
+        self.parse_errors += 1;
+    }
+
+    /// Write metrics in given format.
+    pub fn write(&self, writer: &mut dyn Write, format: Exposition) -> Result<(), Error> {
+        let overflow = self.overflow != Counter::default();
+        let overflow_labels = vec![OVERFLOW.to_string(); self.labels.len()];
+        let series = self
+            .series
+            .iter()
+            .chain(overflow.then_some((&overflow_labels, &self.overflow)));
+
+        let mut events = Family::new("ufw_events", "Number of UFW log events.", format);
+        let mut bytes = Family::new(
+            "ufw_packet_bytes",
+            "Total length of packets in UFW log events.",
+            format,
+        );
+        for (values, counter) in series {
+            let labels = self
+                .labels
+                .iter()
+                .zip(values)
+                .map(|(label, value)| format!("{}=\"{}\"", label.name(), escape(value)))
+                .collect::<Vec<_>>()
+                .join(",");
+            events.sample(&labels, counter.events);
+            bytes.sample(&labels, counter.bytes);
+        }
+        let mut overflow = Family::new(
+            "ufw_series_overflow",
+            "Number of UFW log events which exceed the series limit.",
+            format,
+        );
+        overflow.sample("", self.overflow.events);
+        let mut errors = Family::new(
+            "ufw_parse_errors",
+            "Number of lines which can't be parsed as UFW log.",
+            format,
+        );
+        errors.sample("", self.parse_errors);
+
+        write!(writer, "{events}{bytes}{overflow}{errors}")?;
+        if format == Exposition::OpenMetrics {
+            writeln!(writer, "# EOF")?;
+        }
+        Ok(writer.flush()?)
+    }
+}
+
+impl<'a> Extend<&'a UfwLog> for Metrics {
+    fn extend<T: IntoIterator<Item = &'a UfwLog>>(&mut self, logs: T) {
+        logs.into_iter().for_each(|log| self.add(log));
+    }
+}
+
+/// Count logs, and count errors as parse errors.
+impl Extend<Result<UfwLog, Error>> for Metrics {
+    fn extend<T: IntoIterator<Item = Result<UfwLog, Error>>>(&mut self, logs: T) {
+        for log in logs {
+            match log {
+                Ok(log) => self.add(&log),
+                Err(_) => self.add_error(),
+            }
+        }
+    }
+}
+
+/// A counter metric family in text format
+struct Family {
+    name: &'static str,
+    help: &'static str,
+    format: Exposition,
+    samples: String,
+}
+
+impl Family {
+    fn new(name: &'static str, help: &'static str, format: Exposition) -> Self {
+        Self {
+            name,
+            help,
+            format,
+            samples: String::new(),
+        }
+    }
+
+    /// Add a sample with formatted labels.
+    fn sample(&mut self, labels: &str, value: u64) {
+        let labels = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{labels}}}")
+        };
+        self.samples += &format!("{}_total{labels} {value}\n", self.name);
+    }
+}
+
+impl Display for Family {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // the family name of counter has `_total` suffix in Prometheus format only
+        let name = match self.format {
+            Exposition::Prometheus => format!("{}_total", self.name),
+            Exposition::OpenMetrics => self.name.to_string(),
+        };
+        writeln!(f, "# HELP {name} {}", self.help)?;
+        writeln!(f, "# TYPE {name} counter")?;
+        f.write_str(&self.samples)
+    }
+}
+
+/// Escape backslash, double quote and line break in label value.
+pub(crate) fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Serve metrics on `/metrics` over HTTP, it handles requests one by one and never returns
+/// unless accepting connection fails.
+///
+/// A connection is closed if it is idle for 5 seconds, and a request whose headers exceed 16 KiB
+/// is rejected with `431`.
+///
+/// OpenMetrics format is used if the `Accept` header of request contains
+/// `application/openmetrics-text`, otherwise Prometheus text format.
+///
+/// # Examples
+///
+/// ```rust, no_run
+/// use std::net::TcpListener;
+/// use std::sync::{Arc, Mutex};
+/// use ufwlog::follow::Follower;
+/// use ufwlog::metrics::{serve, Metrics};
+///
+/// let metrics = Arc::new(Mutex::new(Metrics::new()));
+/// let listener = TcpListener::bind("127.0.0.1:9632")?;
+/// let shared = metrics.clone();
+/// std::thread::spawn(move || serve(listener, shared));
+/// for log in Follower::open("/var/log/ufw.log")? {
+///     metrics.lock().unwrap().extend([log]);
+/// }
+/// # Ok::<(), ufwlog::error::Error>(())
+/// ```
+pub fn serve(listener: TcpListener, metrics: Arc<Mutex<Metrics>>) -> Result<(), Error> {
+    for stream in listener.incoming() {
+        // a broken connection shouldn't stop the server
+        let _ = respond(stream?, &metrics);
+    }
+    Ok(())
+}
+
+/// Read a request and write response.
+fn respond(stream: std::net::TcpStream, metrics: &Mutex<Metrics>) -> Result<(), Error> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(stream.take(MAX_REQUEST_HEAD));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut format = Exposition::Prometheus;
+    let mut line = String::new();
+    let mut complete = false;
+    while reader.read_line(&mut line)? > 0 {
+        if line.trim().is_empty() {
+            complete = true;
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("accept")
+                && value.contains("application/openmetrics-text")
+            {
+                format = Exposition::OpenMetrics;
+            }
+        }
+        line.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        // the limit is reached before the end of headers
+        _ if !complete && reader.get_ref().limit() == 0 => (
+            "431 Request Header Fields Too Large",
+            "text/plain",
+            b"Request Header Fields Too Large\n".to_vec(),
+        ),
+        (Some("GET"), Some("/metrics")) => {
+            let mut body = vec![];
+            metrics
+                .lock()
+                .map_err(|e| Error::Export(e.to_string()))?
+                .write(&mut body, format)?;
+            ("200 OK", format.content_type(), body)
+        }
+        (Some("GET"), _) => ("404 Not Found", "text/plain", b"Not Found\n".to_vec()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            b"Method Not Allowed\n".to_vec(),
+        ),
+    };
+    let stream = reader.get_mut().get_mut();
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(&body)?;
+    Ok(stream.flush()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpStream;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    fn logs() -> Vec<Result<UfwLog, Error>> {
+        vec![
+            UfwLog::from_str(LOG),
+            UfwLog::from_str(LOG),
+            UfwLog::from_str(&LOG.replace("DPT=22", "DPT=80")),
+            UfwLog::from_str(&LOG.replace("[UFW BLOCK] IN=eth0 OUT=", "[UFW ALLOW] IN= OUT=lo")),
+        ]
+    }
+
+    #[test]
+    fn test_prometheus() {
+        let mut metrics = Metrics::new();
+        metrics.extend(logs());
+        metrics.add_error();
+        let mut output = vec![];
+        metrics.write(&mut output, Exposition::Prometheus).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"# HELP ufw_events_total Number of UFW log events.
+# TYPE ufw_events_total counter
+ufw_events_total{policy="ALLOW",proto="TCP",in="",dpt="22"} 1
+ufw_events_total{policy="BLOCK",proto="TCP",in="eth0",dpt="22"} 2
+ufw_events_total{policy="BLOCK",proto="TCP",in="eth0",dpt="80"} 1
+# HELP ufw_packet_bytes_total Total length of packets in UFW log events.
+# TYPE ufw_packet_bytes_total counter
+ufw_packet_bytes_total{policy="ALLOW",proto="TCP",in="",dpt="22"} 40
+ufw_packet_bytes_total{policy="BLOCK",proto="TCP",in="eth0",dpt="22"} 80
+ufw_packet_bytes_total{policy="BLOCK",proto="TCP",in="eth0",dpt="80"} 40
+# HELP ufw_series_overflow_total Number of UFW log events which exceed the series limit.
+# TYPE ufw_series_overflow_total counter
+ufw_series_overflow_total 0
+# HELP ufw_parse_errors_total Number of lines which can't be parsed as UFW log.
+# TYPE ufw_parse_errors_total counter
+ufw_parse_errors_total 1
+"#
+        );
+    }
+
+    #[test]
+    fn test_labels_and_overflow() {
+        let mut metrics = Metrics::new()
+            .labels(vec![Label::Hostname, Label::Dpt])
+            .max_series(1);
+        metrics.extend(logs());
+        let mut output = vec![];
+        metrics.write(&mut output, Exposition::OpenMetrics).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("# TYPE ufw_events counter\n"));
+        assert!(output.contains("ufw_events_total{hostname=\"myhost\",dpt=\"22\"} 3\n"));
+        assert!(output.contains("ufw_events_total{hostname=\"other\",dpt=\"other\"} 1\n"));
+        assert!(output.contains("ufw_series_overflow_total 1\n"));
+        assert!(output.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_label_from_str() {
+        assert_eq!(Label::from_str(" DPT"), Ok(Label::Dpt));
+        assert!(Label::from_str("mac").is_err());
+        assert_eq!(escape("a\"b\\c\n"), "a\\\"b\\\\c\\n");
+    }
+
+    #[test]
+    fn test_serve() {
+        let metrics = Arc::new(Mutex::new(Metrics::new()));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let shared = metrics.clone();
+        std::thread::spawn(move || serve(listener, shared));
+        metrics.lock().unwrap().extend(logs());
+
+        let get = |path: &str, accept: &str| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(
+                stream,
+                "GET {path} HTTP/1.1\r\nHost: localhost\r\nAccept: {accept}\r\n\r\n"
+            )
+            .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let response = get("/metrics", "*/*");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Type: text/plain; version=0.0.4"));
+        assert!(response.contains(
+            "ufw_events_total{policy=\"BLOCK\",proto=\"TCP\",in=\"eth0\",dpt=\"22\"} 2\n"
+        ));
+
+        let response = get("/metrics", "application/openmetrics-text;version=1.0.0");
+        assert!(response.contains("Content-Type: application/openmetrics-text"));
+        assert!(response.ends_with("# EOF\n"));
+
+        assert!(get("/", "*/*").starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        // a request with endless header is rejected, then the server still works
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let head = "GET /metrics HTTP/1.1\r\nX-Long: ";
+        // exactly the limit, unread data would make the server reset the connection
+        let mut request = head.as_bytes().to_vec();
+        request.resize(MAX_REQUEST_HEAD as usize, b'a');
+        stream.write_all(&request).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+        assert!(get("/metrics", "*/*").starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+}
@@ -88,20 +88,21 @@ pub fn to_hashmap(log: &str) -> HashMap<&str, String> {
                 // for example, it may be "kernel: [   21.050483] [UFW BLOCK]"
 
                 // event index probably in [6, 9]
-                if index <= 9 && index > 6 {
-                    // the end of event name
-                    if value.contains("]") {
-                        is_event_range = false;
-                        event_name.push(remove_brackets(value));
-                        associative.insert("event", event_name.join(" ").trim().to_string());
+                if (6..=9).contains(&index) {
+                    // the start of event name
+                    if value.starts_with("[UFW") {
+                        is_event_range = true;
                         continue;
                     }
                     if is_event_range {
-                        event_name.push(value.parse().unwrap());
-                    }
-                    // the start of event name
-                    if value.contains("[UFW") {
-                        is_event_range = true;
+                        // the end of event name
+                        if value.contains("]") {
+                            is_event_range = false;
+                            event_name.push(remove_brackets(value));
+                            associative.insert("event", event_name.join(" ").trim().to_string());
+                            continue;
+                        }
+                        event_name.push(value.to_string());
                     }
                 }
             }
@@ -169,6 +170,16 @@ mod test {
         assert_eq!(split_by_space(&some_log), vec!["Apr", "7", "20:28:26"]);
     }
 
+    #[test]
+    // policy name may contain more than one word, and uptime may be padded or not
+    fn test_multi_word_event() {
+        let padded = "Apr  7 20:28:26 host kernel: [   21.050483] [UFW AUDIT INVALID] IN=eth0 OUT=";
+        assert_eq!(to_hashmap(padded).get("event").unwrap(), "AUDIT INVALID");
+        let unpadded =
+            "Apr  7 20:28:26 host kernel: [3601090.569259] [UFW LIMIT BLOCK] IN=eth0 OUT=";
+        assert_eq!(to_hashmap(unpadded).get("event").unwrap(), "LIMIT BLOCK");
+    }
+
     #[test]
     // test split by space and should filter empty element
     fn test_remove_brackets() {
@@ -0,0 +1,165 @@
+//! Render human readable reports from logs.
+//!
+//! Reports summarize logs with [`Stats`], currently available formats are:
+//!
+//! * [`html`]: a single self-contained page with charts and a filterable table of events, which
+//!   can be sent by mail or opened offline.
+//! * [`markdown`]: tables of top talkers, top blocked ports and policy counts, which can be pasted
+//!   into tickets and chat.
+//!
+//! Both can be limited to a time window by `since` and `until`, in unix timestamp, which can be
+//! parsed from text by [`parse_time`].
+
+pub mod html;
+pub mod markdown;
+
+use crate::error::Error;
+use crate::stats::Stats;
+use crate::ufw_log::UfwLog;
+use std::collections::VecDeque;
+
+/// Parse a time in UTC into unix timestamp, the text is a unix timestamp, `YYYY-MM-DD` or
+/// `YYYY-MM-DDTHH:MM:SS` with optional `Z`.
+///
+/// Return `None` if the text is invalid, e.g. `2024-02-30`.
+///
+/// # Examples
+///
+/// ```rust
+/// use ufwlog::report::parse_time;
+///
+/// assert_eq!(parse_time("2024-01-16T02:13:52Z"), Some(1705371232));
+/// assert_eq!(parse_time("2024-01-16"), Some(1705363200));
+/// assert_eq!(parse_time("1705371232"), Some(1705371232));
+/// assert_eq!(parse_time("2024-02-30"), None);
+/// ```
+pub fn parse_time(text: &str) -> Option<i64> {
+    if let Ok(timestamp) = text.parse::<i64>() {
+        return Some(timestamp);
+    }
+    let trimmed = text.strip_suffix('Z').unwrap_or(text);
+    let (date, time) = trimmed
+        .split_once(['T', ' '])
+        .unwrap_or((trimmed, "00:00:00"));
+    let mut parts = date.split('-');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(year), Some(month), Some(day), None) => crate::time::timestamp(
+            year.parse().ok()?,
+            month.parse().ok()?,
+            day.parse().ok()?,
+            time,
+        ),
+        _ => None,
+    }
+}
+
+/// Time window of logs, `since` is inclusive and `until` is exclusive.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Window {
+    pub(crate) since: Option<i64>,
+    pub(crate) until: Option<i64>,
+    pub(crate) year: Option<i32>,
+}
+
+impl Window {
+    /// Whether the log is in the window. If any bound is set, logs with invalid date are out
+    /// of the window.
+    fn contains(&self, log: &UfwLog) -> bool {
+        if self.since.is_none() && self.until.is_none() {
+            return true;
+        }
+        match log.timestamp(self.year) {
+            Some(timestamp) => {
+                self.since.is_none_or(|since| timestamp >= since)
+                    && self.until.is_none_or(|until| timestamp < until)
+            }
+            None => false,
+        }
+    }
+}
+
+/// Count logs in the window into stats, and return the latest `keep` of them.
+///
+/// Lines which can't be parsed are counted as errors, other errors are returned.
+pub(crate) fn aggregate(
+    logs: &mut dyn Iterator<Item = Result<UfwLog, Error>>,
+    stats: &mut Stats,
+    window: Window,
+    keep: usize,
+) -> Result<Vec<UfwLog>, Error> {
+    let mut kept = VecDeque::new();
+    for log in logs {
+        match log {
+            Ok(log) if window.contains(&log) => {
+                stats.add(&log);
+                if keep > 0 {
+                    if kept.len() == keep {
+                        kept.pop_front();
+                    }
+                    kept.push_back(log);
+                }
+            }
+            Ok(_) => {}
+            Err(Error::Parse(_)) => stats.add_error(),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(kept.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("2024-01-16T02:13:52"), Some(1705371232));
+        assert_eq!(parse_time("2024-02-29 00:00:00Z"), Some(1709164800));
+        assert_eq!(parse_time("-1"), Some(-1));
+        for invalid in [
+            "2023-02-29",
+            "2024-04-31",
+            "2024-13-01",
+            "2024-01-16T24:00:00",
+            "2024-01-16T02:13",
+            "99999999999-01-01",
+            "2024-01",
+            "yesterday",
+        ] {
+            assert_eq!(parse_time(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let lines = [
+            LOG.to_string(),
+            LOG.replace("02:13:52", "02:13:53"),
+            LOG.replace("02:13:52", "02:13:54"),
+            "invalid line".to_string(),
+            LOG.replace("Jan 16", "Feb 30"),
+        ];
+        let window = Window {
+            since: Some(1_705_371_233),
+            until: Some(1_705_371_234),
+            year: Some(2024),
+        };
+        let mut stats = Stats::new();
+        let mut logs = lines.iter().map(|line| UfwLog::from_str(line));
+        let kept = aggregate(&mut logs, &mut stats, window, 10).unwrap();
+        assert_eq!(stats.total(), 1);
+        assert_eq!(stats.errors(), 1);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].time, "02:13:53");
+
+        let mut stats = Stats::new();
+        let mut logs = lines.iter().map(|line| UfwLog::from_str(line));
+        let kept = aggregate(&mut logs, &mut stats, Window::default(), 2).unwrap();
+        assert_eq!(stats.total(), 4);
+        assert_eq!(kept[0].time, "02:13:54");
+        assert_eq!(kept[1].month, 2);
+    }
+}
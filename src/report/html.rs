@@ -0,0 +1,480 @@
+//! HTML report module
+//!
+//! Render logs into a single HTML file, whose CSS and JavaScript are inlined, so it can be opened
+//! offline and attached to mail. The page contains:
+//!
+//! * counts of each [policy](crate::UfwPolicy)
+//! * top sources, destinations and destination ports
+//! * counters of each network interface
+//! * a histogram of logs over time
+//! * a table of latest events, which can be filtered by text and policy
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use std::io::BufReader;
+//! use ufwlog::report::html::Report;
+//! use ufwlog::UfwLog;
+//!
+//! let reader = BufReader::new(std::fs::File::open("./ufw.log")?);
+//! let mut file = std::fs::File::create("report.html")?;
+//! Report::new()
+//!     .title("Weekly firewall report")
+//!     .year(2024)
+//!     .render(&mut UfwLog::from_buf_reader(reader), &mut file)?;
+//!
+//! # std::fs::remove_file("report.html").unwrap();
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::{aggregate, Window};
+use crate::error::Error;
+use crate::stats::Stats;
+use crate::time::format_rfc3339;
+use crate::ufw_log::{UfwLog, MONTH};
+use std::io::Write;
+use std::time::Duration;
+
+const STYLE: &str = r##"
+body { font-family: system-ui, sans-serif; margin: 2em; color: #222; background: #fafafa; }
+h1 { margin-bottom: 0; }
+.meta { color: #666; margin-top: .3em; }
+section { margin: 1.5em 0; }
+.cards { display: flex; flex-wrap: wrap; gap: 1em; }
+.card { background: #fff; border: 1px solid #ddd; border-radius: 6px; padding: .8em 1.2em; min-width: 8em; }
+.card .value { font-size: 1.8em; font-weight: bold; }
+.card .label { color: #666; }
+.grid { display: grid; grid-template-columns: repeat(auto-fit, minmax(16em, 1fr)); gap: 1em; }
+table { border-collapse: collapse; background: #fff; width: 100%; }
+th, td { border: 1px solid #ddd; padding: .3em .6em; text-align: left; }
+th { background: #f0f0f0; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+.histogram { display: flex; align-items: flex-end; height: 10em; gap: 1px; background: #fff; border: 1px solid #ddd; padding: .5em; }
+.histogram div { flex: 1; background: #3b82f6; min-height: 1px; }
+.axis { display: flex; justify-content: space-between; color: #666; font-size: .85em; }
+.filter { margin-bottom: .5em; }
+.BLOCK, .LIMIT-BLOCK { color: #b91c1c; }
+.ALLOW { color: #15803d; }
+"##;
+
+const SCRIPT: &str = r##"
+(function () {
+  var text = document.getElementById("filter-text");
+  var policy = document.getElementById("filter-policy");
+  var count = document.getElementById("filter-count");
+  var rows = document.querySelectorAll("#events tbody tr");
+  function filter() {
+    var keyword = text.value.toLowerCase();
+    var shown = 0;
+    rows.forEach(function (row) {
+      var visible = (!policy.value || row.dataset.policy === policy.value)
+        && (!keyword || row.textContent.toLowerCase().indexOf(keyword) !== -1);
+      row.style.display = visible ? "" : "none";
+      if (visible) shown++;
+    });
+    count.textContent = shown + " shown";
+  }
+  text.addEventListener("input", filter);
+  policy.addEventListener("change", filter);
+  filter();
+})();
+"##;
+
+/// Renderer of HTML report
+///
+/// Default is the top 10 of each ranking, hourly histogram and the latest 1000 events.
+#[derive(Debug, Clone)]
+pub struct Report {
+    title: String,
+    top: usize,
+    max_events: usize,
+    bucket: Duration,
+    since: Option<i64>,
+    until: Option<i64>,
+    year: Option<i32>,
+}
+
+impl Default for Report {
+    fn default() -> Self {
+        Self {
+            title: "UFW Report".to_string(),
+            top: 10,
+            max_events: 1000,
+            bucket: Duration::from_secs(3600),
+            since: None,
+            until: None,
+            year: None,
+        }
+    }
+}
+
+impl Report {
+    /// New a report with default config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the title of page.
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    /// Set how many rows of each ranking.
+    pub fn top(mut self, top: usize) -> Self {
+        self.top = top;
+        self
+    }
+
+    /// Set the maximum events in the table, only the latest events are kept.
+    pub fn max_events(mut self, max_events: usize) -> Self {
+        self.max_events = max_events;
+        self
+    }
+
+    /// Set the width of histogram buckets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the width is less than a second.
+    pub fn bucket(mut self, bucket: Duration) -> Self {
+        assert!(bucket.as_secs() > 0, "Bucket should be at least one second");
+        self.bucket = bucket;
+        self
+    }
+
+    /// Only include logs at or after the unix timestamp.
+    pub fn since(mut self, since: i64) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Only include logs before the unix timestamp.
+    pub fn until(mut self, until: i64) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Set the year of logs, which is used to place logs in the histogram and time window. See
+    /// [`UfwLog::timestamp`] for the default.
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+
+    /// Aggregate logs from an iterator and write the report, return how many logs are counted.
+    ///
+    /// Lines which can't be parsed are counted and skipped, other errors are returned. Logs out of
+    /// the time window are skipped.
+    pub fn render(
+        &self,
+        logs: &mut dyn Iterator<Item = Result<UfwLog, Error>>,
+        writer: &mut dyn Write,
+    ) -> Result<usize, Error> {
+        let mut stats = self.stats();
+        let window = Window {
+            since: self.since,
+            until: self.until,
+            year: self.year,
+        };
+        let events = aggregate(logs, &mut stats, window, self.max_events)?;
+        self.write(&stats, &events, writer)?;
+        Ok(stats.total() as usize)
+    }
+
+    /// Make an empty [`Stats`] with the year and bucket of report.
+    pub fn stats(&self) -> Stats {
+        let stats = Stats::new().bucket(self.bucket);
+        match self.year {
+            Some(year) => stats.year(year),
+            None => stats,
+        }
+    }
+
+    /// Write the report of aggregated statistics and events of the table.
+    pub fn write(
+        &self,
+        stats: &Stats,
+        events: &[UfwLog],
+        writer: &mut dyn Write,
+    ) -> Result<(), Error> {
+        let title = escape(&self.title);
+        writeln!(writer, "<!DOCTYPE html>")?;
+        writeln!(
+            writer,
+            "<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">"
+        )?;
+        writeln!(
+            writer,
+            "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">"
+        )?;
+        writeln!(
+            writer,
+            "<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>"
+        )?;
+        writeln!(writer, "<h1>{title}</h1>")?;
+        let range = match stats.time_range() {
+            Some((first, last)) => format!(
+                "From {} to {}. ",
+                format_rfc3339(first),
+                format_rfc3339(last)
+            ),
+            None => String::new(),
+        };
+        writeln!(
+            writer,
+            "<p class=\"meta\">{range}Generated at {}.</p>",
+            format_rfc3339(crate::time::now())
+        )?;
+
+        // policy counts
+        writeln!(
+            writer,
+            "<section>\n<h2>Policies</h2>\n<div class=\"cards\">"
+        )?;
+        card(writer, "Total", stats.total(), "")?;
+        for (policy, count) in stats.policies() {
+            let policy = policy.to_string();
+            card(writer, &policy, count, &policy.replace(' ', "-"))?;
+        }
+        if stats.errors() > 0 {
+            card(writer, "Invalid lines", stats.errors(), "")?;
+        }
+        writeln!(writer, "</div>\n</section>")?;
+
+        // rankings
+        writeln!(writer, "<section class=\"grid\">")?;
+        ranking(
+            writer,
+            "Top sources",
+            "Source",
+            stats.top_sources(self.top).into_iter(),
+        )?;
+        ranking(
+            writer,
+            "Top destinations",
+            "Destination",
+            stats.top_destinations(self.top).into_iter(),
+        )?;
+        ranking(
+            writer,
+            "Top destination ports",
+            "Port",
+            stats
+                .top_ports(self.top)
+                .into_iter()
+                .map(|(port, count)| (port.to_string(), count)),
+        )?;
+        writeln!(writer, "</section>")?;
+
+        // interfaces
+        writeln!(writer, "<section>\n<h2>Interfaces</h2>\n<table>")?;
+        writeln!(
+            writer,
+            "<thead><tr><th>Interface</th><th>Incoming</th><th>Outgoing</th><th>Blocked</th></tr></thead>\n<tbody>"
+        )?;
+        for (name, counter) in stats.interfaces() {
+            writeln!(
+                writer,
+                "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+                escape(name),
+                counter.incoming,
+                counter.outgoing,
+                counter.blocked
+            )?;
+        }
+        writeln!(writer, "</tbody>\n</table>\n</section>")?;
+
+        // histogram
+        let histogram = stats.histogram();
+        let max = histogram.iter().map(|(_, count)| *count).max().unwrap_or(0);
+        writeln!(writer, "<section>\n<h2>Events over time</h2>")?;
+        writeln!(writer, "<div class=\"histogram\">")?;
+        for (start, count) in &histogram {
+            writeln!(
+                writer,
+                "<div style=\"height:{:.1}%\" title=\"{}: {count}\"></div>",
+                *count as f64 * 100.0 / max as f64,
+                format_rfc3339(*start)
+            )?;
+        }
+        writeln!(writer, "</div>")?;
+        if let (Some((first, _)), Some((last, _))) = (histogram.first(), histogram.last()) {
+            writeln!(
+                writer,
+                "<div class=\"axis\"><span>{}</span><span>{}</span></div>",
+                format_rfc3339(*first),
+                format_rfc3339(*last + stats.bucket_seconds())
+            )?;
+        }
+        writeln!(writer, "</section>")?;
+
+        // events
+        writeln!(writer, "<section>\n<h2>Events</h2>")?;
+        writeln!(
+            writer,
+            "<p class=\"meta\">Latest {} of {} events.</p>",
+            events.len(),
+            stats.total()
+        )?;
+        writeln!(writer, "<div class=\"filter\"><input id=\"filter-text\" type=\"search\" placeholder=\"Filter\"> <select id=\"filter-policy\"><option value=\"\">All policies</option>")?;
+        for (policy, _) in stats.policies() {
+            let policy = escape(&policy.to_string());
+            writeln!(writer, "<option>{policy}</option>")?;
+        }
+        writeln!(writer, "</select> <span id=\"filter-count\"></span></div>")?;
+        writeln!(writer, "<table id=\"events\">\n<thead><tr><th>Time</th><th>Host</th><th>Policy</th><th>In</th><th>Out</th><th>Proto</th><th>Source</th><th>Src port</th><th>Destination</th><th>Dst port</th><th>Length</th></tr></thead>\n<tbody>")?;
+        for log in events {
+            let policy = log.policy.to_string();
+            let port = |port: Option<u16>| port.map_or(String::new(), |p| p.to_string());
+            writeln!(
+                writer,
+                "<tr data-policy=\"{policy}\"><td>{}</td><td>{}</td><td class=\"{}\">{policy}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"num\">{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+                self.time(log),
+                escape(&log.hostname),
+                policy.replace(' ', "-"),
+                escape(&log.r#in),
+                escape(&log.out),
+                escape(&log.proto),
+                escape(&log.src),
+                port(log.spt),
+                escape(&log.dst),
+                port(log.dpt),
+                log.len
+            )?;
+        }
+        writeln!(writer, "</tbody>\n</table>\n</section>")?;
+
+        writeln!(writer, "<script>{SCRIPT}</script>\n</body>\n</html>")?;
+        Ok(writer.flush()?)
+    }
+
+    /// Time of event in RFC 3339, or as in log if the date is invalid.
+    fn time(&self, log: &UfwLog) -> String {
+        match log.timestamp(self.year) {
+            Some(timestamp) => format_rfc3339(timestamp),
+            None => escape(&format!(
+                "{} {} {}",
+                MONTH
+                    .get(usize::from(log.month).wrapping_sub(1))
+                    .unwrap_or(&""),
+                log.day,
+                log.time
+            )),
+        }
+    }
+}
+
+fn card(writer: &mut dyn Write, label: &str, value: u64, class: &str) -> Result<(), Error> {
+    writeln!(
+        writer,
+        "<div class=\"card\"><div class=\"value {class}\">{value}</div><div class=\"label\">{}</div></div>",
+        escape(label)
+    )?;
+    Ok(())
+}
+
+fn ranking<K: AsRef<str>>(
+    writer: &mut dyn Write,
+    title: &str,
+    column: &str,
+    rows: impl Iterator<Item = (K, u64)>,
+) -> Result<(), Error> {
+    writeln!(writer, "<div>\n<h2>{title}</h2>\n<table>")?;
+    writeln!(
+        writer,
+        "<thead><tr><th>{column}</th><th>Events</th></tr></thead>\n<tbody>"
+    )?;
+    for (key, count) in rows {
+        writeln!(
+            writer,
+            "<tr><td>{}</td><td class=\"num\">{count}</td></tr>",
+            escape(key.as_ref())
+        )?;
+    }
+    writeln!(writer, "</tbody>\n</table>\n</div>")?;
+    Ok(())
+}
+
+/// Escape text for HTML content and quoted attribute value.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped += "&amp;",
+            '<' => escaped += "&lt;",
+            '>' => escaped += "&gt;",
+            '"' => escaped += "&quot;",
+            '\'' => escaped += "&#39;",
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    fn render(report: &Report, lines: &[&str]) -> String {
+        let mut logs = lines.iter().map(|line| UfwLog::from_str(line));
+        let mut output = vec![];
+        report.render(&mut logs, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_render() {
+        let allow = LOG
+            .replace("BLOCK", "ALLOW")
+            .replace("02:13:52", "03:00:00");
+        let html = render(
+            &Report::new().title("<Weekly>").year(2024),
+            &[LOG, &allow, "invalid line"],
+        );
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>&lt;Weekly&gt;</title>"));
+        assert!(!html.contains("<script src") && !html.contains("<link"));
+        assert!(html.contains("From 2024-01-16T02:13:52Z to 2024-01-16T03:00:00Z."));
+        assert!(html.contains("<div class=\"value BLOCK\">1</div><div class=\"label\">BLOCK</div>"));
+        assert!(
+            html.contains("<div class=\"value \">1</div><div class=\"label\">Invalid lines</div>")
+        );
+        assert!(html.contains("<tr><td>192.168.0.8</td><td class=\"num\">2</td></tr>"));
+        assert!(html.contains("<tr><td>22/TCP</td><td class=\"num\">2</td></tr>"));
+        assert!(html.contains("<tr><td>eth0</td><td class=\"num\">2</td><td class=\"num\">0</td><td class=\"num\">1</td></tr>"));
+        assert!(
+            html.contains("<div style=\"height:100.0%\" title=\"2024-01-16T02:00:00Z: 1\"></div>")
+        );
+        assert!(html.contains("<span>2024-01-16T04:00:00Z</span>"));
+        assert!(html.contains("<option>ALLOW</option>"));
+        assert!(
+            html.contains("<tr data-policy=\"BLOCK\"><td>2024-01-16T02:13:52Z</td><td>myhost</td>")
+        );
+        assert!(html.trim_end().ends_with("</html>"));
+    }
+
+    #[test]
+    fn test_max_events() {
+        let second = LOG.replace("02:13:52", "02:13:53");
+        let html = render(&Report::new().year(2024).max_events(1), &[LOG, &second]);
+        assert!(html.contains("Latest 1 of 2 events."));
+        assert!(!html.contains("<td>2024-01-16T02:13:52Z</td>"));
+        assert!(html.contains("<td>2024-01-16T02:13:53Z</td>"));
+
+        let html = render(&Report::new().max_events(0), &[LOG]);
+        assert!(html.contains("Latest 0 of 1 events."));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape(r#"<a href="x">Tom & 'Jerry'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#39;Jerry&#39;&lt;/a&gt;"
+        );
+    }
+}
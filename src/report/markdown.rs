@@ -0,0 +1,322 @@
+//! Markdown report module
+//!
+//! Render logs into a Markdown summary, which can be pasted into incident tickets and chat:
+//!
+//! ````markdown
+//! ## UFW Summary
+//!
+//! - Window: 2024-01-16T00:00:00Z to 2024-01-17T00:00:00Z
+//! - Events: 3
+//!
+//! ### Policy counts
+//!
+//! | Policy | Events |
+//! | --- | ---: |
+//! | BLOCK | 2 |
+//! | ALLOW | 1 |
+//!
+//! ### Top talkers
+//! ...
+//! ### Top blocked ports
+//! ...
+//! ### Sample
+//!
+//! ```text
+//! Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= ...
+//! ```
+//! ````
+//!
+//! Top talkers are ranked by source address, and blocked ports include rate limiting. The sample
+//! of raw lines is only written if [`Report::sample`] is set.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use std::io::BufReader;
+//! use ufwlog::report::markdown::Report;
+//! use ufwlog::UfwLog;
+//!
+//! let reader = BufReader::new(std::fs::File::open("./ufw.log")?);
+//! Report::new()
+//!     .since(1705363200) // 2024-01-16T00:00:00Z
+//!     .until(1705449600) // 2024-01-17T00:00:00Z
+//!     .sample(5)
+//!     .year(2024)
+//!     .render(&mut UfwLog::from_buf_reader(reader), &mut std::io::stdout())?;
+//!
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use super::{aggregate, Window};
+use crate::error::Error;
+use crate::stats::Stats;
+use crate::time::format_rfc3339;
+use crate::ufw_log::UfwLog;
+use std::io::Write;
+
+/// Renderer of Markdown report
+///
+/// Default is the top 10 of each ranking without sample.
+#[derive(Debug, Clone)]
+pub struct Report {
+    title: String,
+    top: usize,
+    sample: usize,
+    since: Option<i64>,
+    until: Option<i64>,
+    year: Option<i32>,
+}
+
+impl Default for Report {
+    fn default() -> Self {
+        Self {
+            title: "UFW Summary".to_string(),
+            top: 10,
+            sample: 0,
+            since: None,
+            until: None,
+            year: None,
+        }
+    }
+}
+
+impl Report {
+    /// New a report with default config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the title, which is written as a level 2 heading.
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    /// Set how many rows of each ranking.
+    pub fn top(mut self, top: usize) -> Self {
+        self.top = top;
+        self
+    }
+
+    /// Set how many raw lines are written as sample, only the latest lines are kept.
+    pub fn sample(mut self, sample: usize) -> Self {
+        self.sample = sample;
+        self
+    }
+
+    /// Only include logs at or after the unix timestamp.
+    pub fn since(mut self, since: i64) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Only include logs before the unix timestamp.
+    pub fn until(mut self, until: i64) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Set the year of logs, which is used to check time window. See [`UfwLog::timestamp`] for
+    /// the default.
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+
+    /// Aggregate logs from an iterator and write the report, return how many logs are counted.
+    ///
+    /// Lines which can't be parsed are counted and skipped, other errors are returned. Logs out of
+    /// the time window are skipped.
+    pub fn render(
+        &self,
+        logs: &mut dyn Iterator<Item = Result<UfwLog, Error>>,
+        writer: &mut dyn Write,
+    ) -> Result<usize, Error> {
+        let mut stats = match self.year {
+            Some(year) => Stats::new().year(year),
+            None => Stats::new(),
+        };
+        let window = Window {
+            since: self.since,
+            until: self.until,
+            year: self.year,
+        };
+        let sample = aggregate(logs, &mut stats, window, self.sample)?;
+        self.write(&stats, &sample, writer)?;
+        Ok(stats.total() as usize)
+    }
+
+    /// Write the report of aggregated statistics and sample logs.
+    pub fn write(
+        &self,
+        stats: &Stats,
+        sample: &[UfwLog],
+        writer: &mut dyn Write,
+    ) -> Result<(), Error> {
+        writeln!(writer, "## {}\n", self.title)?;
+        let range = stats.time_range();
+        if self.since.is_some() || self.until.is_some() || range.is_some() {
+            let bound = |bound: Option<i64>| bound.map_or("…".to_string(), format_rfc3339);
+            writeln!(
+                writer,
+                "- Window: {} to {}",
+                bound(self.since.or(range.map(|(first, _)| first))),
+                bound(self.until.or(range.map(|(_, last)| last)))
+            )?;
+        }
+        writeln!(writer, "- Events: {}", stats.total())?;
+        if stats.errors() > 0 {
+            writeln!(writer, "- Invalid lines: {}", stats.errors())?;
+        }
+
+        writeln!(writer, "\n### Policy counts\n")?;
+        table(
+            writer,
+            "Policy",
+            stats
+                .policies()
+                .into_iter()
+                .map(|(policy, count)| (policy.to_string(), count)),
+        )?;
+        writeln!(writer, "\n### Top talkers\n")?;
+        table(writer, "Source", stats.top_sources(self.top).into_iter())?;
+        writeln!(writer, "\n### Top blocked ports\n")?;
+        table(
+            writer,
+            "Port",
+            stats
+                .top_blocked_ports(self.top)
+                .into_iter()
+                .map(|(port, count)| (port.to_string(), count)),
+        )?;
+
+        if self.sample > 0 {
+            writeln!(writer, "\n### Sample\n")?;
+            let lines = sample.iter().map(UfwLog::get_origin).collect::<Vec<_>>();
+            let fence = "`".repeat(longest_backticks(&lines).max(2) + 1);
+            writeln!(writer, "{fence}text")?;
+            for line in lines {
+                writeln!(writer, "{}", line.trim_end())?;
+            }
+            writeln!(writer, "{fence}")?;
+        }
+        Ok(writer.flush()?)
+    }
+}
+
+/// Write a table of counts, or a placeholder if there is no row.
+fn table<K: AsRef<str>>(
+    writer: &mut dyn Write,
+    column: &str,
+    rows: impl Iterator<Item = (K, u64)>,
+) -> Result<(), Error> {
+    let mut rows = rows.peekable();
+    if rows.peek().is_none() {
+        writeln!(writer, "_No events._")?;
+        return Ok(());
+    }
+    writeln!(writer, "| {column} | Events |\n| --- | ---: |")?;
+    for (key, count) in rows {
+        writeln!(writer, "| {} | {count} |", escape(key.as_ref()))?;
+    }
+    Ok(())
+}
+
+/// Escape text in table cell.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+/// Length of the longest run of backticks, the fence of code block should be longer.
+fn longest_backticks(lines: &[&str]) -> usize {
+    lines
+        .iter()
+        .flat_map(|line| line.split(|c| c != '`'))
+        .map(str::len)
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    fn render(report: &Report, lines: &[&str]) -> String {
+        let mut logs = lines.iter().map(|line| UfwLog::from_str(line));
+        let mut output = vec![];
+        report.render(&mut logs, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_render() {
+        let allow = LOG
+            .replace("BLOCK", "ALLOW")
+            .replace("02:13:52", "03:00:00")
+            .replace("SRC=192.168.0.8", "SRC=10.0.0.1");
+        let late = LOG.replace("Jan 16", "Jan 17");
+        let markdown = render(
+            &Report::new()
+                .since(1_705_363_200)
+                .until(1_705_449_600)
+                .year(2024),
+            &[LOG, LOG, &allow, &late, "invalid line"],
+        );
+        assert_eq!(
+            markdown,
+            "## UFW Summary
+
+- Window: 2024-01-16T00:00:00Z to 2024-01-17T00:00:00Z
+- Events: 3
+- Invalid lines: 1
+
+### Policy counts
+
+| Policy | Events |
+| --- | ---: |
+| BLOCK | 2 |
+| ALLOW | 1 |
+
+### Top talkers
+
+| Source | Events |
+| --- | ---: |
+| 192.168.0.8 | 2 |
+| 10.0.0.1 | 1 |
+
+### Top blocked ports
+
+| Port | Events |
+| --- | ---: |
+| 22/TCP | 2 |
+"
+        );
+    }
+
+    #[test]
+    fn test_sample() {
+        let second = LOG.replace("02:13:52", "02:13:53");
+        let markdown = render(
+            &Report::new().title("Incident").year(2024).sample(1),
+            &[LOG, &second],
+        );
+        assert!(markdown.starts_with(
+            "## Incident\n\n- Window: 2024-01-16T02:13:52Z to 2024-01-16T02:13:53Z\n- Events: 2\n"
+        ));
+        assert!(markdown.ends_with(&format!("### Sample\n\n```text\n{second}\n```\n")));
+
+        let markdown = render(&Report::new().since(0).until(1), &[LOG]);
+        assert!(markdown.contains("- Events: 0\n"));
+        assert!(markdown.contains("### Top talkers\n\n_No events._\n"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape(r"a|b\c"), r"a\|b\\c");
+        assert_eq!(longest_backticks(&["a ``` b", "````"]), 4);
+        assert_eq!(longest_backticks(&["plain"]), 0);
+    }
+}
@@ -0,0 +1,454 @@
+//! Aggregate logs into summary statistics.
+//!
+//! [`Stats`] counts logs by policy, source, destination, destination port and interface, and
+//! into a histogram of fixed time buckets. It's the base of [reports](crate::report), and can be
+//! used to build your own summary.
+//!
+//! ## Quick Start
+//!
+//! ```rust, no_run
+//! use std::io::BufReader;
+//! use ufwlog::stats::Stats;
+//! use ufwlog::UfwLog;
+//!
+//! let reader = BufReader::new(std::fs::File::open("./ufw.log")?);
+//! let mut stats = Stats::new().year(2024);
+//! stats.extend(UfwLog::from_buf_reader(reader));
+//! for (src, count) in stats.top_sources(10) {
+//!     println!("{src}\t{count}");
+//! }
+//! # Ok::<(), ufwlog::error::Error>(())
+//! ```
+
+use crate::error::Error;
+use crate::ufw_log::{Policy, UfwLog};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// Policies in the order of output
+const POLICIES: [Policy; 6] = [
+    Policy::Block,
+    Policy::Allow,
+    Policy::Audit,
+    Policy::AuditInvalid,
+    Policy::LimitBlock,
+    Policy::Unknown,
+];
+
+/// A destination port of a protocol, displayed as `22/TCP`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Port {
+    pub proto: String,
+    pub port: u16,
+}
+
+impl Display for Port {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.port, self.proto)
+    }
+}
+
+/// Counters of a network interface
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Interface {
+    /// Number of packets arrived on the interface (`IN=`)
+    pub incoming: u64,
+    /// Number of packets departed from the interface (`OUT=`)
+    pub outgoing: u64,
+    /// Number of packets of the interface which are blocked, including rate limiting
+    pub blocked: u64,
+}
+
+/// Summary statistics aggregated from logs
+#[derive(Debug, Clone)]
+pub struct Stats {
+    year: Option<i32>,
+    bucket: i64,
+    total: u64,
+    errors: u64,
+    policies: [u64; 6],
+    sources: HashMap<String, u64>,
+    destinations: HashMap<String, u64>,
+    /// Counts of each policy keyed by port
+    ports: HashMap<Port, [u64; 6]>,
+    interfaces: BTreeMap<String, Interface>,
+    /// Counts keyed by the start of bucket
+    histogram: BTreeMap<i64, u64>,
+    first: Option<i64>,
+    last: Option<i64>,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            year: None,
+            bucket: 3600,
+            total: 0,
+            errors: 0,
+            policies: [0; 6],
+            sources: HashMap::new(),
+            destinations: HashMap::new(),
+            ports: HashMap::new(),
+            interfaces: BTreeMap::new(),
+            histogram: BTreeMap::new(),
+            first: None,
+            last: None,
+        }
+    }
+}
+
+impl Stats {
+    /// New statistics with hourly histogram
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the year of logs, which is used to place logs in the histogram. See
+    /// [`UfwLog::timestamp`] for the default.
+    pub fn year(mut self, year: i32) -> Self {
+        self.year = Some(year);
+        self
+    }
+
+    /// Set the width of histogram buckets, default is an hour.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the width is less than a second, or any log is added.
+    pub fn bucket(mut self, bucket: Duration) -> Self {
+        assert!(bucket.as_secs() > 0, "Bucket should be at least one second");
+        assert!(
+            self.histogram.is_empty(),
+            "Bucket can't be changed after logs are added"
+        );
+        self.bucket = bucket.as_secs() as i64;
+        self
+    }
+
+    /// Count a log.
+    pub fn add(&mut self, log: &UfwLog) {
+        self.total += 1;
+        self.policies[policy_index(log.policy)] += 1;
+        increase(&mut self.sources, &log.src);
+        increase(&mut self.destinations, &log.dst);
+
+        if let Some(port) = log.dpt {
+            let port = Port {
+                proto: log.proto.clone(),
+                port,
+            };
+            self.ports.entry(port).or_default()[policy_index(log.policy)] += 1;
+        }
+
+        if !log.r#in.is_empty() {
+            self.interface(&log.r#in).incoming += 1;
+        }
+        if !log.out.is_empty() {
+            self.interface(&log.out).outgoing += 1;
+        }
+        if matches!(log.policy, Policy::Block | Policy::LimitBlock) {
+            if !log.r#in.is_empty() {
+                self.interface(&log.r#in).blocked += 1;
+            }
+            if !log.out.is_empty() && log.out != log.r#in {
+                self.interface(&log.out).blocked += 1;
+            }
+        }
+
+        if let Some(timestamp) = log.timestamp(self.year) {
+            let start = timestamp - timestamp.rem_euclid(self.bucket);
+            *self.histogram.entry(start).or_default() += 1;
+            self.first = Some(self.first.map_or(timestamp, |t| t.min(timestamp)));
+            self.last = Some(self.last.map_or(timestamp, |t| t.max(timestamp)));
+        }
+    }
+
+    /// Count a line which can't be parsed.
+    pub fn add_error(&mut self) {
+        self.errors += 1;
+    }
+
+    /// Number of logs
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Number of lines which can't be parsed
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    /// Number of logs of each policy, in the order of [`Policy`] variants with `Unknown` at
+    /// last. Policies without any log are skipped.
+    pub fn policies(&self) -> Vec<(Policy, u64)> {
+        POLICIES
+            .iter()
+            .zip(self.policies)
+            .filter(|(_, count)| *count > 0)
+            .map(|(policy, count)| (*policy, count))
+            .collect()
+    }
+
+    /// Number of logs of a policy
+    pub fn policy(&self, policy: Policy) -> u64 {
+        self.policies[policy_index(policy)]
+    }
+
+    /// Most frequent source addresses, at most `n`.
+    pub fn top_sources(&self, n: usize) -> Vec<(&str, u64)> {
+        top(self.sources.iter().map(|(src, count)| (src, *count)), n)
+            .into_iter()
+            .map(|(src, count)| (src.as_str(), count))
+            .collect()
+    }
+
+    /// Most frequent destination addresses, at most `n`.
+    pub fn top_destinations(&self, n: usize) -> Vec<(&str, u64)> {
+        top(
+            self.destinations.iter().map(|(dst, count)| (dst, *count)),
+            n,
+        )
+        .into_iter()
+        .map(|(dst, count)| (dst.as_str(), count))
+        .collect()
+    }
+
+    /// Most frequent destination ports, at most `n`.
+    pub fn top_ports(&self, n: usize) -> Vec<(&Port, u64)> {
+        top(
+            self.ports
+                .iter()
+                .map(|(port, counts)| (port, counts.iter().sum())),
+            n,
+        )
+    }
+
+    /// Most frequent destination ports of blocked logs, including rate limiting, at most `n`.
+    pub fn top_blocked_ports(&self, n: usize) -> Vec<(&Port, u64)> {
+        let blocked = [
+            policy_index(Policy::Block),
+            policy_index(Policy::LimitBlock),
+        ];
+        top(
+            self.ports
+                .iter()
+                .map(|(port, counts)| (port, blocked.iter().map(|i| counts[*i]).sum()))
+                .filter(|(_, count)| *count > 0),
+            n,
+        )
+    }
+
+    /// Number of logs of a destination port and policy, which can make a pivot table with
+    /// [`top_ports`](Self::top_ports) and [`policies`](Self::policies).
+    pub fn port_policy(&self, port: &Port, policy: Policy) -> u64 {
+        self.ports
+            .get(port)
+            .map_or(0, |counts| counts[policy_index(policy)])
+    }
+
+    /// Counters of each interface, ordered by name.
+    pub fn interfaces(&self) -> Vec<(&str, Interface)> {
+        self.interfaces
+            .iter()
+            .map(|(name, counter)| (name.as_str(), *counter))
+            .collect()
+    }
+
+    /// Number of logs in each bucket, keyed by the unix timestamp of bucket start. Buckets
+    /// between the first and last log are included even if they are empty.
+    pub fn histogram(&self) -> Vec<(i64, u64)> {
+        let (first, last) = match (
+            self.histogram.keys().next(),
+            self.histogram.keys().next_back(),
+        ) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return vec![],
+        };
+        (0..=(last - first) / self.bucket)
+            .map(|i| first + i * self.bucket)
+            .map(|start| (start, self.histogram.get(&start).copied().unwrap_or(0)))
+            .collect()
+    }
+
+    /// Width of histogram buckets in seconds
+    pub fn bucket_seconds(&self) -> i64 {
+        self.bucket
+    }
+
+    /// Unix timestamps of the earliest and latest logs, `None` if no log has valid time.
+    pub fn time_range(&self) -> Option<(i64, i64)> {
+        self.first.zip(self.last)
+    }
+
+    fn interface(&mut self, name: &str) -> &mut Interface {
+        if !self.interfaces.contains_key(name) {
+            self.interfaces
+                .insert(name.to_string(), Interface::default());
+        }
+        self.interfaces.get_mut(name).unwrap()
+    }
+}
+
+impl<'a> Extend<&'a UfwLog> for Stats {
+    fn extend<T: IntoIterator<Item = &'a UfwLog>>(&mut self, logs: T) {
+        logs.into_iter().for_each(|log| self.add(log));
+    }
+}
+
+/// Count logs, and count errors as lines which can't be parsed.
+impl Extend<Result<UfwLog, Error>> for Stats {
+    fn extend<T: IntoIterator<Item = Result<UfwLog, Error>>>(&mut self, logs: T) {
+        for log in logs {
+            match log {
+                Ok(log) => self.add(&log),
+                Err(_) => self.add_error(),
+            }
+        }
+    }
+}
+
+fn policy_index(policy: Policy) -> usize {
+    POLICIES.iter().position(|p| *p == policy).unwrap()
+}
+
+fn increase(counter: &mut HashMap<String, u64>, key: &str) {
+    if key.is_empty() {
+        return;
+    }
+    match counter.get_mut(key) {
+        Some(count) => *count += 1,
+        None => {
+            counter.insert(key.to_string(), 1);
+        }
+    }
+}
+
+/// At most `n` keys with the largest counts, ties are ordered by key.
+fn top<K: Ord>(counter: impl Iterator<Item = (K, u64)>, n: usize) -> Vec<(K, u64)> {
+    let mut entries = counter.collect::<Vec<_>>();
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    entries.truncate(n);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const LOG: &str = "Jan 16 02:13:52 myhost kernel: [ 1230.568282] [UFW BLOCK] IN=eth0 OUT= MAC=00:ff:ff:ff:ff:ff:cc:ff:20:19:01:01:08:00 SRC=192.168.0.8 DST=127.0.111.111 LEN=40 TOS=0x00 PREC=0x00 TTL=234 ID=17062 DF PROTO=TCP SPT=46468 DPT=22 WINDOW=1024 RES=0x00 SYN URGP=0";
+
+    fn logs() -> Vec<UfwLog> {
+        let allow = LOG
+            .replace("BLOCK", "ALLOW")
+            .replace("02:13:52", "04:59:59")
+            .replace("SRC=192.168.0.8", "SRC=10.0.0.1")
+            .replace("DPT=22", "DPT=443");
+        let outgoing = LOG
+            .replace("IN=eth0 OUT=", "IN= OUT=wg0")
+            .replace("DPT=22", "DPT=53")
+            .replace("PROTO=TCP", "PROTO=UDP");
+        [LOG, LOG, &allow, &outgoing]
+            .iter()
+            .map(|line| UfwLog::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_counts() {
+        let mut stats = Stats::new().year(2024);
+        stats.extend(&logs());
+        stats.extend([UfwLog::from_str("not a log")]);
+
+        assert_eq!(stats.total(), 4);
+        assert_eq!(stats.errors(), 1);
+        assert_eq!(
+            stats.policies(),
+            vec![(Policy::Block, 3), (Policy::Allow, 1)]
+        );
+        assert_eq!(stats.policy(Policy::Audit), 0);
+        assert_eq!(
+            stats.top_sources(10),
+            vec![("192.168.0.8", 3), ("10.0.0.1", 1)]
+        );
+        assert_eq!(stats.top_destinations(1), vec![("127.0.111.111", 4)]);
+
+        let ports = stats
+            .top_ports(10)
+            .into_iter()
+            .map(|(port, count)| (port.to_string(), count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ports,
+            vec![
+                ("22/TCP".to_string(), 2),
+                ("443/TCP".to_string(), 1),
+                ("53/UDP".to_string(), 1)
+            ]
+        );
+        let blocked = stats
+            .top_blocked_ports(10)
+            .into_iter()
+            .map(|(port, count)| (port.to_string(), count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            blocked,
+            vec![("22/TCP".to_string(), 2), ("53/UDP".to_string(), 1)]
+        );
+        let ssh = Port {
+            proto: "TCP".to_string(),
+            port: 22,
+        };
+        assert_eq!(stats.port_policy(&ssh, Policy::Block), 2);
+        assert_eq!(stats.port_policy(&ssh, Policy::Allow), 0);
+
+        assert_eq!(
+            stats.interfaces(),
+            vec![
+                (
+                    "eth0",
+                    Interface {
+                        incoming: 3,
+                        outgoing: 0,
+                        blocked: 2
+                    }
+                ),
+                (
+                    "wg0",
+                    Interface {
+                        incoming: 0,
+                        outgoing: 1,
+                        blocked: 1
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_histogram() {
+        let mut stats = Stats::new().year(2024);
+        assert_eq!(stats.histogram(), vec![]);
+        assert_eq!(stats.time_range(), None);
+
+        stats.extend(&logs());
+        // 2024-01-16T02:00:00Z
+        let start = 1_705_370_400;
+        assert_eq!(
+            stats.histogram(),
+            vec![(start, 3), (start + 3600, 0), (start + 7200, 1)]
+        );
+        assert_eq!(stats.time_range(), Some((1_705_371_232, 1_705_381_199)));
+
+        let mut stats = Stats::new().year(2024).bucket(Duration::from_secs(86400));
+        stats.extend(&logs());
+        assert_eq!(stats.histogram(), vec![(1_705_363_200, 4)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_bucket() {
+        Stats::new().bucket(Duration::from_millis(10));
+    }
+}
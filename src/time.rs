@@ -0,0 +1,209 @@
+//! Date and time helpers.
+//!
+//! ufw log doesn't contain year and time zone, so the date is completed with a given or inferred
+//! year and treated as UTC.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds of a day
+const DAY: i64 = 86400;
+
+/// Days since 1970-01-01 of given date in proleptic Gregorian calendar.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+pub(crate) fn days_from_civil(year: i32, month: u8, day: u8) -> i64 {
+    let year = year as i64 - if month <= 2 { 1 } else { 0 };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let yoe = year - era * 400;
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Year, month and day of given days since 1970-01-01.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+pub(crate) fn civil_from_days(days: i64) -> (i32, u8, u8) {
+    let z = days + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = (yoe + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+    (year, month, day)
+}
+
+/// Current unix timestamp in seconds.
+pub(crate) fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Guess the year of a log record: the current year, or last year if the date would be in the
+/// future (e.g. read December log in January).
+pub(crate) fn infer_year(month: u8, day: u8) -> i32 {
+    let today = now().div_euclid(DAY);
+    let (year, _, _) = civil_from_days(today);
+    // allow one day ahead because of time zone
+    if days_from_civil(year, month, day) > today + 1 {
+        year - 1
+    } else {
+        year
+    }
+}
+
+/// Unix timestamp in seconds of given date and `HH:MM:SS` time.
+///
+/// Return `None` if the date or time is invalid.
+pub(crate) fn timestamp(year: i32, month: u8, day: u8, time: &str) -> Option<i64> {
+    if !(1..=12).contains(&month) || day == 0 || day > days_of_month(year, month) {
+        return None;
+    }
+    let mut parts = time.split(':').map(|p| p.parse::<i64>().ok());
+    let (hour, minute, second) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(Some(h)), Some(Some(m)), Some(Some(s)), None)
+            if (0..24).contains(&h) && (0..60).contains(&m) && (0..61).contains(&s) =>
+        {
+            (h, m, s)
+        }
+        _ => return None,
+    };
+    Some(days_from_civil(year, month, day) * DAY + hour * 3600 + minute * 60 + second)
+}
+
+/// Format unix timestamp in seconds as RFC 3339 in UTC, e.g. `2024-01-16T02:13:52Z`.
+pub(crate) fn format_rfc3339(timestamp: i64) -> String {
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(DAY));
+    let seconds = timestamp.rem_euclid(DAY);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Format unix timestamp in seconds with a pattern in UTC, e.g. `ufw-%Y.%m.%d` to
+/// `ufw-2024.01.16`.
+///
+/// Supported specifiers are `%Y`, `%m`, `%d`, `%H` and `%%`, others are kept as is.
+#[cfg(feature = "http")]
+pub(crate) fn format_pattern(pattern: &str, timestamp: i64) -> String {
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(DAY));
+    let hour = timestamp.rem_euclid(DAY) / 3600;
+    let mut output = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => output += &format!("{year:04}"),
+            Some('m') => output += &format!("{month:02}"),
+            Some('d') => output += &format!("{day:02}"),
+            Some('H') => output += &format!("{hour:02}"),
+            Some('%') => output.push('%'),
+            Some(other) => {
+                output.push('%');
+                output.push(other);
+            }
+            None => output.push('%'),
+        }
+    }
+    output
+}
+
+fn days_of_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Convert unix timestamp in seconds into nanoseconds, `None` if it overflows `i64`, which is
+/// after year 2262 or before 1677.
+pub(crate) fn nanos(second: i64) -> Option<i64> {
+    second.checked_mul(1_000_000_000)
+}
+
+/// Sequence of consecutive logs in the same second.
+///
+/// ufw log only has second precision, so the sequence is added to timestamp as nanoseconds to
+/// keep logs of the same second distinct and in order.
+#[derive(Debug, Default)]
+pub(crate) struct Sequence {
+    second: Option<i64>,
+    count: i64,
+}
+
+impl Sequence {
+    /// Get the timestamp in nanoseconds of the next log at given unix timestamp, `None` if it
+    /// overflows `i64`.
+    pub(crate) fn next(&mut self, second: i64) -> Option<i64> {
+        if self.second == Some(second) {
+            self.count += 1;
+        } else {
+            self.second = Some(second);
+            self.count = 0;
+        }
+        nanos(second)?.checked_add(self.count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_days_from_civil() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+        assert_eq!(civil_from_days(11017), (2000, 3, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(2024, 1, 16, "02:13:52"), Some(1705371232));
+        assert_eq!(timestamp(2024, 2, 29, "00:00:00"), Some(1709164800));
+        assert_eq!(timestamp(2023, 2, 29, "00:00:00"), None);
+        assert_eq!(timestamp(2024, 0, 1, "00:00:00"), None);
+        assert_eq!(timestamp(2024, 1, 1, "24:00:00"), None);
+        assert_eq!(timestamp(2024, 1, 1, "00:00"), None);
+    }
+
+    #[test]
+    fn test_format_rfc3339() {
+        assert_eq!(format_rfc3339(1705371232), "2024-01-16T02:13:52Z");
+        assert_eq!(format_rfc3339(-1), "1969-12-31T23:59:59Z");
+    }
+
+    #[test]
+    #[cfg(feature = "http")]
+    fn test_format_pattern() {
+        assert_eq!(format_pattern("ufw-%Y.%m.%d", 1705371232), "ufw-2024.01.16");
+        assert_eq!(format_pattern("%H%%%q%", 1705371232), "02%%q%");
+    }
+
+    #[test]
+    fn test_sequence() {
+        let mut sequence = Sequence::default();
+        assert_eq!(sequence.next(1), Some(1_000_000_000));
+        assert_eq!(sequence.next(1), Some(1_000_000_001));
+        assert_eq!(sequence.next(2), Some(2_000_000_000));
+        assert_eq!(sequence.next(1), Some(1_000_000_000));
+        // 2263-01-01
+        let second = timestamp(2263, 1, 1, "00:00:00").unwrap();
+        assert_eq!(nanos(second), None);
+        assert_eq!(sequence.next(second), None);
+    }
+}
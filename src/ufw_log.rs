@@ -557,6 +557,30 @@ impl UfwLog {
         })
     }
 
+    /// Get unix timestamp (in seconds) of the log.
+    ///
+    /// ufw log doesn't record year and time zone, so the time is treated as UTC and `year` is
+    /// used to complete the date. If `year` is `None`, it will be the current year, or last year
+    /// if the date would be in the future.
+    ///
+    /// Return `None` if the date or time is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::str::FromStr;
+    /// use ufwlog::UfwLog;
+    ///
+    /// let log_str = "Jan 16 02:13:52 103213020 kernel: [3601090.569259] [UFW AUDIT] IN= OUT=lo SRC=127.0.0.1 DST=127.0.0.1 LEN=84 TOS=0x00 PREC=0x00 TTL=64 ID=33539 DF PROTO=ICMP TYPE=8 CODE=0 ID=10289 SEQ=1";
+    /// let log = UfwLog::from_str(log_str).unwrap();
+    ///
+    /// assert_eq!(log.timestamp(Some(2024)), Some(1705371232));
+    /// ```
+    pub fn timestamp(&self, year: Option<i32>) -> Option<i64> {
+        let year = year.unwrap_or_else(|| crate::time::infer_year(self.month, self.day));
+        crate::time::timestamp(year, self.month, self.day, &self.time)
+    }
+
     /// Get origin content of log
     ///
     /// # Examples
@@ -606,7 +630,7 @@ impl FromStr for UfwLog {
 /// The ufw policy list.
 ///
 /// Community may call it "action" or "event", but we use "policy", as variable named in [source code](https://launchpad.net/ufw).
-#[derive(Debug, Default, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub enum Policy {
     /// Unknown policy.
     ///
@@ -662,7 +686,7 @@ impl Display for Policy {
     }
 }
 
-const MONTH: [&str; 12] = [
+pub(crate) const MONTH: [&str; 12] = [
     "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
 ];
 